@@ -0,0 +1,102 @@
+// 样式计算的性能测试：生成一个较大的文档和样式表，比较依次遍历和并行遍历的耗时
+// 运行方式：cargo run --release -- --bench-style
+use std::time::{Duration, Instant};
+
+use crate::{css, html::types::{elem, text, AttrMap, Node}, style::{self, StyledNode, Traversal}};
+
+// 每种遍历方式运行的次数，取最快的一次
+const RUNS: usize = 5;
+
+pub fn style() {
+    let rules = 2000;
+    let root = document(200, 50);
+    let stylesheets = vec![css::user_agent::stylesheet(), css::parser::parse(stylesheet(rules))];
+    let env = css::media::MediaEnvironment::new(800.0, 600.0);
+
+    let run = |traversal| {
+        let mut best = Duration::MAX;
+        let mut result = None;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let styled = style::style_tree_with_stats(&root, &stylesheets, &env, traversal);
+            best = best.min(start.elapsed());
+            result = Some(styled);
+        }
+        (best, result.unwrap())
+    };
+    let (sequential_time, (sequential, sequential_stats)) = run(Traversal::Sequential);
+    let (parallel_time, (parallel, parallel_stats)) = run(Traversal::Parallel);
+
+    assert!(same_styles(&sequential, &parallel), "parallel traversal produced different styles");
+    assert_eq!(sequential_stats, parallel_stats);
+
+    println!("nodes: {}, rules: {}, threads: {}", count(&sequential), rules, rayon::current_num_threads());
+    println!("style sharing: {} hits, {} misses", sequential_stats.hits, sequential_stats.misses);
+    println!("sequential: {:?}", sequential_time);
+    println!("parallel:   {:?}", parallel_time);
+    println!("speedup:    {:.2}x", sequential_time.as_secs_f64() / parallel_time.as_secs_f64());
+    // 只有一个线程时两种方式实际上都是依次计算，并行遍历只会多出调度的开销
+    if rayon::current_num_threads() == 1 {
+        println!("note: only one thread is available, run on a multi-core machine to see the parallel speedup");
+    }
+}
+
+// sections 个 section，每个包含 items 个列表项，一部分列表项有 ID 或者不同的类名，无法共享样式
+fn document(sections: usize, items: usize) -> Node {
+    let attrs = |pairs: &[(&str, String)]| -> AttrMap {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.clone())).collect()
+    };
+    let sections = (0..sections).map(|s| {
+        let items = (0..items).map(|i| {
+            let mut pairs = vec![("class", format!("item c{} c{}", s % 100, i % 7))];
+            if i % 5 == 0 {
+                pairs.push(("id", format!("item-{}-{}", s, i)));
+            }
+            elem("li".to_string(), attrs(&pairs), vec![text(format!("item {}", i))])
+        }).collect();
+        let list = elem("ul".to_string(), attrs(&[("class", "list".to_string())]), items);
+        elem("section".to_string(), attrs(&[("class", format!("section c{}", s % 100))]), vec![list])
+    }).collect();
+    let body = elem("body".to_string(), AttrMap::new(), sections);
+    elem("html".to_string(), AttrMap::new(), vec![body])
+}
+
+fn stylesheet(rules: usize) -> String {
+    (0..rules).map(|i| match i % 4 {
+        0 => format!(".c{} {{ margin: {}px; color: #{:06x}; }}\n", i % 100, i % 13, i * 7919 % 0xffffff),
+        1 => format!("li.c{} {{ padding: {}em 2px; --gap: {}px; }}\n", i % 7, i % 3, i % 11),
+        2 => format!("#item-{}-{} {{ width: calc(50% - var(--gap, 4px)); }}\n", i % 200, i % 50),
+        _ => format!("section.c{} {{ border: {}px solid #333333; font-size: {}px; }}\n", i % 100, i % 4, 10 + i % 9),
+    }).collect()
+}
+
+fn same_styles(a: &StyledNode, b: &StyledNode) -> bool {
+    std::ptr::eq(a.node, b.node)
+        && a.computed_values == b.computed_values
+        && a.style == b.style
+        && a.children.len() == b.children.len()
+        && a.children.iter().zip(&b.children).all(|(a, b)| same_styles(a, b))
+}
+
+fn count(node: &StyledNode) -> usize {
+    1 + node.children.iter().map(count).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{css, style::{self, Traversal}};
+
+    use super::{document, same_styles, stylesheet};
+
+    #[test]
+    fn parallel_and_sequential_styles_are_identical() {
+        let root = document(20, 12);
+        let stylesheets = vec![css::user_agent::stylesheet(), css::parser::parse(stylesheet(200))];
+        let env = css::media::MediaEnvironment::new(800.0, 600.0);
+        let (sequential, sequential_stats) = style::style_tree_with_stats(&root, &stylesheets, &env, Traversal::Sequential);
+        let (parallel, parallel_stats) = style::style_tree_with_stats(&root, &stylesheets, &env, Traversal::Parallel);
+        assert!(same_styles(&sequential, &parallel));
+        assert_eq!(sequential_stats, parallel_stats);
+        assert!(sequential_stats.hits > 0);
+    }
+}
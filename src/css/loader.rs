@@ -0,0 +1,93 @@
+// 从文件加载样式表，并递归加载其中 @import 的样式表
+use std::{fs, path::{Component, Path, PathBuf}};
+
+use super::{parser, types::{CssRule, FontSource, Origin, Stylesheet}};
+
+// 读取样式表内容的方式，例如从文件系统、内存或者网络读取
+pub trait ResourceLoader {
+    fn load(&self, path: &Path) -> Result<String, String>;
+}
+
+// 从文件系统读取
+pub struct FileLoader;
+
+impl ResourceLoader for FileLoader {
+    fn load(&self, path: &Path) -> Result<String, String> {
+        fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// 加载 path 处的样式表，@import 的地址相对于导入它的文件
+// 被导入的规则放在 @import 规则中，保留它的媒体查询；加载失败或者形成循环的 @import 会被忽略
+// @font-face 中字体文件的地址同样相对于所在的文件，加载后改写为相对于当前目录的路径
+pub fn load_stylesheet(path: &Path, origin: Origin, loader: &dyn ResourceLoader) -> Result<Stylesheet, String> {
+    let path = normalize(path);
+    let source = loader.load(&path)?;
+    let mut rules = parser::parse_with_origin(source, origin).rules;
+    resolve_font_sources(&mut rules, &path);
+    resolve_imports(&mut rules, &mut vec![path], loader);
+    Ok(Stylesheet { rules, origin })
+}
+
+// stack 为正在加载的文件，最后一个是 rules 所在的文件
+fn resolve_imports(rules: &mut [CssRule], stack: &mut Vec<PathBuf>, loader: &dyn ResourceLoader) {
+    for rule in rules.iter_mut() {
+        let CssRule::Import(import) = rule else { continue };
+        let path = resolve(stack.last().unwrap(), &import.url);
+        if stack.contains(&path) {
+            eprintln!("warning: circular @import of {} is ignored", path.display());
+            continue;
+        }
+        match loader.load(&path) {
+            Ok(source) => {
+                // 被导入的样式表和导入它的样式表来源相同，来源在这里不起作用
+                import.rules = parser::parse(source).rules;
+                resolve_font_sources(&mut import.rules, &path);
+                stack.push(path);
+                resolve_imports(&mut import.rules, stack, loader);
+                stack.pop();
+            }
+            Err(e) => eprintln!("warning: failed to @import {}", e)
+        }
+    }
+}
+
+// base 为 rules 所在的文件
+fn resolve_font_sources(rules: &mut [CssRule], base: &Path) {
+    for rule in rules.iter_mut() {
+        match rule {
+            CssRule::FontFace(font_face) => {
+                for source in &mut font_face.sources {
+                    if let FontSource::Url(url, _) = source {
+                        *url = resolve(base, url).to_string_lossy().into_owned();
+                    }
+                }
+            }
+            CssRule::Media(media) => resolve_font_sources(&mut media.rules, base),
+            CssRule::Supports(supports) => resolve_font_sources(&mut supports.rules, base),
+            // 被导入的样式表在加载时处理
+            CssRule::Style(_) | CssRule::Import(_) => {}
+        }
+    }
+}
+
+// 相对地址基于导入它的文件所在的目录
+fn resolve(base: &Path, url: &str) -> PathBuf {
+    let dir = base.parent().unwrap_or_else(|| Path::new(""));
+    normalize(&dir.join(url))
+}
+
+// 去掉路径中的 `.` 和 `..`，使同一个文件总是对应同一个路径，用来检测循环
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(result.components().next_back(), Some(Component::Normal(_))) => {
+                result.pop();
+            }
+            _ => result.push(component)
+        }
+    }
+    result
+}
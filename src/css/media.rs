@@ -0,0 +1,204 @@
+// 媒体查询，例如：`@media screen and (min-width: 600px)`，以及用来计算媒体查询的环境
+use super::types::{Unit, Value};
+
+// 逗号分隔的多个媒体查询，任意一个匹配即可，空列表表示匹配所有媒体
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQueryList(pub Vec<MediaQuery>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaQuery {
+    pub qualifier: Option<MediaQualifier>,
+    pub media_type: MediaType,
+    pub condition: Option<MediaCondition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaQualifier {
+    Not,
+    Only,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaCondition {
+    Feature(MediaFeature),
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaFeature {
+    // 布尔形式，例如：`(color)`
+    Boolean(String),
+    // 比较形式，例如：`(width >= 600px)`，`(min-width: 600px)` 也会转换成这种形式，特性名称总在左边
+    Range(String, Comparison, Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+// 计算媒体查询时使用的环境，一般由可视区域的尺寸创建
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaEnvironment {
+    pub media_type: MediaType,
+    pub width: f32,
+    pub height: f32,
+    // 每个 css 像素对应的设备像素数（dppx）
+    pub resolution: f32,
+    pub prefers_color_scheme: ColorScheme,
+}
+
+// 媒体查询中的 em 相对于初始字体大小
+const INITIAL_FONT_SIZE: f32 = 16.0;
+
+impl MediaEnvironment {
+    pub fn new(width: f32, height: f32) -> MediaEnvironment {
+        MediaEnvironment {
+            media_type: MediaType::Screen,
+            width,
+            height,
+            resolution: 1.0,
+            prefers_color_scheme: ColorScheme::Light,
+        }
+    }
+}
+
+impl MediaQueryList {
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        self.0.is_empty() || self.0.iter().any(|query| query.matches(env))
+    }
+}
+
+impl MediaQuery {
+    // 不合法的媒体查询等同于 `not all`
+    pub fn not_all() -> MediaQuery {
+        MediaQuery { qualifier: Some(MediaQualifier::Not), media_type: MediaType::All, condition: None }
+    }
+
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        let type_matches = match self.media_type {
+            MediaType::All => true,
+            ref media_type => *media_type == env.media_type
+        };
+        let matches = type_matches && self.condition.as_ref().is_none_or(|condition| condition.matches(env));
+        match self.qualifier {
+            Some(MediaQualifier::Not) => !matches,
+            _ => matches
+        }
+    }
+}
+
+impl MediaCondition {
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        match self {
+            MediaCondition::Feature(feature) => feature.matches(env),
+            MediaCondition::Not(condition) => !condition.matches(env),
+            MediaCondition::And(conditions) => conditions.iter().all(|c| c.matches(env)),
+            MediaCondition::Or(conditions) => conditions.iter().any(|c| c.matches(env)),
+        }
+    }
+}
+
+impl MediaFeature {
+    // 不支持的特性永远不匹配
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        match self {
+            MediaFeature::Boolean(name) => match &**name {
+                "width" => env.width != 0.0,
+                "height" => env.height != 0.0,
+                "resolution" => env.resolution != 0.0,
+                "orientation" | "prefers-color-scheme" => true,
+                _ => false
+            },
+            MediaFeature::Range(name, comparison, value) => match &**name {
+                "width" => length(value).is_some_and(|v| comparison.compare(env.width, v)),
+                "height" => length(value).is_some_and(|v| comparison.compare(env.height, v)),
+                "resolution" => resolution(value).is_some_and(|v| comparison.compare(env.resolution, v)),
+                "orientation" => {
+                    // 高度大于等于宽度时是竖屏
+                    let orientation = if env.height >= env.width { "portrait" } else { "landscape" };
+                    *comparison == Comparison::Eq && keyword(value) == Some(orientation)
+                }
+                "prefers-color-scheme" => {
+                    let scheme = match env.prefers_color_scheme {
+                        ColorScheme::Light => "light",
+                        ColorScheme::Dark => "dark",
+                    };
+                    *comparison == Comparison::Eq && keyword(value) == Some(scheme)
+                }
+                _ => false
+            }
+        }
+    }
+}
+
+impl Comparison {
+    // 比较环境中的值和查询中的值
+    fn compare(self, actual: f32, expected: f32) -> bool {
+        match self {
+            Comparison::Lt => actual < expected,
+            Comparison::Le => actual <= expected,
+            Comparison::Eq => actual == expected,
+            Comparison::Ge => actual >= expected,
+            Comparison::Gt => actual > expected,
+        }
+    }
+
+    // 交换比较的两边，例如 `600px <= width` 等同于 `width >= 600px`
+    pub fn flip(self) -> Comparison {
+        match self {
+            Comparison::Lt => Comparison::Gt,
+            Comparison::Le => Comparison::Ge,
+            Comparison::Eq => Comparison::Eq,
+            Comparison::Ge => Comparison::Le,
+            Comparison::Gt => Comparison::Lt,
+        }
+    }
+}
+
+fn keyword(value: &Value) -> Option<&str> {
+    match value {
+        Value::Keyword(k) => Some(k),
+        _ => None
+    }
+}
+
+fn length(value: &Value) -> Option<f32> {
+    match *value {
+        Value::Length(v, Unit::Px) => Some(v),
+        Value::Length(v, Unit::Em) | Value::Length(v, Unit::Rem) => Some(v * INITIAL_FONT_SIZE),
+        Value::Length(v, Unit::Pt) => Some(v * 4.0 / 3.0),
+        Value::Number(0.0) => Some(0.0),
+        _ => None
+    }
+}
+
+// 分辨率统一转换为 dppx
+fn resolution(value: &Value) -> Option<f32> {
+    match *value {
+        Value::Length(v, Unit::Dppx) => Some(v),
+        Value::Length(v, Unit::Dpi) => Some(v / 96.0),
+        Value::Length(v, Unit::Dpcm) => Some(v * 2.54 / 96.0),
+        _ => None
+    }
+}
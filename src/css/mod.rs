@@ -9,4 +9,11 @@
  * ]
  */
 pub mod types;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod media;
+pub mod supports;
+pub mod serializer;
+pub mod loader;
+pub mod properties;
+pub mod shorthand;
+pub mod user_agent;
\ No newline at end of file
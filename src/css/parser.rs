@@ -1,18 +1,31 @@
 // 简单的选择器：一个标签名称、一个 ID、任意数量的类名称，或者以上的某种组合，且支持 * 选择器。
 use crate::parser::Parser;
 
-use super::types;
+use super::{types, shorthand, media, properties, supports::SupportsCondition};
 
 pub fn parse(source: String) -> types::Stylesheet {
+    parse_with_origin(source, types::Origin::Author)
+}
+
+// 解析单个属性值，例如属性的初始值 `medium`，值不合法时返回 None
+pub fn parse_value(source: String) -> Option<types::Value> {
+    let mut parser = CSSParser { pos: 0, input: source + ";" };
+    parser.consume_whitespace();
+    let value = parser.parse_value().ok()?;
+    // 值的后面不能有多余的内容
+    (parser.pos + 1 == parser.input.len()).then_some(value)
+}
+
+// 解析指定来源的样式表，例如浏览器默认样式表、用户样式表
+pub fn parse_with_origin(source: String, origin: types::Origin) -> types::Stylesheet {
     let mut parser = CSSParser { pos: 0, input: source };
-    types::Stylesheet { rules: parser.parse_rules() }
+    types::Stylesheet { rules: parser.parse_rules(), origin }
 }
 
+type ParseResult<T> = Result<T, String>;
+
 fn valid_identifier_char(c: char) -> bool {
-    match c {
-        'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => true,
-        _ => false,
-    }
+    matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_')
 }
 
 struct CSSParser {
@@ -38,17 +51,166 @@ impl Parser for CSSParser {
 }
 
 impl CSSParser {
-    // 解析一组 css 规则
-    fn parse_rules(&mut self) -> Vec<types::Rule> {
+    // 解析一组 css 规则，直到文件结束或者遇到条件规则块结尾的 `}`
+    fn parse_rules(&mut self) -> Vec<types::CssRule> {
         let mut rules = Vec::new();
         loop {
             self.consume_whitespace();
-            if self.eof() { break; }
-            rules.push(self.parse_rule());
+            if self.eof() || self.next_char() == '}' { break; }
+            if self.next_char() == '@' {
+                match self.parse_at_rule() {
+                    // @import 只能出现在其它规则之前，后面的 @import 会被忽略
+                    Some(types::CssRule::Import(import)) if rules.iter().any(|rule| !matches!(rule, types::CssRule::Import(_))) => {
+                        eprintln!("warning: @import \"{}\" after other rules is ignored", import.url);
+                    }
+                    rule => rules.extend(rule)
+                }
+            } else {
+                rules.push(types::CssRule::Style(self.parse_rule()));
+            }
         }
         rules
     }
 
+    // 解析 @ 规则，例如：`@media <queries> { <rules> }`，不支持的 @ 规则会被跳过
+    fn parse_at_rule(&mut self) -> Option<types::CssRule> {
+        assert_eq!(self.consume_char(), '@');
+        let name = self.parse_identifier().to_ascii_lowercase();
+        self.consume_whitespace();
+        let prelude = self.consume_until(|c| c == '{' || c == ';');
+
+        match &*name {
+            "import" => {
+                self.skip_at_rule_body();
+                let (url, queries) = parse_import_prelude(prelude)?;
+                Some(types::CssRule::Import(types::ImportRule { url, queries, rules: Vec::new() }))
+            }
+            "media" => Some(types::CssRule::Media(types::MediaRule {
+                queries: parse_media_query_list(prelude),
+                rules: self.parse_nested_rules(),
+            })),
+            // 条件不合法的 @supports 规则整个被忽略
+            "supports" => {
+                let condition = parse_supports_prelude(prelude);
+                let rules = self.parse_nested_rules();
+                Some(types::CssRule::Supports(types::SupportsRule { condition: condition?, rules }))
+            }
+            "font-face" if !self.eof() && self.next_char() == '{' => self.parse_font_face().map(types::CssRule::FontFace),
+            _ => {
+                self.skip_at_rule_body();
+                None
+            }
+        }
+    }
+
+    // 解析条件规则中用 `{}` 包裹的一组规则
+    fn parse_nested_rules(&mut self) -> Vec<types::CssRule> {
+        assert_eq!(self.consume_char(), '{');
+        let mut rules = self.parse_rules();
+        // @import 不能出现在条件规则中
+        rules.retain(|rule| !matches!(rule, types::CssRule::Import(_)));
+        if !self.eof() {
+            assert_eq!(self.consume_char(), '}');
+        }
+        rules
+    }
+
+    // 解析 @font-face 的描述符，缺少 font-family 或者没有合法的 src 时整个规则被忽略
+    fn parse_font_face(&mut self) -> Option<types::FontFaceRule> {
+        assert_eq!(self.consume_char(), '{');
+        let mut family = None;
+        let mut sources = None;
+        let mut weight = 400.0;
+        let mut style = "normal".to_string();
+        loop {
+            self.consume_whitespace();
+            if self.eof() {
+                break;
+            }
+            if self.next_char() == '}' {
+                self.consume_char();
+                break;
+            }
+            let name = self.parse_identifier().to_ascii_lowercase();
+            self.consume_whitespace();
+            assert_eq!(self.consume_char(), ':');
+            self.consume_whitespace();
+            let css = self.consume_until(|c| c == ';' || c == '}');
+            if !self.eof() && self.next_char() == ';' {
+                self.consume_char();
+            }
+
+            match &*name {
+                "font-family" => family = parse_family_name(css),
+                "src" => sources = parse_font_sources(css),
+                "font-weight" => match parse_value(css) {
+                    Some(types::Value::Keyword(k)) if k == "normal" => weight = 400.0,
+                    Some(types::Value::Keyword(k)) if k == "bold" => weight = 700.0,
+                    Some(types::Value::Number(n)) if (1.0..=1000.0).contains(&n) => weight = n,
+                    _ => eprintln!("warning: invalid font-weight in @font-face")
+                },
+                "font-style" => match parse_value(css) {
+                    Some(types::Value::Keyword(k)) if matches!(&*k, "normal" | "italic" | "oblique") => style = k,
+                    _ => eprintln!("warning: invalid font-style in @font-face")
+                },
+                _ => eprintln!("warning: unsupported @font-face descriptor `{}` is ignored", name)
+            }
+        }
+
+        match (family, sources) {
+            (Some(family), Some(sources)) => Some(types::FontFaceRule { family, sources, weight, style }),
+            _ => {
+                eprintln!("warning: @font-face without a valid font-family and src is ignored");
+                None
+            }
+        }
+    }
+
+    // 解析 src 中的一项：`url("a.ttf") format("truetype")` 或者 `local("Name")`
+    fn parse_font_source(&mut self) -> ParseResult<types::FontSource> {
+        if self.starts_with_keyword("local") {
+            self.parse_identifier();
+            self.expect_char('(')?;
+            self.consume_whitespace();
+            let name = match self.parse_component()? {
+                types::Value::Str(name) | types::Value::Keyword(name) => name,
+                _ => return Err("Expected font name in local()".to_string())
+            };
+            self.consume_whitespace();
+            self.expect_char(')')?;
+            return Ok(types::FontSource::Local(name));
+        }
+
+        let url = self.parse_url()?;
+        self.consume_whitespace();
+        let mut format = None;
+        if self.starts_with_keyword("format") {
+            self.parse_identifier();
+            self.expect_char('(')?;
+            self.consume_whitespace();
+            format = match self.parse_component()? {
+                types::Value::Str(format) | types::Value::Keyword(format) => Some(format),
+                _ => return Err("Expected font format in format()".to_string())
+            };
+            self.consume_whitespace();
+            self.expect_char(')')?;
+        }
+        Ok(types::FontSource::Url(url, format))
+    }
+
+    // 跳过 @ 规则的内容：以 `;` 结尾，或者是一个 `{}` 块
+    fn skip_at_rule_body(&mut self) {
+        if self.eof() {
+            return;
+        }
+        if self.consume_char() == '{' {
+            self.consume_until(|c| c == '}');
+            if !self.eof() {
+                self.consume_char();
+            }
+        }
+    }
+
     // 解析一个 css 规则，例如：`<selectors> { <declarations> }`
     fn parse_rule(&mut self) -> types::Rule {
         types::Rule { 
@@ -70,7 +232,7 @@ impl CSSParser {
             }
         }
         // 按照 css 选择器的权重排序，权重高的在前面
-        selector.sort_by(|a, b| b.specificity().cmp(&a.specificity()));
+        selector.sort_by_key(|s| std::cmp::Reverse(s.specificity()));
         selector
     }
 
@@ -115,64 +277,663 @@ impl CSSParser {
                 self.consume_char();
                 break;
             }
-            declarations.push(self.parse_declaration())
+            // 简写属性在这里展开为长属性，值不合法或者引擎不支持的声明会被丢弃
+            let (name, value, important) = match self.parse_declaration() {
+                Some(declaration) => declaration,
+                None => continue
+            };
+            if let Some(expanded) = shorthand::expand(&name, value) {
+                // 不支持的属性留到层叠时统一警告
+                if expanded.iter().all(|d| properties::lookup(&d.name).is_none() || properties::is_valid(&d.name, &d.value)) {
+                    declarations.extend(expanded.into_iter().map(|d| types::Declaration { important, ..d }));
+                }
+            }
         }
         declarations
     }
 
-    // 解析一组声明：<property>: <value>
-    fn parse_declaration(&mut self) -> types::Declaration {
-        let property_name = self.parse_identifier();
+    // 解析一组声明：<property>: <value> [!important]，值不合法时返回 None
+    fn parse_declaration(&mut self) -> Option<(String, types::Value, bool)> {
+        let property_name = property_name(self.parse_identifier());
         self.consume_whitespace();
         assert_eq!(self.consume_char(), ':');
         self.consume_whitespace();
-        let value = self.parse_value();
-        self.consume_whitespace();
-        assert_eq!(self.consume_char(), ';');
 
-        types::Declaration { name: property_name, value }
+        // 先读取声明值的原始文本，再从中解析出值
+        let (css, important) = split_important(&self.consume_until(|c| c == ';' || c == '}'));
+        if self.next_char() == ';' {
+            self.consume_char();
+        }
+
+        // 自定义属性的值是任意的文本，包含 var() 的值要等到计算值阶段才能解析
+        let value = if property_name.starts_with("--") || css.to_ascii_lowercase().contains("var(") {
+            types::Value::Unparsed(types::UnparsedValue { css, shorthand: None })
+        } else {
+            parse_value(css)?
+        };
+        Some((property_name, value, important))
+    }
+
+    // 读取原始文本，直到遇到同一层级的 stop 字符，括号和字符串中的字符不算
+    fn consume_until<F>(&mut self, stop: F) -> String where F: Fn(char) -> bool {
+        let start = self.pos;
+        let mut depth = 0;
+        while !self.eof() {
+            match self.next_char() {
+                c if depth == 0 && stop(c) => break,
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                '"' | '\'' => {
+                    let quote = self.consume_char();
+                    self.consume_while(|c| c != quote);
+                    if self.eof() { break; }
+                }
+                _ => {}
+            }
+            self.consume_char();
+        }
+        self.input[start..self.pos].trim().to_string()
+    }
+
+    // 解析属性值，逗号分隔的每一项可以是空格分隔的多个值，例如：`italic 12px/1.5 Arial, sans-serif`
+    fn parse_value(&mut self) -> ParseResult<types::Value> {
+        let mut groups = vec![self.parse_space_separated()?];
+        while self.next_char() == ',' {
+            self.consume_char();
+            self.consume_whitespace();
+            groups.push(self.parse_space_separated()?);
+        }
+        Ok(list_or_single(groups, types::Separator::Comma))
     }
 
-    fn parse_value(&mut self) -> types::Value {
+    // 解析空格分隔的多个值，直到遇到逗号或声明结束
+    fn parse_space_separated(&mut self) -> ParseResult<types::Value> {
+        let mut values = Vec::new();
+        loop {
+            let mut value = self.parse_component()?;
+            self.consume_whitespace();
+
+            // 斜杠将前后两个值组合在一起，例如：`12px/1.5`
+            if self.next_char() == '/' {
+                let mut parts = vec![value];
+                while self.next_char() == '/' {
+                    self.consume_char();
+                    self.consume_whitespace();
+                    parts.push(self.parse_component()?);
+                    self.consume_whitespace();
+                }
+                value = types::Value::List(parts, types::Separator::Slash);
+            }
+            values.push(value);
+
+            match self.next_char() {
+                ',' | ';' | '}' | '!' => break,
+                _ => {}
+            }
+        }
+        Ok(list_or_single(values, types::Separator::Space))
+    }
+
+    // 解析单个值
+    fn parse_component(&mut self) -> ParseResult<types::Value> {
         match self.next_char() {
-            '0'..='9' => self.parse_length(),
+            '0'..='9' | '.' => self.parse_length(),
+            '-' if self.starts_with_number(1) => self.parse_length(),
             '#' => self.parse_color(),
-            _ => types::Value::Keyword(self.parse_identifier())
+            '"' | '\'' => self.parse_string(),
+            _ if self.input[self.pos..].get(..4).is_some_and(|s| s.eq_ignore_ascii_case("url(")) => Ok(types::Value::Url(self.parse_url()?)),
+            c if valid_identifier_char(c) => {
+                let name = self.parse_identifier();
+                if self.next_char() == '(' {
+                    self.parse_function(&name)
+                } else {
+                    // 关键字不区分大小写，统一转换为小写，之后的阶段直接比较
+                    Ok(types::Value::Keyword(name.to_ascii_lowercase()))
+                }
+            }
+            c => Err(format!("Unexpected character {} in value", c))
+        }
+    }
+
+    // 解析函数，例如：`calc(100% - 2 * 20px)`
+    fn parse_function(&mut self, name: &str) -> ParseResult<types::Value> {
+        match &*name.to_ascii_lowercase() {
+            name @ ("calc" | "min" | "max" | "clamp") => {
+                let calc = self.parse_math_function(name)?;
+                calc.check_type()?;
+                Ok(types::Value::Calc(calc))
+            }
+            name => Err(format!("Unsupported function {}()", name))
+        }
+    }
+
+    // 解析 calc()、min()、max()、clamp() 的参数
+    fn parse_math_function(&mut self, name: &str) -> ParseResult<types::Calc> {
+        assert_eq!(self.consume_char(), '(');
+        let mut args = Vec::new();
+        loop {
+            self.consume_whitespace();
+            args.push(self.parse_calc_sum()?);
+            self.consume_whitespace();
+            match self.consume_char() {
+                ',' => {}
+                ')' => break,
+                c => return Err(format!("Unexpected character {} in {}()", c, name))
+            }
+        }
+
+        match (name, args.len()) {
+            ("calc", 1) => Ok(args.remove(0)),
+            ("min", _) => Ok(types::Calc::Min(args)),
+            ("max", _) => Ok(types::Calc::Max(args)),
+            ("clamp", 3) => {
+                let (max, value, min) = (args.remove(2), args.remove(1), args.remove(0));
+                Ok(types::Calc::Clamp(Box::new(min), Box::new(value), Box::new(max)))
+            }
+            _ => Err(format!("Wrong number of arguments to {}()", name))
         }
     }
 
-    fn parse_length(&mut self) -> types::Value {
-        types::Value::Length(self.parse_float(), self.parse_unit())
+    // 解析加减法，`+` 和 `-` 两边必须有空格
+    fn parse_calc_sum(&mut self) -> ParseResult<types::Calc> {
+        let mut left = self.parse_calc_product()?;
+        loop {
+            let start = self.pos;
+            self.consume_whitespace();
+            let op = self.next_char();
+            // 先确认是 `+` 或 `-` 再检查后面的空格，其他字符可能占多个字节
+            let spaced = (op == '+' || op == '-') && self.pos > start && self.input[self.pos + 1..].starts_with(char::is_whitespace);
+            if !spaced {
+                self.pos = start;
+                return Ok(left);
+            }
+            self.consume_char();
+            self.consume_whitespace();
+            let right = Box::new(self.parse_calc_product()?);
+            left = match op {
+                '+' => types::Calc::Sum(Box::new(left), right),
+                _ => types::Calc::Difference(Box::new(left), right)
+            };
+        }
     }
 
-    fn parse_float(&mut self) -> f32 {
-        let s = self.consume_while(|c| match c {
-            '0'..='9' | '.' => true,
-            _ => false,
-        });
-        s.parse().unwrap()
+    // 解析乘除法
+    fn parse_calc_product(&mut self) -> ParseResult<types::Calc> {
+        let mut left = self.parse_calc_value()?;
+        loop {
+            let start = self.pos;
+            self.consume_whitespace();
+            let op = self.next_char();
+            if op != '*' && op != '/' {
+                self.pos = start;
+                return Ok(left);
+            }
+            self.consume_char();
+            self.consume_whitespace();
+            let right = Box::new(self.parse_calc_value()?);
+            left = match op {
+                '*' => types::Calc::Product(Box::new(left), right),
+                _ => types::Calc::Quotient(Box::new(left), right)
+            };
+        }
+    }
+
+    // 解析表达式中的单个值：数字、长度、括号或者嵌套的数学函数
+    fn parse_calc_value(&mut self) -> ParseResult<types::Calc> {
+        match self.next_char() {
+            '(' => {
+                self.consume_char();
+                self.consume_whitespace();
+                let value = self.parse_calc_sum()?;
+                self.consume_whitespace();
+                match self.consume_char() {
+                    ')' => Ok(value),
+                    c => Err(format!("Unexpected character {} in calc()", c))
+                }
+            }
+            '0'..='9' | '.' => self.parse_calc_number(),
+            '-' if self.starts_with_number(1) => self.parse_calc_number(),
+            c if valid_identifier_char(c) => {
+                let name = self.parse_identifier().to_ascii_lowercase();
+                match &*name {
+                    "calc" | "min" | "max" | "clamp" if self.next_char() == '(' => self.parse_math_function(&name),
+                    _ => Err(format!("Unexpected {} in calc()", name))
+                }
+            }
+            c => Err(format!("Unexpected character {} in calc()", c))
+        }
+    }
+
+    fn parse_calc_number(&mut self) -> ParseResult<types::Calc> {
+        match self.parse_length()? {
+            types::Value::Length(value, unit) => Ok(types::Calc::Length(value, unit)),
+            types::Value::Number(value) => Ok(types::Calc::Number(value)),
+            _ => unreachable!()
+        }
     }
 
-    fn parse_unit(&mut self) -> types::Unit {
+    // 解析单个媒体查询：`[not | only]? <media-type> [and <condition>]?` 或者 `<condition>`
+    fn parse_media_query(&mut self) -> ParseResult<media::MediaQuery> {
+        if self.next_char() == '(' || self.starts_with_not_condition() {
+            let condition = self.parse_media_condition(true)?;
+            return Ok(media::MediaQuery { qualifier: None, media_type: media::MediaType::All, condition: Some(condition) });
+        }
+
+        let mut word = self.parse_identifier().to_ascii_lowercase();
+        self.consume_whitespace();
+        let qualifier = match &*word {
+            "not" => Some(media::MediaQualifier::Not),
+            "only" => Some(media::MediaQualifier::Only),
+            _ => None
+        };
+        if qualifier.is_some() {
+            word = self.parse_identifier().to_ascii_lowercase();
+            self.consume_whitespace();
+        }
+        let media_type = match &*word {
+            "all" => media::MediaType::All,
+            "screen" => media::MediaType::Screen,
+            "print" => media::MediaType::Print,
+            "" | "and" | "or" | "not" | "only" => return Err(format!("Invalid media type {}", word)),
+            _ => media::MediaType::Unknown(word),
+        };
+
+        let condition = if self.starts_with_keyword("and") {
+            self.parse_identifier();
+            self.consume_whitespace();
+            // 媒体类型后面的条件不能使用 or
+            Some(self.parse_media_condition(false)?)
+        } else {
+            None
+        };
+        Ok(media::MediaQuery { qualifier, media_type, condition })
+    }
+
+    // 解析媒体条件：`not <in-parens>`，或者用 and（allow_or 时也可以是 or）连接的多个 <in-parens>
+    fn parse_media_condition(&mut self, allow_or: bool) -> ParseResult<media::MediaCondition> {
+        if self.starts_with_keyword("not") {
+            self.parse_identifier();
+            self.consume_whitespace();
+            return Ok(media::MediaCondition::Not(Box::new(self.parse_media_in_parens()?)));
+        }
+
+        let first = self.parse_media_in_parens()?;
+        self.consume_whitespace();
+        let combinator = if self.starts_with_keyword("and") {
+            "and"
+        } else if allow_or && self.starts_with_keyword("or") {
+            "or"
+        } else {
+            return Ok(first);
+        };
+
+        let mut conditions = vec![first];
+        while self.starts_with_keyword(combinator) {
+            self.parse_identifier();
+            self.consume_whitespace();
+            conditions.push(self.parse_media_in_parens()?);
+            self.consume_whitespace();
+        }
+        Ok(match combinator {
+            "and" => media::MediaCondition::And(conditions),
+            _ => media::MediaCondition::Or(conditions)
+        })
+    }
+
+    // 解析括号中的媒体条件或者媒体特性
+    fn parse_media_in_parens(&mut self) -> ParseResult<media::MediaCondition> {
+        if self.consume_char() != '(' {
+            return Err("Expected ( in media condition".to_string());
+        }
+        self.consume_whitespace();
+        let condition = if self.next_char() == '(' || self.starts_with_keyword("not") {
+            self.parse_media_condition(true)?
+        } else {
+            self.parse_media_feature()?
+        };
+        self.consume_whitespace();
+        if self.consume_char() != ')' {
+            return Err("Expected ) in media condition".to_string());
+        }
+        Ok(condition)
+    }
+
+    // 解析媒体特性：`(name)`、`(name: value)`、`(name <op> value)`、`(value <op> name)`、`(value <op> name <op> value)`
+    fn parse_media_feature(&mut self) -> ParseResult<media::MediaCondition> {
+        let feature = media::MediaFeature::Range;
+        let condition = media::MediaCondition::Feature;
+
+        // 以值开头的范围形式
+        if !self.next_char().is_alphabetic() {
+            let value = self.parse_component()?;
+            self.consume_whitespace();
+            let op = self.parse_comparison()?;
+            self.consume_whitespace();
+            let name = self.parse_identifier().to_ascii_lowercase();
+            self.consume_whitespace();
+            let left = condition(feature(name.clone(), op.flip(), value));
+            if self.next_char() == ')' {
+                return Ok(left);
+            }
+            let op2 = self.parse_comparison()?;
+            self.consume_whitespace();
+            let right = condition(feature(name, op2, self.parse_component()?));
+            return Ok(media::MediaCondition::And(vec![left, right]));
+        }
+
+        let name = self.parse_identifier().to_ascii_lowercase();
+        self.consume_whitespace();
+        match self.next_char() {
+            ')' => Ok(condition(media::MediaFeature::Boolean(name))),
+            ':' => {
+                self.consume_char();
+                self.consume_whitespace();
+                let value = self.parse_component()?;
+                // min-/max- 前缀转换为范围形式
+                let (name, op) = match (name.strip_prefix("min-"), name.strip_prefix("max-")) {
+                    (Some(name), _) => (name.to_string(), media::Comparison::Ge),
+                    (_, Some(name)) => (name.to_string(), media::Comparison::Le),
+                    _ => (name, media::Comparison::Eq)
+                };
+                Ok(condition(feature(name, op, value)))
+            }
+            _ => {
+                let op = self.parse_comparison()?;
+                self.consume_whitespace();
+                Ok(condition(feature(name, op, self.parse_component()?)))
+            }
+        }
+    }
+
+    // 解析比较运算符：<、<=、=、>=、>
+    fn parse_comparison(&mut self) -> ParseResult<media::Comparison> {
+        let op = self.consume_while(|c| matches!(c, '<' | '>' | '='));
+        match &*op {
+            "<" => Ok(media::Comparison::Lt),
+            "<=" => Ok(media::Comparison::Le),
+            "=" => Ok(media::Comparison::Eq),
+            ">=" => Ok(media::Comparison::Ge),
+            ">" => Ok(media::Comparison::Gt),
+            _ => Err(format!("Invalid comparison {}", op))
+        }
+    }
+
+    // 解析特性查询条件：`not <in-parens>`，或者用 and 或 or 连接的多个 <in-parens>
+    fn parse_supports_condition(&mut self) -> ParseResult<SupportsCondition> {
+        if self.starts_with_keyword("not") {
+            self.parse_identifier();
+            self.consume_whitespace();
+            return Ok(SupportsCondition::Not(Box::new(self.parse_supports_in_parens()?)));
+        }
+
+        let first = self.parse_supports_in_parens()?;
+        self.consume_whitespace();
+        let combinator = if self.starts_with_keyword("and") {
+            "and"
+        } else if self.starts_with_keyword("or") {
+            "or"
+        } else {
+            return Ok(first);
+        };
+
+        // and 和 or 不能混用，需要用括号分组
+        let mut conditions = vec![first];
+        while self.starts_with_keyword(combinator) {
+            self.parse_identifier();
+            self.consume_whitespace();
+            conditions.push(self.parse_supports_in_parens()?);
+            self.consume_whitespace();
+        }
+        Ok(match combinator {
+            "and" => SupportsCondition::And(conditions),
+            _ => SupportsCondition::Or(conditions)
+        })
+    }
+
+    // 解析括号中的条件或者声明，以及无法识别的函数，例如：`selector(a > b)`
+    fn parse_supports_in_parens(&mut self) -> ParseResult<SupportsCondition> {
+        if self.next_char() != '(' {
+            let name = self.parse_identifier();
+            if name.is_empty() || self.eof() || self.next_char() != '(' {
+                return Err("Expected ( in supports condition".to_string());
+            }
+            self.consume_char();
+            let args = self.consume_until(|c| c == ')');
+            self.expect_char(')')?;
+            return Ok(SupportsCondition::Unknown(format!("{}({})", name, args)));
+        }
+
+        self.consume_char();
+        self.consume_whitespace();
+        let condition = if self.next_char() == '(' || self.starts_with_keyword("not") {
+            self.parse_supports_condition()?
+        } else {
+            let start = self.pos;
+            let name = self.parse_identifier();
+            self.consume_whitespace();
+            if !name.is_empty() && self.next_char() == ':' {
+                self.consume_char();
+                SupportsCondition::Declaration(property_name(name), self.consume_until(|c| c == ')'))
+            } else {
+                self.pos = start;
+                SupportsCondition::Unknown(format!("({})", self.consume_until(|c| c == ')')))
+            }
+        };
+        self.consume_whitespace();
+        self.expect_char(')')?;
+        Ok(condition)
+    }
+
+    fn expect_char(&mut self, expected: char) -> ParseResult<()> {
+        if self.eof() || self.consume_char() != expected {
+            return Err(format!("Expected {}", expected));
+        }
+        Ok(())
+    }
+
+    // 是否以 `not (` 开头（中间可以有空白），这时媒体查询没有媒体类型，只有条件
+    fn starts_with_not_condition(&mut self) -> bool {
+        if !self.starts_with_keyword("not") {
+            return false;
+        }
+        let pos = self.pos;
+        self.parse_identifier();
+        self.consume_whitespace();
+        let result = self.next_char() == '(';
+        self.pos = pos;
+        result
+    }
+
+    // 接下来是否是给定的关键字（不区分大小写，并且后面不是标识符的一部分）
+    fn starts_with_keyword(&self, keyword: &str) -> bool {
+        let rest = &self.input[self.pos..];
+        rest.get(..keyword.len()).is_some_and(|s| s.eq_ignore_ascii_case(keyword))
+            && !rest[keyword.len()..].starts_with(valid_identifier_char)
+    }
+
+    // 跳过 offset 个字节后是否是数字
+    fn starts_with_number(&self, offset: usize) -> bool {
+        self.input[self.pos + offset..].starts_with(|c: char| c.is_ascii_digit() || c == '.')
+    }
+
+    // 解析长度或者数字，没有单位的值视为数字，例如：`1.5`
+    fn parse_length(&mut self) -> ParseResult<types::Value> {
+        let value = self.parse_float()?;
+        Ok(match self.parse_unit()? {
+            Some(unit) => types::Value::Length(value, unit),
+            None => types::Value::Number(value)
+        })
+    }
+
+    fn parse_float(&mut self) -> ParseResult<f32> {
+        let mut s = String::new();
+        if self.next_char() == '-' {
+            s.push(self.consume_char());
+        }
+        s.push_str(&self.consume_while(|c| matches!(c, '0'..='9' | '.')));
+        s.parse().map_err(|_| format!("Invalid number {}", s))
+    }
+
+    fn parse_unit(&mut self) -> ParseResult<Option<types::Unit>> {
+        if !self.eof() && self.next_char() == '%' {
+            self.consume_char();
+            return Ok(Some(types::Unit::Percent));
+        }
         match &*self.parse_identifier().to_ascii_lowercase() {
-            "px" => types::Unit::Px,
-            _ => panic!("unrecognized unit")
+            "" => Ok(None),
+            "px" => Ok(Some(types::Unit::Px)),
+            "pt" => Ok(Some(types::Unit::Pt)),
+            "em" => Ok(Some(types::Unit::Em)),
+            "rem" => Ok(Some(types::Unit::Rem)),
+            "dpi" => Ok(Some(types::Unit::Dpi)),
+            "dpcm" => Ok(Some(types::Unit::Dpcm)),
+            "dppx" | "x" => Ok(Some(types::Unit::Dppx)),
+            unit => Err(format!("Unrecognized unit {}", unit))
         }
     }
 
-    fn parse_color(&mut self) -> types::Value {
+    // 解析字符串，例如："Helvetica Neue"
+    fn parse_string(&mut self) -> ParseResult<types::Value> {
+        let open_quote = self.consume_char();
+        let value = self.consume_while(|c| c != open_quote);
+        if self.eof() {
+            return Err("Unterminated string".to_string());
+        }
+        self.consume_char();
+        Ok(types::Value::Str(value))
+    }
+
+    // 解析地址：`url("x.css")`、`url(x.css)` 或者字符串 `"x.css"`
+    fn parse_url(&mut self) -> ParseResult<String> {
+        let is_function = self.input[self.pos..].get(..4).is_some_and(|s| s.eq_ignore_ascii_case("url("));
+        if is_function {
+            self.pos += 4;
+            self.consume_whitespace();
+        }
+
+        let url = if !self.eof() && matches!(self.next_char(), '"' | '\'') {
+            match self.parse_string()? {
+                types::Value::Str(url) => url,
+                _ => unreachable!()
+            }
+        } else if is_function {
+            self.consume_while(|c| c != ')' && !c.is_whitespace())
+        } else {
+            return Err("Expected url".to_string());
+        };
+
+        if is_function {
+            self.consume_whitespace();
+            if self.eof() || self.consume_char() != ')' {
+                return Err("Expected ) in url()".to_string());
+            }
+        }
+        Ok(url)
+    }
+
+    // 解析十六进制颜色：#rgb、#rgba、#rrggbb、#rrggbbaa
+    fn parse_color(&mut self) -> ParseResult<types::Value> {
         assert_eq!(self.consume_char(), '#');
-        types::Value::ColorValue(types::Color {
-            r: self.parse_hex_pair(),
-            g: self.parse_hex_pair(),
-            b: self.parse_hex_pair(),
-            a: 255
-        })
+        let hex = self.consume_while(|c| c.is_ascii_hexdigit());
+        let digits: Vec<u8> = match hex.len() {
+            // 单个数字重复一次，例如 #0cf 等同于 #00ccff
+            3 | 4 => hex.chars().map(|c| parse_hex(&c.to_string().repeat(2))).collect::<ParseResult<_>>()?,
+            6 | 8 => (0..hex.len()).step_by(2).map(|i| parse_hex(&hex[i..i + 2])).collect::<ParseResult<_>>()?,
+            _ => return Err(format!("Invalid color #{}", hex))
+        };
+        Ok(types::Value::ColorValue(types::Color {
+            r: digits[0],
+            g: digits[1],
+            b: digits[2],
+            a: digits.get(3).copied().unwrap_or(255)
+        }))
+    }
+}
+
+fn parse_hex(s: &str) -> ParseResult<u8> {
+    u8::from_str_radix(s, 16).map_err(|e| e.to_string())
+}
+
+// 属性名称不区分大小写，统一转换为小写，自定义属性的名称区分大小写
+fn property_name(name: String) -> String {
+    if name.starts_with("--") { name } else { name.to_ascii_lowercase() }
+}
+
+// 解析 @import 的地址和媒体查询，例如：`url("base.css") screen`、`"print.css" print`
+fn parse_import_prelude(prelude: String) -> Option<(String, media::MediaQueryList)> {
+    let mut parser = CSSParser { pos: 0, input: prelude };
+    let url = parser.parse_url().ok()?;
+    let queries = parse_media_query_list(parser.input[parser.pos..].to_string());
+    Some((url, queries))
+}
+
+// 解析 @font-face 的 font-family，只能是一个名称，例如：`"Open Sans"`、`Open Sans`
+fn parse_family_name(css: String) -> Option<String> {
+    match parse_value(css)? {
+        types::Value::Str(name) | types::Value::Keyword(name) => Some(name),
+        types::Value::List(words, types::Separator::Space) => words.into_iter().map(|word| match word {
+            types::Value::Keyword(word) => Some(word),
+            _ => None
+        }).collect::<Option<Vec<_>>>().map(|words| words.join(" ")),
+        _ => None
     }
+}
+
+// 解析 @font-face 的 src，逗号分隔的多个来源，其中任何一项不合法时返回 None
+fn parse_font_sources(css: String) -> Option<Vec<types::FontSource>> {
+    // 末尾加上 `;` 作为结束标记
+    let mut parser = CSSParser { pos: 0, input: css + ";" };
+    let mut sources = Vec::new();
+    loop {
+        parser.consume_whitespace();
+        sources.push(parser.parse_font_source().ok()?);
+        parser.consume_whitespace();
+        match parser.consume_char() {
+            ',' => {}
+            ';' if parser.eof() => return Some(sources),
+            _ => return None
+        }
+    }
+}
+
+// 解析 @supports 的条件，条件不合法时返回 None
+fn parse_supports_prelude(prelude: String) -> Option<SupportsCondition> {
+    // 末尾加上 `{` 作为结束标记
+    let mut parser = CSSParser { pos: 0, input: prelude + "{" };
+    parser.parse_supports_condition().ok().filter(|_| parser.next_char() == '{')
+}
 
-    fn parse_hex_pair(&mut self) -> u8 {
-        let s = &self.input[self.pos .. self.pos + 2];
-        self.pos += 2;
-        u8::from_str_radix(s, 16).unwrap()
+// 解析媒体查询列表，例如：`screen and (min-width: 600px), print`，不合法的查询等同于 `not all`
+fn parse_media_query_list(prelude: String) -> media::MediaQueryList {
+    if prelude.trim().is_empty() {
+        return media::MediaQueryList(Vec::new());
     }
-}
\ No newline at end of file
+    let queries = prelude.split(',').map(|query| {
+        // 末尾加上 `{` 作为结束标记
+        let mut parser = CSSParser { pos: 0, input: query.trim().to_string() + "{" };
+        parser.parse_media_query()
+            .ok()
+            .filter(|_| parser.next_char() == '{')
+            .unwrap_or_else(media::MediaQuery::not_all)
+    }).collect();
+    media::MediaQueryList(queries)
+}
+
+// 拆分出声明末尾的 !important
+fn split_important(css: &str) -> (String, bool) {
+    if let Some(bang) = css.rfind('!') {
+        if css[bang + 1..].trim().eq_ignore_ascii_case("important") {
+            return (css[..bang].trim_end().to_string(), true);
+        }
+    }
+    (css.to_string(), false)
+}
+
+// 只有一个值时不需要包装成列表
+fn list_or_single(mut values: Vec<types::Value>, separator: types::Separator) -> types::Value {
+    if values.len() == 1 {
+        values.swap_remove(0)
+    } else {
+        types::Value::List(values, separator)
+    }
+}
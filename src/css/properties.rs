@@ -0,0 +1,172 @@
+// 引擎支持的长属性列表，记录每个属性是否可以继承以及它的初始值
+use std::{collections::HashMap, sync::OnceLock};
+
+use super::{parser, types::{CalcType, Separator, Unit, Value}};
+
+pub struct Property {
+    pub inherited: bool,
+    pub initial: Value,
+    pub syntax: &'static [Syntax],
+}
+
+// 属性值可以取的类型，一个属性可以接受多种类型
+#[derive(Debug, Clone, Copy)]
+pub enum Syntax {
+    Keywords(&'static [&'static str]),
+    Length,
+    Percentage,
+    Number,
+    Color,
+    // 逗号分隔的字体名称
+    FamilyNames,
+}
+
+use Syntax::*;
+
+const AUTO: Syntax = Keywords(&["auto"]);
+const NONE: Syntax = Keywords(&["none"]);
+const BORDER_STYLE: Syntax = Keywords(&["none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset"]);
+const BORDER_WIDTH: Syntax = Keywords(&["thin", "medium", "thick"]);
+const COLOR: &[Syntax] = &[Keywords(&["currentcolor", "transparent"]), Color];
+const GAP: &[Syntax] = &[Keywords(&["normal"]), Length, Percentage];
+// 弹性项目在交叉轴上的对齐方式
+const SELF_ALIGNMENT: [&str; 8] = ["normal", "stretch", "flex-start", "flex-end", "start", "end", "center", "baseline"];
+// 主轴上的项目或者交叉轴上的行的分布方式
+const CONTENT_DISTRIBUTION: [&str; 10] = [
+    "normal", "stretch", "flex-start", "flex-end", "start", "end", "center", "space-between", "space-around", "space-evenly",
+];
+
+// 所有属性都接受的关键字
+const CSS_WIDE_KEYWORDS: [&str; 4] = ["inherit", "initial", "unset", "revert"];
+
+// (属性名称, 是否继承, 初始值, 值的类型)，计算值时按照这个顺序处理，border-style 需要排在 border-width 之前
+const PROPERTIES: &[(&str, bool, &str, &[Syntax])] = &[
+    ("display", false, "inline", &[Keywords(&["inline", "block", "flow-root", "flex", "inline-flex", "none"])]),
+    ("position", false, "static", &[Keywords(&["static", "relative", "absolute", "fixed", "sticky"])]),
+    ("float", false, "none", &[Keywords(&["none", "left", "right"])]),
+    ("clear", false, "none", &[Keywords(&["none", "left", "right", "both"])]),
+    ("overflow", false, "visible", &[Keywords(&["visible", "hidden", "clip", "scroll", "auto"])]),
+    ("top", false, "auto", &[AUTO, Length, Percentage]),
+    ("right", false, "auto", &[AUTO, Length, Percentage]),
+    ("bottom", false, "auto", &[AUTO, Length, Percentage]),
+    ("left", false, "auto", &[AUTO, Length, Percentage]),
+    ("width", false, "auto", &[AUTO, Length, Percentage]),
+    ("height", false, "auto", &[AUTO, Length, Percentage]),
+    ("min-width", false, "auto", &[AUTO, Length, Percentage]),
+    ("min-height", false, "auto", &[AUTO, Length, Percentage]),
+    ("max-width", false, "none", &[NONE, Length, Percentage]),
+    ("max-height", false, "none", &[NONE, Length, Percentage]),
+    ("box-sizing", false, "content-box", &[Keywords(&["content-box", "border-box"])]),
+    ("flex-direction", false, "row", &[Keywords(&["row", "row-reverse", "column", "column-reverse"])]),
+    ("flex-wrap", false, "nowrap", &[Keywords(&["nowrap", "wrap", "wrap-reverse"])]),
+    ("flex-grow", false, "0", &[Number]),
+    ("flex-shrink", false, "1", &[Number]),
+    ("flex-basis", false, "auto", &[Keywords(&["auto", "content"]), Length, Percentage]),
+    ("order", false, "0", &[Number]),
+    ("justify-content", false, "normal", &[Keywords(&CONTENT_DISTRIBUTION)]),
+    ("align-items", false, "normal", &[Keywords(&SELF_ALIGNMENT)]),
+    ("align-self", false, "auto", &[AUTO, Keywords(&SELF_ALIGNMENT)]),
+    ("align-content", false, "normal", &[Keywords(&CONTENT_DISTRIBUTION)]),
+    ("row-gap", false, "normal", GAP),
+    ("column-gap", false, "normal", GAP),
+    ("margin-top", false, "0px", &[AUTO, Length, Percentage]),
+    ("margin-right", false, "0px", &[AUTO, Length, Percentage]),
+    ("margin-bottom", false, "0px", &[AUTO, Length, Percentage]),
+    ("margin-left", false, "0px", &[AUTO, Length, Percentage]),
+    ("padding-top", false, "0px", &[Length, Percentage]),
+    ("padding-right", false, "0px", &[Length, Percentage]),
+    ("padding-bottom", false, "0px", &[Length, Percentage]),
+    ("padding-left", false, "0px", &[Length, Percentage]),
+    ("border-top-style", false, "none", &[BORDER_STYLE]),
+    ("border-right-style", false, "none", &[BORDER_STYLE]),
+    ("border-bottom-style", false, "none", &[BORDER_STYLE]),
+    ("border-left-style", false, "none", &[BORDER_STYLE]),
+    ("border-top-width", false, "medium", &[BORDER_WIDTH, Length]),
+    ("border-right-width", false, "medium", &[BORDER_WIDTH, Length]),
+    ("border-bottom-width", false, "medium", &[BORDER_WIDTH, Length]),
+    ("border-left-width", false, "medium", &[BORDER_WIDTH, Length]),
+    ("border-top-color", false, "currentcolor", COLOR),
+    ("border-right-color", false, "currentcolor", COLOR),
+    ("border-bottom-color", false, "currentcolor", COLOR),
+    ("border-left-color", false, "currentcolor", COLOR),
+    ("background-color", false, "transparent", COLOR),
+    ("color", true, "#000000", COLOR),
+    ("font-family", true, "serif", &[FamilyNames]),
+    ("font-size", true, "medium", &[
+        Keywords(&["xx-small", "x-small", "small", "medium", "large", "x-large", "xx-large", "smaller", "larger"]),
+        Length,
+        Percentage,
+    ]),
+    ("font-style", true, "normal", &[Keywords(&["normal", "italic", "oblique"])]),
+    ("font-variant", true, "normal", &[Keywords(&["normal", "small-caps"])]),
+    ("font-weight", true, "normal", &[Keywords(&["normal", "bold", "bolder", "lighter"]), Number]),
+    ("line-height", true, "normal", &[Keywords(&["normal"]), Number, Length, Percentage]),
+    ("visibility", true, "visible", &[Keywords(&["visible", "hidden", "collapse"])]),
+    ("white-space", true, "normal", &[Keywords(&["normal", "nowrap", "pre", "pre-wrap"])]),
+    ("direction", true, "ltr", &[Keywords(&["ltr", "rtl"])]),
+    ("unicode-bidi", false, "normal", &[Keywords(&["normal", "embed", "isolate", "bidi-override", "isolate-override", "plaintext"])]),
+];
+
+fn registry() -> &'static HashMap<&'static str, Property> {
+    static REGISTRY: OnceLock<HashMap<&'static str, Property>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        PROPERTIES.iter().map(|&(name, inherited, initial, syntax)| {
+            let initial = parser::parse_value(initial.to_string()).expect("invalid initial value");
+            (name, Property { inherited, initial, syntax })
+        }).collect()
+    })
+}
+
+// 查找属性的定义，不支持的属性返回 None
+pub fn lookup(name: &str) -> Option<&'static Property> {
+    registry().get(name)
+}
+
+// 以 -- 开头的自定义属性，例如：`--brand: #00ccff`
+pub fn is_custom(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+// 全部支持的属性名称
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PROPERTIES.iter().map(|&(name, _, _, _)| name)
+}
+
+// 引擎是否支持属性的这个值，不支持的属性返回 false，自定义属性可以是任意值
+// 包含 var() 的值要等到替换之后才能判断，这里认为是支持的
+pub fn is_valid(name: &str, value: &Value) -> bool {
+    if is_custom(name) || matches!(value, Value::Unparsed(_)) {
+        return true;
+    }
+    if matches!(value, Value::Keyword(k) if CSS_WIDE_KEYWORDS.iter().any(|w| k.eq_ignore_ascii_case(w))) {
+        return true;
+    }
+    lookup(name).is_some_and(|property| property.syntax.iter().any(|syntax| syntax.accepts(value)))
+}
+
+impl Syntax {
+    fn accepts(&self, value: &Value) -> bool {
+        match (*self, value) {
+            (Keywords(keywords), Value::Keyword(k)) => keywords.iter().any(|keyword| k.eq_ignore_ascii_case(keyword)),
+            (Length, Value::Length(_, unit)) => matches!(unit, Unit::Px | Unit::Pt | Unit::Em | Unit::Rem),
+            (Length, Value::Number(n)) => *n == 0.0,
+            (Length, Value::Calc(calc)) => calc.check_type() == Ok(CalcType::Length),
+            (Percentage, Value::Length(_, Unit::Percent)) => true,
+            (Number, Value::Number(_)) => true,
+            (Number, Value::Calc(calc)) => calc.check_type() == Ok(CalcType::Number),
+            (Color, Value::ColorValue(_)) => true,
+            (FamilyNames, Value::List(families, Separator::Comma)) => families.iter().all(is_family_name),
+            (FamilyNames, _) => is_family_name(value),
+            _ => false
+        }
+    }
+}
+
+// 字体名称可以是字符串，也可以是空格分隔的多个标识符，例如：Times New Roman
+fn is_family_name(value: &Value) -> bool {
+    match value {
+        Value::Keyword(_) | Value::Str(_) => true,
+        Value::List(words, Separator::Space) => words.iter().all(|word| matches!(word, Value::Keyword(_))),
+        _ => false
+    }
+}
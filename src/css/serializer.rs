@@ -0,0 +1,469 @@
+// 把样式表转换回 css 文本，支持带缩进的格式化输出和去掉多余空白的压缩输出
+use super::{
+    media::{Comparison, MediaCondition, MediaFeature, MediaQualifier, MediaQuery, MediaQueryList, MediaType},
+    supports::SupportsCondition,
+    types::{Calc, Color, CssRule, Declaration, FontFaceRule, FontSource, Rule, Selector, Separator, Stylesheet, Unit, UnparsedValue, Value},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    // 每条声明一行，嵌套的规则缩进四个空格，规则之间空一行
+    Pretty,
+    // 去掉所有可以省略的空白和分号
+    Minified,
+}
+
+pub fn serialize(stylesheet: &Stylesheet, mode: Mode) -> String {
+    let mut serializer = Serializer { mode, output: String::new(), depth: 0 };
+    serializer.rules(&stylesheet.rules);
+    serializer.output
+}
+
+pub fn serialize_value(value: &Value, mode: Mode) -> String {
+    Serializer { mode, output: String::new(), depth: 0 }.value(value)
+}
+
+struct Serializer {
+    mode: Mode,
+    output: String,
+    // 当前嵌套的层数，用于缩进
+    depth: usize,
+}
+
+impl Serializer {
+    fn pretty(&self) -> bool {
+        self.mode == Mode::Pretty
+    }
+
+    // 格式化输出时返回 pretty，压缩输出时返回 minified
+    fn choose<'a>(&self, pretty: &'a str, minified: &'a str) -> &'a str {
+        if self.pretty() { pretty } else { minified }
+    }
+
+    fn indent(&mut self) {
+        if self.pretty() {
+            self.output.push_str(&"    ".repeat(self.depth));
+        }
+    }
+
+    fn rules(&mut self, rules: &[CssRule]) {
+        for (i, rule) in rules.iter().enumerate() {
+            if i > 0 && self.pretty() {
+                self.output.push('\n');
+            }
+            self.rule(rule);
+        }
+    }
+
+    fn rule(&mut self, rule: &CssRule) {
+        match rule {
+            CssRule::Style(rule) => self.style_rule(rule),
+            CssRule::Media(media) => {
+                let header = format!("@media {}", self.media_query_list(&media.queries));
+                self.block(&header, |s| s.rules(&media.rules));
+            }
+            CssRule::Supports(supports) => {
+                let header = format!("@supports {}", self.supports_condition(&supports.condition));
+                self.block(&header, |s| s.rules(&supports.rules));
+            }
+            // 被导入的规则属于另一个文件，只输出 @import 本身
+            CssRule::Import(import) => {
+                self.indent();
+                self.output.push_str(&format!("@import url({})", quote(&import.url)));
+                if !import.queries.0.is_empty() {
+                    self.output.push(' ');
+                    self.output.push_str(&self.media_query_list(&import.queries));
+                }
+                self.output.push(';');
+                if self.pretty() {
+                    self.output.push('\n');
+                }
+            }
+            CssRule::FontFace(font_face) => self.font_face(font_face),
+        }
+    }
+
+    // 输出 `<header> { <body> }`
+    fn block<F>(&mut self, header: &str, body: F) where F: FnOnce(&mut Serializer) {
+        self.indent();
+        self.output.push_str(header);
+        self.output.push_str(self.choose(" {\n", "{"));
+        self.depth += 1;
+        body(self);
+        self.depth -= 1;
+        self.indent();
+        self.output.push_str(self.choose("}\n", "}"));
+    }
+
+    fn style_rule(&mut self, rule: &Rule) {
+        let selectors: Vec<String> = rule.selectors.iter().map(serialize_selector).collect();
+        let header = selectors.join(self.choose(", ", ","));
+        let declarations = self.declarations(&rule.declarations);
+        self.declaration_block(&header, declarations);
+    }
+
+    // 描述符按照 font-family、src、font-weight、font-style 的顺序输出，取默认值的描述符省略
+    fn font_face(&mut self, font_face: &FontFaceRule) {
+        let separator = self.choose(": ", ":");
+        let sources: Vec<String> = font_face.sources.iter().map(|source| match source {
+            FontSource::Url(url, None) => format!("url({})", quote(url)),
+            FontSource::Url(url, Some(format)) => format!("url({}) format({})", quote(url), quote(format)),
+            FontSource::Local(name) => format!("local({})", quote(name)),
+        }).collect();
+        let mut descriptors = vec![
+            format!("font-family{}{}", separator, quote(&font_face.family)),
+            format!("src{}{}", separator, sources.join(self.choose(", ", ","))),
+        ];
+        if font_face.weight != 400.0 {
+            descriptors.push(format!("font-weight{}{}", separator, self.number(font_face.weight)));
+        }
+        if font_face.style != "normal" {
+            descriptors.push(format!("font-style{}{}", separator, font_face.style));
+        }
+        self.declaration_block("@font-face", descriptors);
+    }
+
+    // 输出 `<header> { <declarations> }`
+    fn declaration_block(&mut self, header: &str, declarations: Vec<String>) {
+        self.block(header, |s| {
+            let count = declarations.len();
+            for (i, declaration) in declarations.into_iter().enumerate() {
+                s.indent();
+                s.output.push_str(&declaration);
+                // 压缩输出时最后一条声明不需要分号
+                if s.pretty() || i + 1 < count {
+                    s.output.push(';');
+                }
+                if s.pretty() {
+                    s.output.push('\n');
+                }
+            }
+        });
+    }
+
+    // 包含 var() 的简写属性在解析时展开成了多个带有相同原始文本的长属性，这里重新合并为简写属性
+    fn declarations(&self, declarations: &[Declaration]) -> Vec<String> {
+        let mut result = Vec::new();
+        let mut previous: Option<(&str, &str, bool)> = None;
+        for declaration in declarations {
+            let (name, value) = match declaration.value {
+                Value::Unparsed(UnparsedValue { ref css, shorthand: Some(ref shorthand) }) => {
+                    let current = (&**shorthand, &**css, declaration.important);
+                    if previous == Some(current) {
+                        continue;
+                    }
+                    previous = Some(current);
+                    (&**shorthand, css.clone())
+                }
+                _ => {
+                    previous = None;
+                    (&*declaration.name, self.value(&declaration.value))
+                }
+            };
+            let important = if declaration.important { self.choose(" !important", "!important") } else { "" };
+            result.push(format!("{}{}{}{}", name, self.choose(": ", ":"), value, important));
+        }
+        result
+    }
+
+    fn value(&self, value: &Value) -> String {
+        match value {
+            Value::Keyword(k) => k.clone(),
+            Value::Length(v, unit) => format!("{}{}", self.number(*v), unit_str(unit)),
+            Value::Number(n) => self.number(*n),
+            Value::Str(s) => quote(s),
+            Value::Url(url) => format!("url({})", quote(url)),
+            Value::ColorValue(color) => self.color(color),
+            Value::List(values, separator) => {
+                let separator = match separator {
+                    Separator::Space => " ",
+                    Separator::Comma => self.choose(", ", ","),
+                    Separator::Slash => "/",
+                };
+                values.iter().map(|v| self.value(v)).collect::<Vec<_>>().join(separator)
+            }
+            Value::Unparsed(UnparsedValue { css, .. }) => css.clone(),
+            // min()、max()、clamp() 本身就是函数，其它表达式需要包在 calc() 中
+            Value::Calc(calc @ (Calc::Min(_) | Calc::Max(_) | Calc::Clamp(..))) => self.calc(calc),
+            Value::Calc(calc) => format!("calc({})", self.calc(calc)),
+        }
+    }
+
+    // 压缩输出时省略整数部分的 0，例如 0.5 输出为 .5
+    fn number(&self, n: f32) -> String {
+        let s = n.to_string();
+        match self.mode {
+            Mode::Minified if s.starts_with("0.") => s[1..].to_string(),
+            Mode::Minified if s.starts_with("-0.") => format!("-{}", &s[2..]),
+            _ => s
+        }
+    }
+
+    // 压缩输出时尽量使用三位或者四位的写法，例如 #00ccff 输出为 #0cf
+    fn color(&self, color: &Color) -> String {
+        let mut channels = vec![color.r, color.g, color.b];
+        if color.a != 255 {
+            channels.push(color.a);
+        }
+        let short = !self.pretty() && channels.iter().all(|c| c >> 4 == c & 0xf);
+        let hex: String = channels.iter()
+            .map(|&c| if short { format!("{:x}", c & 0xf) } else { format!("{:02x}", c) })
+            .collect();
+        format!("#{}", hex)
+    }
+
+    fn calc(&self, calc: &Calc) -> String {
+        let args = |args: &[&Calc]| args.iter().map(|arg| self.calc(arg)).collect::<Vec<_>>().join(self.choose(", ", ","));
+        // 加减号两边必须有空格，乘除号两边的空格可以省略
+        let product = self.choose(" * ", "*");
+        let quotient = self.choose(" / ", "/");
+        match calc {
+            Calc::Number(n) => self.number(*n),
+            Calc::Length(v, unit) => format!("{}{}", self.number(*v), unit_str(unit)),
+            Calc::Sum(a, b) => format!("{} + {}", self.calc(a), self.calc(b)),
+            Calc::Difference(a, b) => format!("{} - {}", self.calc(a), self.operand(b, is_sum)),
+            Calc::Product(a, b) => format!("{}{}{}", self.operand(a, is_sum), product, self.operand(b, is_sum)),
+            Calc::Quotient(a, b) => {
+                format!("{}{}{}", self.operand(a, is_sum), quotient, self.operand(b, |c| is_sum(c) || is_product(c)))
+            }
+            Calc::Min(values) => format!("min({})", args(&values.iter().collect::<Vec<_>>())),
+            Calc::Max(values) => format!("max({})", args(&values.iter().collect::<Vec<_>>())),
+            Calc::Clamp(min, value, max) => format!("clamp({})", args(&[min, value, max])),
+        }
+    }
+
+    // 优先级更低的子表达式需要加上括号
+    fn operand(&self, calc: &Calc, needs_parens: fn(&Calc) -> bool) -> String {
+        if needs_parens(calc) {
+            format!("({})", self.calc(calc))
+        } else {
+            self.calc(calc)
+        }
+    }
+
+    fn media_query_list(&self, queries: &MediaQueryList) -> String {
+        if queries.0.is_empty() {
+            return "all".to_string();
+        }
+        queries.0.iter().map(|query| self.media_query(query)).collect::<Vec<_>>().join(self.choose(", ", ","))
+    }
+
+    fn media_query(&self, query: &MediaQuery) -> String {
+        let media_type = match query.media_type {
+            MediaType::All => "all",
+            MediaType::Screen => "screen",
+            MediaType::Print => "print",
+            MediaType::Unknown(ref name) => name,
+        };
+        let qualifier = match query.qualifier {
+            Some(MediaQualifier::Not) => "not ",
+            Some(MediaQualifier::Only) => "only ",
+            None => "",
+        };
+        match (&query.condition, query.qualifier, &query.media_type) {
+            // 只有条件的查询，例如：`(min-width: 600px)`
+            (Some(condition), None, MediaType::All) => self.media_condition(condition),
+            // 媒体类型后面的条件不能直接使用 or
+            (Some(condition @ MediaCondition::Or(_)), ..) => {
+                format!("{}{} and ({})", qualifier, media_type, self.media_condition(condition))
+            }
+            (Some(condition), ..) => format!("{}{} and {}", qualifier, media_type, self.media_condition(condition)),
+            (None, ..) => format!("{}{}", qualifier, media_type),
+        }
+    }
+
+    fn media_condition(&self, condition: &MediaCondition) -> String {
+        let in_parens = |c: &MediaCondition| match c {
+            MediaCondition::Feature(_) => self.media_condition(c),
+            _ => format!("({})", self.media_condition(c)),
+        };
+        match condition {
+            MediaCondition::Feature(MediaFeature::Boolean(name)) => format!("({})", name),
+            MediaCondition::Feature(MediaFeature::Range(name, Comparison::Eq, value)) => {
+                format!("({}{}{})", name, self.choose(": ", ":"), self.value(value))
+            }
+            MediaCondition::Feature(MediaFeature::Range(name, comparison, value)) => {
+                format!("({} {} {})", name, comparison_str(*comparison), self.value(value))
+            }
+            MediaCondition::Not(c) => format!("not {}", in_parens(c)),
+            MediaCondition::And(conditions) => conditions.iter().map(in_parens).collect::<Vec<_>>().join(" and "),
+            MediaCondition::Or(conditions) => conditions.iter().map(in_parens).collect::<Vec<_>>().join(" or "),
+        }
+    }
+
+    fn supports_condition(&self, condition: &SupportsCondition) -> String {
+        let in_parens = |c: &SupportsCondition| match c {
+            SupportsCondition::Declaration(..) | SupportsCondition::Unknown(_) => self.supports_condition(c),
+            _ => format!("({})", self.supports_condition(c)),
+        };
+        match condition {
+            SupportsCondition::Declaration(name, value) => format!("({}{}{})", name, self.choose(": ", ":"), value),
+            SupportsCondition::Not(c) => format!("not {}", in_parens(c)),
+            SupportsCondition::And(conditions) => conditions.iter().map(in_parens).collect::<Vec<_>>().join(" and "),
+            SupportsCondition::Or(conditions) => conditions.iter().map(in_parens).collect::<Vec<_>>().join(" or "),
+            SupportsCondition::Unknown(css) => css.clone(),
+        }
+    }
+}
+
+// 没有标签、ID 和类的选择器就是 *
+pub fn serialize_selector(selector: &Selector) -> String {
+    let Selector::Simple(ref simple) = *selector;
+    let mut result = simple.tag_name.clone().unwrap_or_default();
+    if let Some(ref id) = simple.id {
+        result.push('#');
+        result.push_str(id);
+    }
+    for class in &simple.class {
+        result.push('.');
+        result.push_str(class);
+    }
+    if result.is_empty() {
+        result.push('*');
+    }
+    result
+}
+
+fn unit_str(unit: &Unit) -> &'static str {
+    match unit {
+        Unit::Px => "px",
+        Unit::Pt => "pt",
+        Unit::Em => "em",
+        Unit::Rem => "rem",
+        Unit::Percent => "%",
+        Unit::Dpi => "dpi",
+        Unit::Dpcm => "dpcm",
+        Unit::Dppx => "dppx",
+    }
+}
+
+fn comparison_str(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::Lt => "<",
+        Comparison::Le => "<=",
+        Comparison::Eq => "=",
+        Comparison::Ge => ">=",
+        Comparison::Gt => ">",
+    }
+}
+
+// 字符串中没有转义，包含双引号时使用单引号
+fn quote(s: &str) -> String {
+    if s.contains('"') {
+        format!("'{}'", s)
+    } else {
+        format!("\"{}\"", s)
+    }
+}
+
+fn is_sum(calc: &Calc) -> bool {
+    matches!(calc, Calc::Sum(..) | Calc::Difference(..))
+}
+
+fn is_product(calc: &Calc) -> bool {
+    matches!(calc, Calc::Product(..) | Calc::Quotient(..))
+}
+
+// 解析、输出之后重新解析，结果应该和第一次解析的相同
+#[cfg(test)]
+mod tests {
+    use crate::css::{parser, types::{Color, Stylesheet, Unit, Value}};
+
+    use super::{serialize, serialize_value, Mode};
+
+    // 样式表没有实现 PartialEq，用 Debug 输出比较
+    fn assert_round_trip(css: &str) -> (String, String) {
+        let parsed = parser::parse(css.to_string());
+        let outputs: Vec<String> = [Mode::Pretty, Mode::Minified].into_iter().map(|mode| {
+            let output = serialize(&parsed, mode);
+            let reparsed: Stylesheet = parser::parse(output.clone());
+            assert_eq!(format!("{:?}", reparsed.rules), format!("{:?}", parsed.rules), "{:?}: {}", mode, output);
+            // 再输出一次应该完全相同
+            assert_eq!(serialize(&reparsed, mode), output);
+            output
+        }).collect();
+        (outputs[0].clone(), outputs[1].clone())
+    }
+
+    fn assert_value_round_trip(css: &str) -> Value {
+        let value = parser::parse_value(css.to_string()).unwrap_or_else(|| panic!("invalid value {}", css));
+        for mode in [Mode::Pretty, Mode::Minified] {
+            let output = serialize_value(&value, mode);
+            assert_eq!(parser::parse_value(output.clone()), Some(value.clone()), "{:?}: {}", mode, output);
+        }
+        value
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(assert_value_round_trip("auto"), Value::Keyword("auto".to_string()));
+        assert_eq!(assert_value_round_trip("1.5"), Value::Number(1.5));
+        assert_eq!(assert_value_round_trip("-0.5"), Value::Number(-0.5));
+        assert_eq!(assert_value_round_trip("'Times New Roman'"), Value::Str("Times New Roman".to_string()));
+        assert_eq!(assert_value_round_trip("url(bg.png)"), Value::Url("bg.png".to_string()));
+        assert_eq!(assert_value_round_trip("#00ccff80"), Value::ColorValue(Color { r: 0, g: 0xcc, b: 0xff, a: 0x80 }));
+        assert_eq!(assert_value_round_trip("#123456"), Value::ColorValue(Color { r: 0x12, g: 0x34, b: 0x56, a: 255 }));
+        for (css, v, unit) in [("10px", 10.0, Unit::Px), ("12pt", 12.0, Unit::Pt), ("1.5em", 1.5, Unit::Em), ("2rem", 2.0, Unit::Rem), ("50%", 50.0, Unit::Percent)] {
+            assert_eq!(assert_value_round_trip(css), Value::Length(v, unit));
+        }
+        for css in ["italic bold 12px/1.5 Arial, sans-serif", "1px solid #000", "calc(100% - 2 * (10px + 1em))", "calc(100% / (2 * 3))",
+                    "min(10px, 5%)", "max(1em, 10px)", "clamp(1px, 50% - 2px, 3rem)"] {
+            assert_value_round_trip(css);
+        }
+    }
+
+    #[test]
+    fn pretty_and_minified_output() {
+        let (pretty, minified) = assert_round_trip("div, .a { color: #00ccff; margin-top: 0.5em !important; }");
+        // 选择器按照权重排列
+        assert_eq!(pretty, ".a, div {\n    color: #00ccff;\n    margin-top: 0.5em !important;\n}\n");
+        assert_eq!(minified, ".a,div{color:#0cf;margin-top:.5em!important}");
+    }
+
+    #[test]
+    fn shorthands_with_var_are_merged() {
+        let (pretty, _) = assert_round_trip("p { --gap: 4px; margin: var(--gap) 0; }");
+        assert_eq!(pretty, "p {\n    --gap: 4px;\n    margin: var(--gap) 0;\n}\n");
+    }
+
+    #[test]
+    fn at_rules() {
+        let (pretty, _) = assert_round_trip(
+            "@import url(\"base.css\") screen and (min-width: 600px); \
+             @media screen and (min-width: 600px), print and (orientation: landscape) { #a { width: 50%; } } \
+             @media not print and ((color) or (width >= 30em)) { * { display: block; } } \
+             @supports (display: flex) and (not (display: grid)) { @media (monochrome) { .b { color: #fff; } } } \
+             @font-face { font-family: \"My Font\"; src: url(\"a.ttf\") format(\"truetype\"), local(\"Arial\"); font-weight: 700; font-style: italic; }",
+        );
+        assert_eq!(pretty, "\
+@import url(\"base.css\") screen and (width >= 600px);
+
+@media screen and (width >= 600px), print and (orientation: landscape) {
+    #a {
+        width: 50%;
+    }
+}
+
+@media not print and ((color) or (width >= 30em)) {
+    * {
+        display: block;
+    }
+}
+
+@supports (display: flex) and (not (display: grid)) {
+    @media (monochrome) {
+        .b {
+            color: #ffffff;
+        }
+    }
+}
+
+@font-face {
+    font-family: \"My Font\";
+    src: url(\"a.ttf\") format(\"truetype\"), local(\"Arial\");
+    font-weight: 700;
+    font-style: italic;
+}
+");
+    }
+}
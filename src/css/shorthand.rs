@@ -0,0 +1,361 @@
+// 将简写属性展开为对应的长属性，例如：`margin: 10px 20px` 展开为 margin-top、margin-right、margin-bottom、margin-left
+use super::types::{Declaration, Separator, UnparsedValue, Value};
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+
+const BORDER_STYLES: [&str; 10] = [
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+// font-size 的绝对大小和相对大小关键字
+const FONT_SIZES: [&str; 9] = ["xx-small", "x-small", "small", "medium", "large", "x-large", "xx-large", "smaller", "larger"];
+
+// 如果 name 不是简写属性则原样返回，如果简写属性的值不合法则返回 None
+pub fn expand(name: &str, value: Value) -> Option<Vec<Declaration>> {
+    // 包含 var() 的简写属性暂时无法展开，每个长属性都记录下原始文本，等到替换之后再展开
+    if let Value::Unparsed(UnparsedValue { css, .. }) = value {
+        let shorthand = Some(name.to_string());
+        return Some(match longhands(name) {
+            Some(names) => names.iter().map(|longhand| {
+                declaration(longhand, Value::Unparsed(UnparsedValue { css: css.clone(), shorthand: shorthand.clone() }))
+            }).collect(),
+            None => vec![declaration(name, Value::Unparsed(UnparsedValue { css, shorthand: None }))]
+        });
+    }
+
+    match name {
+        "margin" | "padding" => {
+            let values = expand_sides(value)?;
+            Some(per_side(|side| format!("{}-{}", name, side), values))
+        }
+        "border-width" | "border-style" | "border-color" => {
+            let suffix = &name["border-".len()..];
+            let values = expand_sides(value)?;
+            Some(per_side(|side| format!("border-{}-{}", side, suffix), values))
+        }
+        "border" => {
+            let (width, style, color) = expand_border(value)?;
+            Some(SIDES.iter().flat_map(|side| border_side(side, &width, &style, &color)).collect())
+        }
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            let (width, style, color) = expand_border(value)?;
+            Some(border_side(&name["border-".len()..], &width, &style, &color))
+        }
+        "background" => expand_background(value),
+        "font" => expand_font(value),
+        "flex" => expand_flex(value),
+        "flex-flow" => expand_flex_flow(value),
+        "gap" => {
+            // 只有一个值时行和列的间隔相同
+            let (row, column) = match space_separated(value)?.as_slice() {
+                [gap] => (gap.clone(), gap.clone()),
+                [row, column] => (row.clone(), column.clone()),
+                _ => return None
+            };
+            Some(vec![declaration("row-gap", row), declaration("column-gap", column)])
+        }
+        _ => Some(vec![declaration(name, value)])
+    }
+}
+
+// 简写属性对应的全部长属性，name 不是简写属性时返回 None
+pub fn longhands(name: &str) -> Option<Vec<String>> {
+    let sides = |f: &dyn Fn(&str) -> String| Some(SIDES.iter().map(|side| f(side)).collect());
+    match name {
+        "margin" | "padding" => sides(&|side| format!("{}-{}", name, side)),
+        "border-width" | "border-style" | "border-color" => {
+            sides(&|side| format!("border-{}-{}", side, &name["border-".len()..]))
+        }
+        "border" => Some(SIDES.iter().flat_map(|side| {
+            ["width", "style", "color"].map(|part| format!("border-{}-{}", side, part))
+        }).collect()),
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            Some(["width", "style", "color"].iter().map(|part| format!("{}-{}", name, part)).collect())
+        }
+        "background" => Some(vec!["background-color".to_string()]),
+        "font" => Some(["font-style", "font-variant", "font-weight", "font-size", "line-height", "font-family"]
+            .iter().map(|name| name.to_string()).collect()),
+        "flex" => Some(vec!["flex-grow".to_string(), "flex-shrink".to_string(), "flex-basis".to_string()]),
+        "flex-flow" => Some(vec!["flex-direction".to_string(), "flex-wrap".to_string()]),
+        "gap" => Some(vec!["row-gap".to_string(), "column-gap".to_string()]),
+        _ => None
+    }
+}
+
+fn declaration(name: &str, value: Value) -> Declaration {
+    Declaration { name: name.to_string(), value, important: false }
+}
+
+fn keyword(s: &str) -> Value {
+    Value::Keyword(s.to_string())
+}
+
+// 空格分隔的列表展开成数组，单个值视为只有一项
+fn space_separated(value: Value) -> Option<Vec<Value>> {
+    match value {
+        Value::List(values, Separator::Space) => Some(values),
+        Value::List(..) => None,
+        value => Some(vec![value])
+    }
+}
+
+// 按照 1/2/3/4 个值的规则展开为 上、右、下、左
+fn expand_sides(value: Value) -> Option<[Value; 4]> {
+    let values = space_separated(value)?;
+    match values.as_slice() {
+        [a] => Some([a.clone(), a.clone(), a.clone(), a.clone()]),
+        [a, b] => Some([a.clone(), b.clone(), a.clone(), b.clone()]),
+        [a, b, c] => Some([a.clone(), b.clone(), c.clone(), b.clone()]),
+        [a, b, c, d] => Some([a.clone(), b.clone(), c.clone(), d.clone()]),
+        _ => None
+    }
+}
+
+fn per_side<F>(name: F, values: [Value; 4]) -> Vec<Declaration> where F: Fn(&str) -> String {
+    SIDES.iter().zip(values).map(|(side, value)| Declaration { name: name(side), value, important: false }).collect()
+}
+
+fn border_side(side: &str, width: &Value, style: &Value, color: &Value) -> Vec<Declaration> {
+    vec![
+        declaration(&format!("border-{}-width", side), width.clone()),
+        declaration(&format!("border-{}-style", side), style.clone()),
+        declaration(&format!("border-{}-color", side), color.clone()),
+    ]
+}
+
+fn is_length(value: &Value) -> bool {
+    match value {
+        Value::Length(..) => true,
+        Value::Number(n) => *n == 0.0,
+        _ => false
+    }
+}
+
+fn is_color(value: &Value) -> bool {
+    match value {
+        Value::ColorValue(_) => true,
+        Value::Keyword(k) => k == "transparent" || k == "currentcolor",
+        _ => false
+    }
+}
+
+fn is_border_width(value: &Value) -> bool {
+    match value {
+        Value::Keyword(k) => k == "thin" || k == "medium" || k == "thick",
+        v => is_length(v)
+    }
+}
+
+// 长度、百分比以及大小关键字，font 的斜杠前后分别是 font-size 和 line-height
+fn is_font_size(value: &Value) -> bool {
+    match value {
+        Value::Keyword(k) => FONT_SIZES.contains(&&**k),
+        v => is_length(v)
+    }
+}
+
+fn is_border_style(value: &Value) -> bool {
+    matches!(value, Value::Keyword(k) if BORDER_STYLES.contains(&&**k))
+}
+
+// border 的宽度、样式、颜色可以任意顺序出现，省略的部分使用初始值
+fn expand_border(value: Value) -> Option<(Value, Value, Value)> {
+    let (mut width, mut style, mut color) = (None, None, None);
+    for v in space_separated(value)? {
+        if width.is_none() && is_border_width(&v) {
+            width = Some(v);
+        } else if style.is_none() && is_border_style(&v) {
+            style = Some(v);
+        } else if color.is_none() && is_color(&v) {
+            color = Some(v);
+        } else {
+            return None;
+        }
+    }
+    Some((
+        width.unwrap_or_else(|| keyword("medium")),
+        style.unwrap_or_else(|| keyword("none")),
+        color.unwrap_or_else(|| keyword("currentcolor")),
+    ))
+}
+
+// 目前只支持背景颜色，其它部分（图片、位置和尺寸、平铺方式、滚动方式）跳过，不影响背景颜色
+fn expand_background(value: Value) -> Option<Vec<Declaration>> {
+    let components = match value {
+        // 只有 `center/cover` 这样的位置和尺寸
+        Value::List(parts, Separator::Slash) => vec![Value::List(parts, Separator::Slash)],
+        value => space_separated(value)?
+    };
+    let mut color = None;
+    for v in components {
+        match v {
+            v if color.is_none() && is_color(&v) => color = Some(v),
+            Value::Keyword(_) | Value::Url(_) | Value::List(_, Separator::Slash) => {}
+            v if is_length(&v) => {}
+            _ => return None
+        }
+    }
+    Some(vec![declaration("background-color", color.unwrap_or_else(|| keyword("transparent")))])
+}
+
+// font: [ <font-style> || <font-variant> || <font-weight> ]? <font-size> [ / <line-height> ]? <font-family>
+fn expand_font(value: Value) -> Option<Vec<Declaration>> {
+    // 逗号只能出现在 font-family 中，第一组包含了除其余字体名称外的全部内容
+    let (first, rest_families) = match value {
+        Value::List(mut groups, Separator::Comma) => {
+            let first = groups.remove(0);
+            (first, groups)
+        }
+        value => (value, Vec::new())
+    };
+
+    let mut components = space_separated(first)?.into_iter();
+    let (mut style, mut variant, mut weight) = (None, None, None);
+    let (size, line_height) = loop {
+        match components.next()? {
+            Value::List(parts, Separator::Slash) => match parts.as_slice() {
+                [size, line_height] if is_font_size(size) => break (size.clone(), Some(line_height.clone())),
+                _ => return None
+            },
+            v if is_font_size(&v) => break (v, None),
+            Value::Keyword(k) => match &*k {
+                "normal" => {}
+                "italic" | "oblique" if style.is_none() => style = Some(keyword(&k)),
+                "small-caps" if variant.is_none() => variant = Some(keyword(&k)),
+                "bold" | "bolder" | "lighter" if weight.is_none() => weight = Some(keyword(&k)),
+                _ => return None
+            },
+            Value::Number(n) if weight.is_none() && (1.0..=1000.0).contains(&n) => weight = Some(Value::Number(n)),
+            _ => return None
+        }
+    };
+
+    // 字体大小之后剩下的部分都是 font-family
+    let mut first_family: Vec<Value> = components.collect();
+    let first_family = match first_family.len() {
+        0 => return None,
+        1 => first_family.remove(0),
+        _ => Value::List(first_family, Separator::Space)
+    };
+    let mut families = vec![first_family];
+    families.extend(rest_families);
+    let family = match families.len() {
+        1 => families.remove(0),
+        _ => Value::List(families, Separator::Comma)
+    };
+
+    Some(vec![
+        declaration("font-style", style.unwrap_or_else(|| keyword("normal"))),
+        declaration("font-variant", variant.unwrap_or_else(|| keyword("normal"))),
+        declaration("font-weight", weight.unwrap_or_else(|| keyword("normal"))),
+        declaration("font-size", size),
+        declaration("line-height", line_height.unwrap_or_else(|| keyword("normal"))),
+        declaration("font-family", family),
+    ])
+}
+
+// flex: none | [ <flex-grow> <flex-shrink>? || <flex-basis> ]
+// 省略的伸缩系数为 1，省略的 flex-basis 为 0，none 和 auto 分别等同于 0 0 auto 和 1 1 auto
+fn expand_flex(value: Value) -> Option<Vec<Declaration>> {
+    let values = space_separated(value)?;
+    let (grow, shrink, basis) = match values.as_slice() {
+        [Value::Keyword(k)] if k == "none" => (Value::Number(0.0), Value::Number(0.0), keyword("auto")),
+        [Value::Keyword(k)] if k == "auto" => (Value::Number(1.0), Value::Number(1.0), keyword("auto")),
+        [Value::Keyword(k)] if k == "initial" => (Value::Number(0.0), Value::Number(1.0), keyword("auto")),
+        // 其它全局关键字作用于每个长属性
+        [Value::Keyword(k)] if k == "inherit" || k == "unset" || k == "revert" => (keyword(k), keyword(k), keyword(k)),
+        _ => {
+            let (mut grow, mut shrink, mut basis) = (None, None, None);
+            let mut values = values.into_iter().peekable();
+            while let Some(v) = values.next() {
+                match v {
+                    // flex-shrink 只能紧跟在 flex-grow 后面
+                    Value::Number(n) if grow.is_none() => {
+                        grow = Some(Value::Number(n));
+                        if let Some(&Value::Number(n)) = values.peek() {
+                            shrink = Some(Value::Number(n));
+                            values.next();
+                        }
+                    }
+                    v if basis.is_none() => basis = Some(v),
+                    _ => return None
+                }
+            }
+            (
+                grow.unwrap_or(Value::Number(1.0)),
+                shrink.unwrap_or(Value::Number(1.0)),
+                basis.unwrap_or(Value::Number(0.0)),
+            )
+        }
+    };
+    Some(vec![
+        declaration("flex-grow", grow),
+        declaration("flex-shrink", shrink),
+        declaration("flex-basis", basis),
+    ])
+}
+
+// flex-flow: <flex-direction> || <flex-wrap>，省略的部分使用初始值
+fn expand_flex_flow(value: Value) -> Option<Vec<Declaration>> {
+    let (mut direction, mut wrap) = (None, None);
+    for v in space_separated(value)? {
+        match v {
+            Value::Keyword(ref k) if direction.is_none() && ["row", "row-reverse", "column", "column-reverse"].contains(&&**k) => {
+                direction = Some(v)
+            }
+            Value::Keyword(ref k) if wrap.is_none() && ["nowrap", "wrap", "wrap-reverse"].contains(&&**k) => wrap = Some(v),
+            _ => return None
+        }
+    }
+    Some(vec![
+        declaration("flex-direction", direction.unwrap_or_else(|| keyword("row"))),
+        declaration("flex-wrap", wrap.unwrap_or_else(|| keyword("nowrap"))),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::{parser, types::{Color, Unit, Value}};
+
+    // 解析只有一条规则的样式表，返回展开后的声明
+    fn declarations(css: &str) -> Vec<(String, Value)> {
+        let stylesheet = parser::parse(format!("div {{ {} }}", css));
+        let rules = stylesheet.style_rules();
+        rules[0].declarations.iter().map(|d| (d.name.clone(), d.value.clone())).collect()
+    }
+
+    fn value(css: &str, name: &str) -> Option<Value> {
+        declarations(css).into_iter().find(|(n, _)| n == name).map(|(_, value)| value)
+    }
+
+    fn keyword(k: &str) -> Value {
+        Value::Keyword(k.to_string())
+    }
+
+    #[test]
+    fn background_skips_images_and_keeps_the_color() {
+        let red = Value::ColorValue(Color { r: 255, g: 0, b: 0, a: 255 });
+        assert_eq!(value("background: url(x.png) #ff0000", "background-color"), Some(red.clone()));
+        assert_eq!(value("background: #ff0000 url(\"x.png\") no-repeat fixed", "background-color"), Some(red.clone()));
+        assert_eq!(value("background: url(x.png) center / cover #ff0000", "background-color"), Some(red.clone()));
+        assert_eq!(value("background: url(x.png) 10px 50% repeat-x", "background-color"), Some(keyword("transparent")));
+        assert_eq!(value("background: center/cover", "background-color"), Some(keyword("transparent")));
+        // 两个颜色不合法，整个声明被丢弃
+        assert_eq!(value("background: #ff0000 #00ff00", "background-color"), None);
+    }
+
+    #[test]
+    fn font_size_before_slash() {
+        for (css, size) in [
+            ("font: small/1.5 serif", keyword("small")),
+            ("font: bold large/2 Arial", keyword("large")),
+            ("font: 80%/1.2 serif", Value::Length(80.0, Unit::Percent)),
+            ("font: 12px/1.5 serif", Value::Length(12.0, Unit::Px)),
+        ] {
+            assert_eq!(value(css, "font-size"), Some(size), "{}", css);
+            assert!(value(css, "line-height").is_some_and(|v| matches!(v, Value::Number(_))), "{}", css);
+        }
+        assert_eq!(value("font: bold large/2 Arial", "font-weight"), Some(keyword("bold")));
+        assert_eq!(value("font: normal/1.5 serif", "font-size"), None);
+    }
+}
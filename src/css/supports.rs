@@ -0,0 +1,47 @@
+// 特性查询，例如：`@supports (display: grid) and (not (display: inline-grid))`，按照引擎实际支持的属性和值计算
+use super::{parser, properties, shorthand, types::{UnparsedValue, Value}};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupportsCondition {
+    // `(<property>: <value>)`，记录属性名称和值的原始文本
+    Declaration(String, String),
+    Not(Box<SupportsCondition>),
+    And(Vec<SupportsCondition>),
+    Or(Vec<SupportsCondition>),
+    // 无法识别的条件，例如 `selector(a > b)`，永远不满足
+    Unknown(String),
+}
+
+impl SupportsCondition {
+    pub fn matches(&self) -> bool {
+        match self {
+            SupportsCondition::Declaration(name, value) => supports_declaration(name, value),
+            SupportsCondition::Not(condition) => !condition.matches(),
+            SupportsCondition::And(conditions) => conditions.iter().all(|c| c.matches()),
+            SupportsCondition::Or(conditions) => conditions.iter().any(|c| c.matches()),
+            SupportsCondition::Unknown(_) => false,
+        }
+    }
+}
+
+// 简写属性要求展开后的每个长属性都支持
+fn supports_declaration(name: &str, css: &str) -> bool {
+    if properties::is_custom(name) {
+        return true;
+    }
+    // 包含 var() 的值只要求属性是支持的
+    let value = if css.to_ascii_lowercase().contains("var(") {
+        Value::Unparsed(UnparsedValue { css: css.to_string(), shorthand: None })
+    } else {
+        match parser::parse_value(css.to_string()) {
+            Some(value) => value,
+            None => return false
+        }
+    };
+    match shorthand::expand(name, value) {
+        Some(declarations) => declarations.iter().all(|d| {
+            properties::lookup(&d.name).is_some() && properties::is_valid(&d.name, &d.value)
+        }),
+        None => false
+    }
+}
@@ -1,7 +1,121 @@
 
+use super::{media::{MediaEnvironment, MediaQueryList}, supports::SupportsCondition};
+
 #[derive(Debug)]
 pub struct Stylesheet {
-    pub rules: Vec<Rule>,
+    pub rules: Vec<CssRule>,
+    pub origin: Origin,
+}
+
+// 样式表中的规则：普通的样式规则，或者包含一组规则的条件规则
+#[derive(Debug)]
+pub enum CssRule {
+    Style(Rule),
+    Media(MediaRule),
+    Supports(SupportsRule),
+    Import(ImportRule),
+    FontFace(FontFaceRule),
+}
+
+// @supports <condition> { <rules> }
+#[derive(Debug)]
+pub struct SupportsRule {
+    pub condition: SupportsCondition,
+    pub rules: Vec<CssRule>,
+}
+
+// @import url("x.css") <queries>;
+// rules 为加载后的被导入样式表中的规则，还没有加载时为空
+#[derive(Debug)]
+pub struct ImportRule {
+    pub url: String,
+    pub queries: MediaQueryList,
+    pub rules: Vec<CssRule>,
+}
+
+// @font-face { font-family: <name>; src: <sources>; font-weight: <weight>; font-style: <style> }
+#[derive(Debug)]
+pub struct FontFaceRule {
+    pub family: String,
+    // 按照优先顺序排列，使用第一个可以加载的来源
+    pub sources: Vec<FontSource>,
+    pub weight: f32,
+    // normal、italic 或 oblique
+    pub style: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontSource {
+    // 字体文件的地址，以及 format() 中声明的格式
+    Url(String, Option<String>),
+    // 系统中已经安装的字体的名称
+    Local(String),
+}
+
+// @media <queries> { <rules> }
+#[derive(Debug)]
+pub struct MediaRule {
+    pub queries: MediaQueryList,
+    pub rules: Vec<CssRule>,
+}
+
+impl Stylesheet {
+    // 全部样式规则（包括条件规则中的），按照出现的顺序
+    pub fn style_rules(&self) -> Vec<&Rule> {
+        let mut rules = Vec::new();
+        collect_rules(&self.rules, &|_| true, &mut rules);
+        rules.into_iter().filter_map(|rule| match rule {
+            CssRule::Style(rule) => Some(rule),
+            _ => None
+        }).collect()
+    }
+
+    // 在给定的媒体环境下生效的样式规则，按照出现的顺序
+    pub fn effective_rules(&self, env: &MediaEnvironment) -> Vec<&Rule> {
+        self.effective_leaf_rules(env).into_iter().filter_map(|rule| match rule {
+            CssRule::Style(rule) => Some(rule),
+            _ => None
+        }).collect()
+    }
+
+    // 在给定的媒体环境下生效的 @font-face 规则，按照出现的顺序
+    pub fn font_faces(&self, env: &MediaEnvironment) -> Vec<&FontFaceRule> {
+        self.effective_leaf_rules(env).into_iter().filter_map(|rule| match rule {
+            CssRule::FontFace(font_face) => Some(font_face),
+            _ => None
+        }).collect()
+    }
+
+    fn effective_leaf_rules(&self, env: &MediaEnvironment) -> Vec<&CssRule> {
+        let mut rules = Vec::new();
+        collect_rules(&self.rules, &|rule| match rule {
+            CssRule::Media(media) => media.queries.matches(env),
+            CssRule::Supports(supports) => supports.condition.matches(),
+            CssRule::Import(import) => import.queries.matches(env),
+            _ => true
+        }, &mut rules);
+        rules
+    }
+}
+
+// 展开条件规则，收集其中的样式规则和 @font-face 规则，active 判断条件规则是否生效，不生效的条件规则中的规则全部忽略
+fn collect_rules<'a, F>(rules: &'a [CssRule], active: &F, result: &mut Vec<&'a CssRule>) where F: Fn(&CssRule) -> bool {
+    for rule in rules.iter().filter(|rule| active(rule)) {
+        match rule {
+            CssRule::Style(_) | CssRule::FontFace(_) => result.push(rule),
+            CssRule::Media(media) => collect_rules(&media.rules, active, result),
+            CssRule::Supports(supports) => collect_rules(&supports.rules, active, result),
+            CssRule::Import(import) => collect_rules(&import.rules, active, result),
+        }
+    }
+}
+
+// 样式表的来源：浏览器默认样式、用户样式、页面作者样式，按照普通声明的优先级从低到高排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
 }
 
 #[derive(Debug)]
@@ -10,13 +124,13 @@ pub struct Rule {
     pub declarations: Vec<Declaration>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Selector {
     Simple(SimpleSelector),
 }
 
 // css 选择器中用逗号分隔的，每一组代表一个 SimpleSelector，id、class、tag_name 是‘且’的关系
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SimpleSelector {
     pub id: Option<String>,
     pub class: Vec<String>,
@@ -39,13 +153,143 @@ impl Selector {
 pub struct Declaration {
     pub name: String,
     pub value: Value,
+    // 是否带有 !important
+    pub important: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Keyword(String),
     Length(f32, Unit),
+    Number(f32),
+    Str(String),
+    // 地址，例如：`url(bg.png)`
+    Url(String),
     ColorValue(Color),
+    // 多个值组成的列表，例如：`10px 20px`、`Arial, sans-serif`、`12px/1.5`
+    List(Vec<Value>, Separator),
+    // 自定义属性的值以及包含 var() 的值，保留原始文本，等到计算值阶段替换后再解析
+    Unparsed(UnparsedValue),
+    // 数学表达式：calc()、min()、max()、clamp()
+    Calc(Calc),
+}
+
+// 数学表达式，百分比要等到布局时知道包含块的尺寸后才能计算
+#[derive(Debug, Clone, PartialEq)]
+pub enum Calc {
+    Number(f32),
+    Length(f32, Unit),
+    Sum(Box<Calc>, Box<Calc>),
+    Difference(Box<Calc>, Box<Calc>),
+    Product(Box<Calc>, Box<Calc>),
+    Quotient(Box<Calc>, Box<Calc>),
+    Min(Vec<Calc>),
+    Max(Vec<Calc>),
+    Clamp(Box<Calc>, Box<Calc>, Box<Calc>),
+}
+
+// 表达式的类型，长度和百分比可以互相运算，但不能和数字相加减
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalcType {
+    Number,
+    Length,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnparsedValue {
+    pub css: String,
+    // 由简写属性展开得到时，记录简写属性的名称，替换后需要重新展开
+    pub shorthand: Option<String>,
+}
+
+impl Calc {
+    // 检查表达式的类型是否合法，例如 `10px + 1` 和 `10px * 10px` 都是不合法的
+    pub fn check_type(&self) -> Result<CalcType, String> {
+        match self {
+            Calc::Number(_) => Ok(CalcType::Number),
+            Calc::Length(..) => Ok(CalcType::Length),
+            Calc::Sum(a, b) | Calc::Difference(a, b) => {
+                let (a, b) = (a.check_type()?, b.check_type()?);
+                if a == b { Ok(a) } else { Err("Cannot add a number to a length".to_string()) }
+            }
+            Calc::Product(a, b) => match (a.check_type()?, b.check_type()?) {
+                (CalcType::Number, t) | (t, CalcType::Number) => Ok(t),
+                _ => Err("Cannot multiply two lengths".to_string())
+            },
+            // 除数只能是数字，数字在解析时就能算出值，为 0 时整个声明不合法
+            Calc::Quotient(a, b) => match b.check_type()? {
+                CalcType::Number if b.evaluate(None) == Some(0.0) => Err("Cannot divide by zero".to_string()),
+                CalcType::Number => a.check_type(),
+                _ => Err("Cannot divide by a length".to_string())
+            },
+            Calc::Min(args) | Calc::Max(args) => {
+                let types = args.iter().map(|arg| arg.check_type()).collect::<Result<Vec<_>, _>>()?;
+                match types.split_first() {
+                    Some((first, rest)) if rest.iter().all(|t| t == first) => Ok(*first),
+                    Some(_) => Err("Mismatched types in min() or max()".to_string()),
+                    None => Err("Empty min() or max()".to_string())
+                }
+            }
+            Calc::Clamp(min, value, max) => {
+                let t = value.check_type()?;
+                if min.check_type()? == t && max.check_type()? == t { Ok(t) } else { Err("Mismatched types in clamp()".to_string()) }
+            }
+        }
+    }
+
+    // 表达式中是否包含百分比
+    pub fn has_percentage(&self) -> bool {
+        match self {
+            Calc::Number(_) => false,
+            Calc::Length(_, unit) => *unit == Unit::Percent,
+            Calc::Sum(a, b) | Calc::Difference(a, b) | Calc::Product(a, b) | Calc::Quotient(a, b) => {
+                a.has_percentage() || b.has_percentage()
+            }
+            Calc::Min(args) | Calc::Max(args) => args.iter().any(Calc::has_percentage),
+            Calc::Clamp(a, b, c) => a.has_percentage() || b.has_percentage() || c.has_percentage(),
+        }
+    }
+
+    // 把表达式中的每个长度用 f 转换，例如把 em 转换为 px
+    pub fn map_lengths<F>(&self, f: &F) -> Calc where F: Fn(f32, &Unit) -> Calc {
+        let map = |c: &Calc| Box::new(c.map_lengths(f));
+        match self {
+            Calc::Number(n) => Calc::Number(*n),
+            Calc::Length(v, unit) => f(*v, unit),
+            Calc::Sum(a, b) => Calc::Sum(map(a), map(b)),
+            Calc::Difference(a, b) => Calc::Difference(map(a), map(b)),
+            Calc::Product(a, b) => Calc::Product(map(a), map(b)),
+            Calc::Quotient(a, b) => Calc::Quotient(map(a), map(b)),
+            Calc::Min(args) => Calc::Min(args.iter().map(|arg| arg.map_lengths(f)).collect()),
+            Calc::Max(args) => Calc::Max(args.iter().map(|arg| arg.map_lengths(f)).collect()),
+            Calc::Clamp(a, b, c) => Calc::Clamp(map(a), map(b), map(c)),
+        }
+    }
+
+    // 计算表达式的值（长度以 px 为单位），百分比相对于 basis，basis 为 None 时无法计算百分比
+    pub fn evaluate(&self, basis: Option<f32>) -> Option<f32> {
+        Some(match self {
+            Calc::Number(n) => *n,
+            Calc::Length(v, Unit::Px) => *v,
+            Calc::Length(v, Unit::Percent) => basis? * v / 100.0,
+            Calc::Length(..) => return None,
+            Calc::Sum(a, b) => a.evaluate(basis)? + b.evaluate(basis)?,
+            Calc::Difference(a, b) => a.evaluate(basis)? - b.evaluate(basis)?,
+            Calc::Product(a, b) => a.evaluate(basis)? * b.evaluate(basis)?,
+            Calc::Quotient(a, b) => match b.evaluate(basis)? {
+                0.0 => return None,
+                divisor => a.evaluate(basis)? / divisor,
+            },
+            Calc::Min(args) => args.iter().map(|arg| arg.evaluate(basis)).collect::<Option<Vec<_>>>()?
+                .into_iter().fold(f32::INFINITY, f32::min),
+            Calc::Max(args) => args.iter().map(|arg| arg.evaluate(basis)).collect::<Option<Vec<_>>>()?
+                .into_iter().fold(f32::NEG_INFINITY, f32::max),
+            // clamp(MIN, VAL, MAX) 等同于 max(MIN, min(VAL, MAX))
+            Calc::Clamp(min, value, max) => {
+                min.evaluate(basis)?.max(value.evaluate(basis)?.min(max.evaluate(basis)?))
+            }
+        })
+    }
 }
 
 impl Value {
@@ -57,9 +301,25 @@ impl Value {
     }
 }
 
+// 列表中各个值之间的分隔符
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Separator {
+    Space,
+    Comma,
+    Slash,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Unit {
     Px,
+    Pt,
+    Em,
+    Rem,
+    Percent,
+    // 分辨率单位，只用于媒体查询
+    Dpi,
+    Dpcm,
+    Dppx,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -0,0 +1,8 @@
+// 浏览器默认样式表，在层叠中优先级最低（!important 声明除外）
+use super::{parser, types};
+
+const USER_AGENT_CSS: &str = include_str!("user_agent.css");
+
+pub fn stylesheet() -> types::Stylesheet {
+    parser::parse_with_origin(USER_AGENT_CSS.to_string(), types::Origin::UserAgent)
+}
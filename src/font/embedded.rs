@@ -0,0 +1,72 @@
+// 嵌入的位图字体，所有字符等宽，不需要读取任何文件
+use std::{collections::HashMap, sync::OnceLock};
+
+use super::raster::{Outline, Point};
+
+const SOURCE: &str = include_str!("embedded.txt");
+
+// 字形的网格：每个字符 5 列 10 行，字号相当于 10 行的高度
+pub const COLUMNS: usize = 5;
+pub const ROWS: usize = 10;
+pub const UNITS_PER_EM: f32 = 10.0;
+// 基线在第 8 行的下面，字符的宽度比字形多 1 列作为字间距，单位为网格的行和列
+pub const ASCENT: f32 = 8.0;
+pub const DESCENT: f32 = 2.0;
+pub const LINE_GAP: f32 = 2.0;
+pub const ADVANCE: f32 = 6.0;
+
+// 每行一个位掩码，最高位为最左边的一列
+pub type Glyph = [u8; ROWS];
+
+// 字体中没有的字符画成一个方框
+const MISSING: Glyph = [0b00000, 0b11111, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11111, 0b00000, 0b00000];
+
+pub fn has_glyph(c: char) -> bool {
+    glyphs().contains_key(&c)
+}
+
+pub fn glyph(c: char) -> &'static Glyph {
+    glyphs().get(&c).unwrap_or(&MISSING)
+}
+
+// 每个被填充的格子是一个正方形的轮廓，y 向上，基线为 0
+pub fn outline(c: char) -> Outline {
+    let glyph = glyph(c);
+    let mut outline = Vec::new();
+    for (row, bits) in glyph.iter().enumerate() {
+        for column in 0..COLUMNS {
+            if bits & (1 << (COLUMNS - 1 - column)) == 0 {
+                continue;
+            }
+            let (left, right) = (column as f32, column as f32 + 1.0);
+            let (top, bottom) = (ASCENT - row as f32, ASCENT - row as f32 - 1.0);
+            outline.push([(left, top), (right, top), (right, bottom), (left, bottom)]
+                .into_iter()
+                .map(|(x, y)| Point { x, y, on_curve: true })
+                .collect());
+        }
+    }
+    outline
+}
+
+fn glyphs() -> &'static HashMap<char, Glyph> {
+    static GLYPHS: OnceLock<HashMap<char, Glyph>> = OnceLock::new();
+    GLYPHS.get_or_init(|| parse(SOURCE))
+}
+
+// 格式见 embedded.txt 的开头
+fn parse(source: &str) -> HashMap<char, Glyph> {
+    let mut lines = source.lines().filter(|line| !line.is_empty() && !line.starts_with("# "));
+    let mut glyphs = HashMap::new();
+    while let Some(code) = lines.next() {
+        let code = u32::from_str_radix(code.trim_start_matches("0x"), 16).expect("invalid character code");
+        let mut glyph = [0; ROWS];
+        for row in glyph.iter_mut() {
+            let line = lines.next().expect("incomplete glyph");
+            assert!(line.len() == COLUMNS, "invalid glyph row: {}", line);
+            *row = line.chars().fold(0, |bits, c| bits << 1 | (c == '#') as u8);
+        }
+        glyphs.insert(char::from_u32(code).expect("invalid character code"), glyph);
+    }
+    glyphs
+}
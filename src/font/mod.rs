@@ -0,0 +1,333 @@
+// 字体：加载字体文件，按照 font-family、font-weight、font-style 选择字体，测量文字的尺寸，以及把文字光栅化为每个像素的覆盖率
+use std::{fmt, fs, path::Path, sync::Arc};
+
+use crate::{
+    css::{media::MediaEnvironment, types::{FontFaceRule, FontSource, Stylesheet}},
+    style::types::{ComputedStyle, FontStyle},
+};
+
+use self::{raster::Outline, truetype::TrueType};
+
+pub mod embedded;
+pub mod raster;
+pub mod truetype;
+#[cfg(test)]
+pub mod testing;
+
+// 合成粗体时轮廓向右复制的距离，合成斜体时每升高一个单位向右偏移的距离，都相对于字号
+const BOLD_OFFSET: f32 = 0.06;
+const OBLIQUE_SKEW: f32 = 0.2;
+// 字重不低于这个值时视为粗体
+const BOLD_WEIGHT: f32 = 600.0;
+// 字体中没有的字符使用编号为 0 的字形：TrueType 字体的 .notdef，嵌入字体的方框
+const MISSING_GLYPH: u32 = 0;
+// 通用字体族都使用嵌入的字体
+const GENERIC_FAMILIES: [&str; 6] = ["serif", "sans-serif", "monospace", "cursive", "fantasy", "system-ui"];
+
+// 一个字体文件，以及 @font-face 为它声明的字体族、字重和样式
+pub struct FontFace {
+    pub family: String,
+    pub weight: f32,
+    pub style: FontStyle,
+    data: Arc<FaceData>,
+}
+
+enum FaceData {
+    Embedded,
+    TrueType(TrueType),
+}
+
+// 文字使用的字体：按照 font-family 的顺序匹配到的字体，每个字符使用第一个包含它的字体
+#[derive(Debug, Clone)]
+pub struct Font {
+    // 最后一个总是嵌入的字体
+    faces: Arc<[Arc<FontFace>]>,
+    pub size: f32,
+    pub weight: f32,
+    pub style: FontStyle,
+}
+
+// 一行文字中的一个字形，x 为相对于文字开头的位置
+struct PositionedGlyph<'a> {
+    face: &'a FontFace,
+    glyph: u32,
+    x: f32,
+}
+
+// 全部可用的字体
+pub struct FontCollection {
+    faces: Vec<Arc<FontFace>>,
+    embedded: Arc<FontFace>,
+}
+
+impl FontFace {
+    pub fn embedded() -> FontFace {
+        FontFace { family: "embedded".to_string(), weight: 400.0, style: FontStyle::Normal, data: Arc::new(FaceData::Embedded) }
+    }
+
+    // 从文件加载 TrueType 或 OpenType 字体
+    pub fn load(path: &Path, family: &str, weight: f32, style: FontStyle) -> Result<FontFace, String> {
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let font = TrueType::parse(data).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(FontFace { family: family.to_string(), weight, style, data: Arc::new(FaceData::TrueType(font)) })
+    }
+
+    // 以下的尺寸单位都是字体单位，除以 units_per_em 后为相对于字号的比例
+    fn units_per_em(&self) -> f32 {
+        match *self.data {
+            FaceData::Embedded => embedded::UNITS_PER_EM,
+            FaceData::TrueType(ref font) => font.units_per_em,
+        }
+    }
+
+    fn ascent(&self) -> f32 {
+        match *self.data {
+            FaceData::Embedded => embedded::ASCENT,
+            FaceData::TrueType(ref font) => font.ascent,
+        }
+    }
+
+    fn descent(&self) -> f32 {
+        match *self.data {
+            FaceData::Embedded => embedded::DESCENT,
+            FaceData::TrueType(ref font) => font.descent,
+        }
+    }
+
+    fn line_gap(&self) -> f32 {
+        match *self.data {
+            FaceData::Embedded => embedded::LINE_GAP,
+            FaceData::TrueType(ref font) => font.line_gap,
+        }
+    }
+
+    // 字体中没有这个字符时返回 None，嵌入字体的字形编号就是字符编码
+    fn glyph(&self, c: char) -> Option<u32> {
+        match *self.data {
+            FaceData::Embedded => embedded::has_glyph(c).then_some(c as u32),
+            FaceData::TrueType(ref font) => font.glyph_index(c).map(u32::from),
+        }
+    }
+
+    fn advance(&self, glyph: u32) -> f32 {
+        match *self.data {
+            FaceData::Embedded => embedded::ADVANCE,
+            FaceData::TrueType(ref font) => font.advance(glyph as u16),
+        }
+    }
+
+    fn kerning(&self, left: u32, right: u32) -> f32 {
+        match *self.data {
+            FaceData::Embedded => 0.0,
+            FaceData::TrueType(ref font) => font.kerning(left as u16, right as u16),
+        }
+    }
+
+    fn outline(&self, glyph: u32) -> Outline {
+        match *self.data {
+            FaceData::Embedded => embedded::outline(char::from_u32(glyph).unwrap_or('\0')),
+            FaceData::TrueType(ref font) => font.outline(glyph as u16),
+        }
+    }
+}
+
+impl fmt::Debug for FontFace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FontFace({:?}, {}, {:?})", self.family, self.weight, self.style)
+    }
+}
+
+impl Font {
+    // 第一个字体决定了行高和基线的位置
+    fn primary(&self) -> &FontFace {
+        &self.faces[0]
+    }
+
+    fn scale(&self, face: &FontFace) -> f32 {
+        self.size / face.units_per_em()
+    }
+
+    // 基线以上的高度
+    pub fn ascent(&self) -> f32 {
+        self.primary().ascent() * self.scale(self.primary())
+    }
+
+    // 基线以下的高度
+    pub fn descent(&self) -> f32 {
+        self.primary().descent() * self.scale(self.primary())
+    }
+
+    // line-height: normal 时的行高
+    pub fn normal_line_height(&self) -> f32 {
+        let primary = self.primary();
+        (primary.ascent() + primary.descent() + primary.line_gap()) * self.scale(primary)
+    }
+
+    // 是否有字体包含这个字符
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.faces.iter().any(|face| face.glyph(c).is_some())
+    }
+
+    // 文字的宽度，包括字距调整
+    pub fn measure(&self, text: &str) -> f32 {
+        self.shape(text).1
+    }
+
+    // 确定每个字符使用的字体和字形，以及它们的位置，返回全部字形和文字的总宽度
+    fn shape(&self, text: &str) -> (Vec<PositionedGlyph<'_>>, f32) {
+        let mut glyphs: Vec<PositionedGlyph> = Vec::new();
+        let mut x = 0.0;
+        for c in text.chars() {
+            let (face, glyph) = self.faces.iter()
+                .find_map(|face| face.glyph(c).map(|glyph| (&**face, glyph)))
+                .unwrap_or((self.primary(), MISSING_GLYPH));
+            // 相邻的两个字符使用同一个字体时才调整字距
+            if let Some(previous) = glyphs.last() {
+                if std::ptr::eq(previous.face, face) {
+                    x += face.kerning(previous.glyph, glyph) * self.scale(face);
+                }
+            }
+            glyphs.push(PositionedGlyph { face, glyph, x });
+            x += face.advance(glyph) * self.scale(face);
+        }
+        (glyphs, x)
+    }
+
+    // 光栅化从 (x, baseline) 开始的一行文字，对每个被覆盖的像素调用 plot(x, y, 覆盖率)
+    pub fn rasterize<F>(&self, text: &str, x: f32, baseline: f32, mut plot: F) where F: FnMut(i32, i32, f32) {
+        for positioned in self.shape(text).0 {
+            let face = positioned.face;
+            let scale = self.scale(face);
+            let mut outline = face.outline(positioned.glyph);
+            if outline.is_empty() {
+                continue;
+            }
+
+            // 字体本身不是粗体或斜体时由程序合成：粗体把轮廓向右复制一份，斜体把轮廓倾斜
+            if self.weight >= BOLD_WEIGHT && face.weight < BOLD_WEIGHT {
+                let offset = BOLD_OFFSET * face.units_per_em();
+                let copy: Outline = outline.iter()
+                    .map(|contour| contour.iter().map(|&p| raster::Point { x: p.x + offset, ..p }).collect())
+                    .collect();
+                outline.extend(copy);
+            }
+            let skew = if self.style != FontStyle::Normal && face.style == FontStyle::Normal { OBLIQUE_SKEW } else { 0.0 };
+
+            let origin = x + positioned.x;
+            raster::fill(&outline, |ux, uy| (origin + (ux + uy * skew) * scale, baseline - uy * scale), &mut plot);
+        }
+    }
+}
+
+impl Default for FontCollection {
+    fn default() -> FontCollection {
+        FontCollection { faces: Vec::new(), embedded: Arc::new(FontFace::embedded()) }
+    }
+}
+
+impl FontCollection {
+    // 只包含嵌入的字体
+    pub fn new() -> FontCollection {
+        FontCollection::default()
+    }
+
+    pub fn add(&mut self, face: FontFace) {
+        self.faces.push(Arc::new(face));
+    }
+
+    // 加载样式表中在 env 下生效的 @font-face，无法加载的字体会被忽略
+    pub fn load_font_faces(&mut self, stylesheets: &[Stylesheet], env: &MediaEnvironment) {
+        for rule in stylesheets.iter().flat_map(|stylesheet| stylesheet.font_faces(env)) {
+            match self.load_font_face(rule) {
+                Ok(face) => self.add(face),
+                Err(e) => eprintln!("warning: failed to load font \"{}\": {}", rule.family, e)
+            }
+        }
+    }
+
+    // 使用 src 中第一个可以加载的来源
+    fn load_font_face(&self, rule: &FontFaceRule) -> Result<FontFace, String> {
+        let style = match &*rule.style {
+            "italic" => FontStyle::Italic,
+            "oblique" => FontStyle::Oblique,
+            _ => FontStyle::Normal,
+        };
+        let mut errors = Vec::new();
+        for source in &rule.sources {
+            match source {
+                // 只支持 TrueType 格式的轮廓，WOFF 等格式会被跳过
+                FontSource::Url(_, Some(format)) if !["truetype", "opentype"].contains(&&*format.to_ascii_lowercase()) => {
+                    errors.push(format!("unsupported format \"{}\"", format));
+                }
+                FontSource::Url(url, _) => match FontFace::load(Path::new(url), &rule.family, rule.weight, style) {
+                    Ok(face) => return Ok(face),
+                    Err(e) => errors.push(e),
+                },
+                // local() 使用已经加载的同名字体
+                FontSource::Local(name) => match self.faces.iter().find(|face| face.family.eq_ignore_ascii_case(name)) {
+                    Some(face) => {
+                        return Ok(FontFace { family: rule.family.clone(), weight: rule.weight, style, data: face.data.clone() });
+                    }
+                    None => errors.push(format!("no local font \"{}\"", name)),
+                },
+            }
+        }
+        Err(errors.join(", "))
+    }
+
+    // 按照 font-family 列表中的顺序为每个字体族选择最接近 font-weight 和 font-style 的字体
+    pub fn font(&self, style: &ComputedStyle) -> Font {
+        let mut faces: Vec<Arc<FontFace>> = Vec::new();
+        for family in &style.font_family {
+            let face = self.match_face(family, style.font_weight, style.font_style).or_else(|| {
+                GENERIC_FAMILIES.iter().any(|generic| family.eq_ignore_ascii_case(generic)).then(|| self.embedded.clone())
+            });
+            if let Some(face) = face {
+                if !faces.iter().any(|f| Arc::ptr_eq(f, &face)) {
+                    faces.push(face);
+                }
+            }
+        }
+        if !faces.iter().any(|f| Arc::ptr_eq(f, &self.embedded)) {
+            faces.push(self.embedded.clone());
+        }
+        Font { faces: faces.into(), size: style.font_size, weight: style.font_weight, style: style.font_style }
+    }
+
+    // 先按照样式筛选，再选择字重最接近的，字体族中没有任何字体时返回 None
+    fn match_face(&self, family: &str, weight: f32, style: FontStyle) -> Option<Arc<FontFace>> {
+        let mut candidates: Vec<&Arc<FontFace>> = self.faces.iter().filter(|face| face.family.eq_ignore_ascii_case(family)).collect();
+        let preference = match style {
+            FontStyle::Italic => [FontStyle::Italic, FontStyle::Oblique, FontStyle::Normal],
+            FontStyle::Oblique => [FontStyle::Oblique, FontStyle::Italic, FontStyle::Normal],
+            FontStyle::Normal => [FontStyle::Normal, FontStyle::Oblique, FontStyle::Italic],
+        };
+        let style = preference.into_iter().find(|&style| candidates.iter().any(|face| face.style == style))?;
+        candidates.retain(|face| face.style == style);
+        candidates.into_iter()
+            .min_by(|a, b| weight_rank(weight, a.weight).total_cmp(&weight_rank(weight, b.weight)))
+            .cloned()
+    }
+}
+
+// 字重的匹配顺序，值越小越优先：
+// 期望的字重在 400 到 500 之间时，先找它到 500 之间更重的，再找更轻的，最后找 500 以上的；
+// 小于 400 时先找更轻的，再找更重的；大于 500 时先找更重的，再找更轻的
+fn weight_rank(desired: f32, available: f32) -> f32 {
+    const LATER: f32 = 1000.0;
+    if (400.0..=500.0).contains(&desired) {
+        if available >= desired && available <= 500.0 {
+            available - desired
+        } else if available < desired {
+            LATER + desired - available
+        } else {
+            2.0 * LATER + available - 500.0
+        }
+    } else if desired < 400.0 {
+        if available <= desired { desired - available } else { LATER + available - desired }
+    } else if available >= desired {
+        available - desired
+    } else {
+        LATER + desired - available
+    }
+}
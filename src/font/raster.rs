@@ -0,0 +1,154 @@
+// 把字形的轮廓光栅化为每个像素的覆盖率，轮廓由直线和二次贝塞尔曲线组成，按照非零环绕规则填充
+
+// 纵向每个像素的采样次数，横向按照被覆盖的长度精确计算
+const SAMPLES: usize = 4;
+// 曲线拆分成直线时最多拆分的段数
+const MAX_CURVE_SEGMENTS: usize = 16;
+
+// 轮廓中的点，on_curve 为 false 的点是二次贝塞尔曲线的控制点，两个相邻控制点的中点隐含一个曲线上的点
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+    pub on_curve: bool,
+}
+
+// 字形的全部闭合轮廓
+pub type Outline = Vec<Vec<Point>>;
+
+// (x0, y0, x1, y1)
+type Line = (f32, f32, f32, f32);
+
+// transform 把轮廓中的点转换到画布坐标（y 向下），对每个被覆盖的像素调用 plot(x, y, 覆盖率)
+pub fn fill<T, F>(outline: &Outline, transform: T, plot: &mut F) where T: Fn(f32, f32) -> (f32, f32), F: FnMut(i32, i32, f32) {
+    let mut lines = Vec::new();
+    for contour in outline {
+        let points: Vec<Point> = contour.iter().map(|p| {
+            let (x, y) = transform(p.x, p.y);
+            Point { x, y, on_curve: p.on_curve }
+        }).collect();
+        flatten(&points, &mut lines);
+    }
+    if lines.is_empty() {
+        return;
+    }
+
+    let min_x = lines.iter().map(|l| l.0.min(l.2)).fold(f32::INFINITY, f32::min);
+    let max_x = lines.iter().map(|l| l.0.max(l.2)).fold(f32::NEG_INFINITY, f32::max);
+    let min_y = lines.iter().map(|l| l.1.min(l.3)).fold(f32::INFINITY, f32::min);
+    let max_y = lines.iter().map(|l| l.1.max(l.3)).fold(f32::NEG_INFINITY, f32::max);
+    let x0 = min_x.floor() as i32;
+    let width = (max_x.ceil() as i32 - x0) as usize;
+
+    let mut coverage = vec![0.0f32; width];
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    for py in min_y.floor() as i32..max_y.ceil() as i32 {
+        coverage.fill(0.0);
+        for sample in 0..SAMPLES {
+            let sy = py as f32 + (sample as f32 + 0.5) / SAMPLES as f32;
+            crossings.clear();
+            for &(ax, ay, bx, by) in &lines {
+                if (ay <= sy) != (by <= sy) {
+                    let t = (sy - ay) / (by - ay);
+                    crossings.push((ax + t * (bx - ax) - x0 as f32, if by > ay { 1 } else { -1 }));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            // 环绕数不为 0 的区间被填充
+            let mut winding = 0;
+            let mut start = 0.0;
+            for &(x, direction) in &crossings {
+                let previous = winding;
+                winding += direction;
+                if previous == 0 {
+                    start = x;
+                } else if winding == 0 {
+                    add_span(&mut coverage, start, x, 1.0 / SAMPLES as f32);
+                }
+            }
+        }
+        for (i, &c) in coverage.iter().enumerate() {
+            if c > 0.0 {
+                plot(x0 + i as i32, py, c.min(1.0));
+            }
+        }
+    }
+}
+
+// 把 [start, end) 被覆盖的长度累加到经过的每个像素
+fn add_span(coverage: &mut [f32], start: f32, end: f32, weight: f32) {
+    let start = start.max(0.0);
+    let end = end.min(coverage.len() as f32);
+    let mut x = start.floor() as usize;
+    while (x as f32) < end {
+        let left = start.max(x as f32);
+        let right = end.min(x as f32 + 1.0);
+        coverage[x] += (right - left) * weight;
+        x += 1;
+    }
+}
+
+// 把一个闭合轮廓中的曲线拆分为直线
+fn flatten(contour: &[Point], lines: &mut Vec<Line>) {
+    let n = contour.len();
+    if n < 2 {
+        return;
+    }
+    // 从一个曲线上的点开始，全部是控制点时从前两个点的中点开始
+    let (first, start) = match contour.iter().position(|p| p.on_curve) {
+        Some(i) => (i, (contour[i].x, contour[i].y)),
+        None => (0, midpoint(contour[0], contour[1])),
+    };
+
+    let mut current = start;
+    let mut control: Option<Point> = None;
+    for i in 1..=n {
+        let p = contour[(first + i) % n];
+        match (p.on_curve, control) {
+            (true, None) => {
+                lines.push((current.0, current.1, p.x, p.y));
+                current = (p.x, p.y);
+            }
+            (true, Some(c)) => {
+                quadratic(current, c, (p.x, p.y), lines);
+                current = (p.x, p.y);
+                control = None;
+            }
+            (false, None) => control = Some(p),
+            (false, Some(c)) => {
+                let mid = midpoint(c, p);
+                quadratic(current, c, mid, lines);
+                current = mid;
+                control = Some(p);
+            }
+        }
+    }
+    match control {
+        Some(c) => quadratic(current, c, start, lines),
+        None if current != start => lines.push((current.0, current.1, start.0, start.1)),
+        None => {}
+    }
+}
+
+fn midpoint(a: Point, b: Point) -> (f32, f32) {
+    ((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+}
+
+// 二次贝塞尔曲线拆分的段数取决于控制点偏离弦的距离
+fn quadratic(from: (f32, f32), control: Point, to: (f32, f32), lines: &mut Vec<Line>) {
+    let dx = control.x - (from.0 + to.0) / 2.0;
+    let dy = control.y - (from.1 + to.1) / 2.0;
+    let segments = ((dx * dx + dy * dy).sqrt().sqrt() * 2.0).ceil().clamp(1.0, MAX_CURVE_SEGMENTS as f32) as usize;
+    let mut previous = from;
+    for i in 1..=segments {
+        let t = i as f32 / segments as f32;
+        let u = 1.0 - t;
+        let point = (
+            u * u * from.0 + 2.0 * u * t * control.x + t * t * to.0,
+            u * u * from.1 + 2.0 * u * t * control.y + t * t * to.1,
+        );
+        lines.push((previous.0, previous.1, point.0, point.1));
+        previous = point;
+    }
+}
@@ -0,0 +1,59 @@
+// 测试使用的字体：在临时目录中生成一个只包含指定字符的 TrueType 字体，字形都没有轮廓
+use std::{path::Path, sync::Arc};
+
+use crate::style::types::FontStyle;
+
+use super::{Font, FontFace};
+
+// 生成字体文件并返回它的路径，每个字符的宽度为 advance / 1000 em，ascent 为 0.8 em，descent 为 0.2 em
+pub fn write_font(name: &str, ranges: &[(char, char)], advance: u16) -> String {
+    let be16 = |n: u16| n.to_be_bytes().to_vec();
+    let be32 = |n: u32| n.to_be_bytes().to_vec();
+    // 字形 0 是 .notdef，每个区间中的字符依次使用之后的字形
+    let glyphs = 1 + ranges.iter().map(|&(first, last)| last as u32 - first as u32 + 1).sum::<u32>();
+    let glyphs = u16::try_from(glyphs).expect("too many glyphs");
+
+    // head：units_per_em 在第 18 字节，loca 的格式在第 50 字节
+    let mut head = vec![0; 54];
+    head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+    // hhea：只有两个宽度，之后的字形使用最后一个
+    let mut hhea = vec![0; 36];
+    hhea[4..6].copy_from_slice(&800i16.to_be_bytes());
+    hhea[6..8].copy_from_slice(&(-200i16).to_be_bytes());
+    hhea[34..36].copy_from_slice(&2u16.to_be_bytes());
+    let maxp = [be32(0x5000), be16(glyphs)].concat();
+    let hmtx = [be16(advance), be16(0), be16(advance), be16(0)].concat();
+    // cmap：Windows Unicode 完整字符集的格式 12 子表，每个区间一个分组
+    let mut cmap = [be16(0), be16(1), be16(3), be16(10), be32(12)].concat();
+    cmap.extend([be16(12), be16(0), be32(16 + 12 * ranges.len() as u32), be32(0), be32(ranges.len() as u32)].concat());
+    let mut glyph = 1;
+    for &(first, last) in ranges {
+        cmap.extend([be32(first as u32), be32(last as u32), be32(glyph)].concat());
+        glyph += last as u32 - first as u32 + 1;
+    }
+    let loca = vec![0; 2 * (glyphs as usize + 1)];
+    let tables: [(&[u8; 4], Vec<u8>); 7] = [
+        (b"cmap", cmap), (b"glyf", Vec::new()), (b"head", head), (b"hhea", hhea),
+        (b"hmtx", hmtx), (b"loca", loca), (b"maxp", maxp),
+    ];
+
+    let mut font = [be32(0x00010000), be16(tables.len() as u16), vec![0; 6]].concat();
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in &tables {
+        font.extend([tag.to_vec(), be32(0), be32(offset as u32), be32(data.len() as u32)].concat());
+        offset += data.len();
+    }
+    for (_, data) in &tables {
+        font.extend(data);
+    }
+    let path = std::env::temp_dir().join(format!("test-font-{}-{}.ttf", std::process::id(), name));
+    std::fs::write(&path, font).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+// 使用生成的字体、字号为 10px 的字体，字体中没有的字符使用嵌入的字体
+pub fn font(name: &str, ranges: &[(char, char)], advance: u16) -> Font {
+    let path = write_font(name, ranges, advance);
+    let face = FontFace::load(Path::new(&path), name, 400.0, FontStyle::Normal).unwrap();
+    Font { faces: vec![Arc::new(face), Arc::new(FontFace::embedded())].into(), size: 10.0, weight: 400.0, style: FontStyle::Normal }
+}
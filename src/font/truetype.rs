@@ -0,0 +1,360 @@
+// 解析 TrueType 字体文件，以及使用 TrueType 轮廓的 OpenType 字体文件
+// 读取的表：head、hhea、maxp、hmtx、cmap、loca、glyf，以及可选的 kern（GPOS 中的字距调整不支持）
+use std::collections::HashMap;
+
+use super::raster::{Outline, Point};
+
+// 组合字形中的组件最多嵌套的层数
+const MAX_COMPONENT_DEPTH: usize = 8;
+
+pub struct TrueType {
+    data: Vec<u8>,
+    pub units_per_em: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub line_gap: f32,
+    num_glyphs: u16,
+    num_h_metrics: u16,
+    long_loca: bool,
+    hmtx: usize,
+    loca: usize,
+    glyf: usize,
+    // 字符编码到字形编号
+    cmap: HashMap<u32, u16>,
+    // (左边的字形, 右边的字形) 到字距调整的值
+    kerning: HashMap<(u16, u16), i16>,
+}
+
+impl TrueType {
+    pub fn parse(data: Vec<u8>) -> Result<TrueType, String> {
+        let reader = Reader(&data);
+        // 字体集合只使用其中的第一个字体
+        let start = match reader.tag(0)? {
+            b"ttcf" => reader.u32(12)? as usize,
+            _ => 0,
+        };
+        match reader.tag(start)? {
+            [0, 1, 0, 0] | b"true" => {}
+            b"OTTO" => return Err("OpenType fonts with CFF outlines are not supported".to_string()),
+            _ => return Err("not a TrueType font".to_string()),
+        }
+
+        let num_tables = reader.u16(start + 4)? as usize;
+        let mut tables = HashMap::new();
+        for i in 0..num_tables {
+            let record = start + 12 + 16 * i;
+            tables.insert(*reader.tag(record)?, reader.u32(record + 8)? as usize);
+        }
+        let table = |tag: &[u8; 4]| tables.get(tag).copied()
+            .ok_or_else(|| format!("missing `{}` table", String::from_utf8_lossy(tag)));
+
+        let head = table(b"head")?;
+        let hhea = table(b"hhea")?;
+        let maxp = table(b"maxp")?;
+        let cmap = parse_cmap(&reader, table(b"cmap")?)?;
+        let kerning = match tables.get(b"kern") {
+            Some(&kern) => parse_kern(&reader, kern).unwrap_or_default(),
+            None => HashMap::new(),
+        };
+
+        Ok(TrueType {
+            units_per_em: reader.u16(head + 18)? as f32,
+            long_loca: reader.i16(head + 50)? != 0,
+            ascent: reader.i16(hhea + 4)? as f32,
+            // hhea 中的 descender 为负数
+            descent: -(reader.i16(hhea + 6)? as f32),
+            line_gap: reader.i16(hhea + 8)? as f32,
+            num_h_metrics: reader.u16(hhea + 34)?,
+            num_glyphs: reader.u16(maxp + 4)?,
+            hmtx: table(b"hmtx")?,
+            loca: table(b"loca")?,
+            glyf: table(b"glyf")?,
+            cmap,
+            kerning,
+            data,
+        })
+    }
+
+    // 字体中没有这个字符时返回 None
+    pub fn glyph_index(&self, c: char) -> Option<u16> {
+        self.cmap.get(&(c as u32)).copied().filter(|&glyph| glyph != 0 && glyph < self.num_glyphs)
+    }
+
+    // 字形的宽度，单位为字体单位
+    pub fn advance(&self, glyph: u16) -> f32 {
+        let reader = Reader(&self.data);
+        // 超过 num_h_metrics 的字形使用最后一个宽度
+        let index = glyph.min(self.num_h_metrics.saturating_sub(1)) as usize;
+        reader.u16(self.hmtx + 4 * index).unwrap_or(0) as f32
+    }
+
+    pub fn kerning(&self, left: u16, right: u16) -> f32 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0) as f32
+    }
+
+    // 字形的轮廓，单位为字体单位，y 向上；数据损坏时返回空的轮廓
+    pub fn outline(&self, glyph: u16) -> Outline {
+        self.glyph_outline(glyph, 0).unwrap_or_default()
+    }
+
+    fn glyph_outline(&self, glyph: u16, depth: usize) -> Result<Outline, String> {
+        if glyph >= self.num_glyphs || depth > MAX_COMPONENT_DEPTH {
+            return Ok(Vec::new());
+        }
+        let reader = Reader(&self.data);
+        let (start, end) = if self.long_loca {
+            (reader.u32(self.loca + 4 * glyph as usize)? as usize, reader.u32(self.loca + 4 * glyph as usize + 4)? as usize)
+        } else {
+            (reader.u16(self.loca + 2 * glyph as usize)? as usize * 2, reader.u16(self.loca + 2 * glyph as usize + 2)? as usize * 2)
+        };
+        // 没有轮廓的字形，例如空格
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        let offset = self.glyf + start;
+        let contours = reader.i16(offset)?;
+        if contours >= 0 {
+            simple_outline(&reader, offset, contours as usize)
+        } else {
+            self.composite_outline(&reader, offset, depth)
+        }
+    }
+
+    // 组合字形由其他字形经过变换组成，例如带重音符号的字母
+    fn composite_outline(&self, reader: &Reader, offset: usize, depth: usize) -> Result<Outline, String> {
+        const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+        const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+        const WE_HAVE_A_SCALE: u16 = 0x0008;
+        const MORE_COMPONENTS: u16 = 0x0020;
+        const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+        const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+        let mut outline = Vec::new();
+        let mut pos = offset + 10;
+        loop {
+            let flags = reader.u16(pos)?;
+            let glyph = reader.u16(pos + 2)?;
+            pos += 4;
+            let (arg1, arg2) = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                pos += 4;
+                (reader.i16(pos - 4)? as f32, reader.i16(pos - 2)? as f32)
+            } else {
+                pos += 2;
+                (reader.u8(pos - 2)? as i8 as f32, reader.u8(pos - 1)? as i8 as f32)
+            };
+            // 参数是对齐的点的编号时不支持，不做偏移
+            let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 { (arg1, arg2) } else { (0.0, 0.0) };
+
+            // 变换矩阵 [a c; b d]
+            let (mut a, mut b, mut c, mut d) = (1.0, 0.0, 0.0, 1.0);
+            if flags & WE_HAVE_A_SCALE != 0 {
+                a = reader.f2dot14(pos)?;
+                d = a;
+                pos += 2;
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                a = reader.f2dot14(pos)?;
+                d = reader.f2dot14(pos + 2)?;
+                pos += 4;
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                a = reader.f2dot14(pos)?;
+                b = reader.f2dot14(pos + 2)?;
+                c = reader.f2dot14(pos + 4)?;
+                d = reader.f2dot14(pos + 6)?;
+                pos += 8;
+            }
+
+            for contour in self.glyph_outline(glyph, depth + 1)? {
+                outline.push(contour.into_iter().map(|p| Point {
+                    x: a * p.x + c * p.y + dx,
+                    y: b * p.x + d * p.y + dy,
+                    on_curve: p.on_curve,
+                }).collect());
+            }
+            if flags & MORE_COMPONENTS == 0 {
+                return Ok(outline);
+            }
+        }
+    }
+}
+
+fn simple_outline(reader: &Reader, offset: usize, contours: usize) -> Result<Outline, String> {
+    const ON_CURVE: u8 = 0x01;
+    const X_SHORT: u8 = 0x02;
+    const Y_SHORT: u8 = 0x04;
+    const REPEAT: u8 = 0x08;
+    const X_SAME_OR_POSITIVE: u8 = 0x10;
+    const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+    let mut ends = Vec::with_capacity(contours);
+    for i in 0..contours {
+        ends.push(reader.u16(offset + 10 + 2 * i)? as usize);
+    }
+    let points = ends.last().map_or(0, |&end| end + 1);
+    let instructions = reader.u16(offset + 10 + 2 * contours)? as usize;
+    let mut pos = offset + 12 + 2 * contours + instructions;
+
+    let mut flags = Vec::with_capacity(points);
+    while flags.len() < points {
+        let flag = reader.u8(pos)?;
+        pos += 1;
+        let mut count = 1;
+        if flag & REPEAT != 0 {
+            count += reader.u8(pos)? as usize;
+            pos += 1;
+        }
+        flags.extend(std::iter::repeat_n(flag, count));
+    }
+    flags.truncate(points);
+
+    // 坐标是相对于前一个点的差值，短格式为一个字节，符号由另一个标志决定
+    let mut read_coordinates = |short: u8, same_or_positive: u8| -> Result<Vec<f32>, String> {
+        let mut value = 0i32;
+        let mut result = Vec::with_capacity(points);
+        for &flag in &flags {
+            if flag & short != 0 {
+                let delta = reader.u8(pos)? as i32;
+                pos += 1;
+                value += if flag & same_or_positive != 0 { delta } else { -delta };
+            } else if flag & same_or_positive == 0 {
+                value += reader.i16(pos)? as i32;
+                pos += 2;
+            }
+            result.push(value as f32);
+        }
+        Ok(result)
+    };
+    let xs = read_coordinates(X_SHORT, X_SAME_OR_POSITIVE)?;
+    let ys = read_coordinates(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+    let mut outline = Vec::with_capacity(contours);
+    let mut start = 0;
+    for end in ends {
+        if end < start || end >= points {
+            return Err("invalid glyph contour".to_string());
+        }
+        outline.push((start..=end).map(|i| Point { x: xs[i], y: ys[i], on_curve: flags[i] & ON_CURVE != 0 }).collect());
+        start = end + 1;
+    }
+    Ok(outline)
+}
+
+// 优先使用 Unicode 完整字符集（格式 12），其次是基本多文种平面（格式 4）
+fn parse_cmap(reader: &Reader, cmap: usize) -> Result<HashMap<u32, u16>, String> {
+    let mut format4 = None;
+    let mut format12 = None;
+    for i in 0..reader.u16(cmap + 2)? as usize {
+        let record = cmap + 4 + 8 * i;
+        let platform = reader.u16(record)?;
+        let encoding = reader.u16(record + 2)?;
+        let subtable = cmap + reader.u32(record + 4)? as usize;
+        // 只使用 Unicode 编码的子表
+        if !(platform == 0 || platform == 3 && (encoding == 1 || encoding == 10)) {
+            continue;
+        }
+        match reader.u16(subtable)? {
+            4 => format4 = format4.or(Some(subtable)),
+            12 => format12 = format12.or(Some(subtable)),
+            _ => {}
+        }
+    }
+
+    let mut map = HashMap::new();
+    if let Some(subtable) = format12 {
+        for i in 0..reader.u32(subtable + 12)? as usize {
+            let group = subtable + 16 + 12 * i;
+            let (start, end, glyph) = (reader.u32(group)?, reader.u32(group + 4)?, reader.u32(group + 8)?);
+            for c in start..=end {
+                map.insert(c, (glyph + c - start) as u16);
+            }
+        }
+    } else if let Some(subtable) = format4 {
+        let segments = reader.u16(subtable + 6)? as usize / 2;
+        let end_codes = subtable + 14;
+        let start_codes = end_codes + 2 * segments + 2;
+        let deltas = start_codes + 2 * segments;
+        let range_offsets = deltas + 2 * segments;
+        for i in 0..segments {
+            let (start, end) = (reader.u16(start_codes + 2 * i)?, reader.u16(end_codes + 2 * i)?);
+            let delta = reader.u16(deltas + 2 * i)?;
+            let range_offset = reader.u16(range_offsets + 2 * i)? as usize;
+            for c in start..=end {
+                if c == 0xffff {
+                    break;
+                }
+                let glyph = if range_offset == 0 {
+                    c.wrapping_add(delta)
+                } else {
+                    // 偏移量相对于 range_offset 自身的位置
+                    let address = range_offsets + 2 * i + range_offset + 2 * (c - start) as usize;
+                    match reader.u16(address)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                };
+                if glyph != 0 {
+                    map.insert(c as u32, glyph);
+                }
+            }
+        }
+    } else {
+        return Err("no Unicode character map".to_string());
+    }
+    Ok(map)
+}
+
+// 只支持水平方向的格式 0 子表
+fn parse_kern(reader: &Reader, kern: usize) -> Result<HashMap<(u16, u16), i16>, String> {
+    let mut kerning = HashMap::new();
+    if reader.u16(kern)? != 0 {
+        return Ok(kerning);
+    }
+    let mut subtable = kern + 4;
+    for _ in 0..reader.u16(kern + 2)? {
+        let length = reader.u16(subtable + 2)? as usize;
+        let coverage = reader.u16(subtable + 4)?;
+        if coverage >> 8 == 0 && coverage & 0x1 != 0 {
+            for i in 0..reader.u16(subtable + 6)? as usize {
+                let pair = subtable + 14 + 6 * i;
+                kerning.insert((reader.u16(pair)?, reader.u16(pair + 2)?), reader.i16(pair + 4)?);
+            }
+        }
+        subtable += length;
+    }
+    Ok(kerning)
+}
+
+// 按照大端字节序读取数据，越界时返回错误
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<&'a [u8; N], String> {
+        self.0.get(offset..offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "unexpected end of font data".to_string())
+    }
+
+    fn tag(&self, offset: usize) -> Result<&'a [u8; 4], String> {
+        self.bytes(offset)
+    }
+
+    fn u8(&self, offset: usize) -> Result<u8, String> {
+        Ok(self.bytes::<1>(offset)?[0])
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(*self.bytes(offset)?))
+    }
+
+    fn i16(&self, offset: usize) -> Result<i16, String> {
+        Ok(i16::from_be_bytes(*self.bytes(offset)?))
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(*self.bytes(offset)?))
+    }
+
+    // 2.14 定点数
+    fn f2dot14(&self, offset: usize) -> Result<f32, String> {
+        Ok(self.i16(offset)? as f32 / 16384.0)
+    }
+}
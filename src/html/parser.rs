@@ -48,7 +48,7 @@ impl HTMLParser {
             }
             nodes.push(self.parse_node());
         }
-        return nodes;
+        nodes
     }
 
     // 解析一个节点
@@ -76,15 +76,12 @@ impl HTMLParser {
         assert!(self.parse_tag_name() == tag_name);
         assert!(self.consume_char() == '>');
 
-        return types::elem(tag_name, attrs, children)
+        types::elem(tag_name, attrs, children)
     }
 
     // 解析标签或属性名称
     fn parse_tag_name(&mut self) -> String {
-        self.consume_while(|c| match c {
-            'a'..='z' | 'A'..='Z' | '0'..='9' => true,
-            _ => false
-        })
+        self.consume_while(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9'))
     }
 
     // 解析一个文本
@@ -103,7 +100,7 @@ impl HTMLParser {
             let (name, value) = self.parse_attr();
             attributes.insert(name, value);
         }
-        return attributes;
+        attributes
     }
 
     // 解析单个属性，例如：name="value"
@@ -111,7 +108,7 @@ impl HTMLParser {
         let name = self.parse_tag_name();
         assert!(self.consume_char() == '=');
         let value = self.parse_attr_value();
-        return (name, value);
+        (name, value)
     }
 
     // 解析单个值，例如："value"
@@ -120,6 +117,6 @@ impl HTMLParser {
         assert!(open_quote == '"' || open_quote == '\'');
         let value = self.consume_while(|c| c != open_quote);
         assert!(self.consume_char() == open_quote);
-        return value;
+        value
     }
 }
\ No newline at end of file
@@ -1,13 +1,20 @@
-use std::collections::{HashMap, HashSet};
+use std::{collections::{HashMap, HashSet}, sync::atomic::{AtomicBool, AtomicUsize, Ordering}};
 
 pub type AttrMap = HashMap<String, String>;
 
+// 节点的唯一标识，创建节点时分配
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub usize);
+
 #[derive(Debug)]
 pub struct Node {
-    // 子节点
-    pub children: Vec<Node>,
+    pub id: NodeId,
+    // 子节点，只能通过 insert_child 和 remove_child 修改
+    children: Vec<Node>,
     // 节点类型
     pub node_type: NodeType,
+    // 属性修改后需要重新计算样式，增量计算样式时读取并清除
+    style_dirty: AtomicBool,
 }
 
 #[derive(Debug)]
@@ -19,10 +26,19 @@ pub enum NodeType {
 #[derive(Debug)]
 pub struct ElementData {
     pub tag_name: String,
-    pub attributes: AttrMap
+    // 属性只能通过 Node::set_attribute 和 Node::remove_attribute 修改，这样才能标记需要重新计算样式
+    attributes: AttrMap
 }
 
 impl ElementData {
+    pub fn new(tag_name: String, attributes: AttrMap) -> ElementData {
+        ElementData { tag_name, attributes }
+    }
+
+    pub fn attributes(&self) -> &AttrMap {
+        &self.attributes
+    }
+
     pub fn id(&self) -> Option<&String> {
         self.attributes.get("id")
     }
@@ -35,16 +51,74 @@ impl ElementData {
     }
 }
 
+impl Node {
+    // 修改属性，需要通过这个方法修改，增量计算样式时才能知道哪些元素需要重新计算
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        if let NodeType::Element(ref mut elem) = self.node_type {
+            elem.attributes.insert(name.to_string(), value.to_string());
+            self.style_dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) {
+        if let NodeType::Element(ref mut elem) = self.node_type {
+            if elem.attributes.remove(name).is_some() {
+                self.style_dirty.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    // 新插入的节点还没有样式，总是会重新计算，不需要标记
+    pub fn insert_child(&mut self, index: usize, child: Node) {
+        self.children.insert(index, child);
+    }
+
+    pub fn remove_child(&mut self, index: usize) -> Node {
+        self.children.remove(index)
+    }
+
+    // 返回节点的属性是否被修改过，并清除标记
+    pub fn take_style_dirty(&self) -> bool {
+        self.style_dirty.swap(false, Ordering::Relaxed)
+    }
+
+    // 按照先序遍历查找节点
+    pub fn find(&self, id: NodeId) -> Option<&Node> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+
+    pub fn find_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|child| child.find_mut(id))
+    }
+}
+
+fn next_id() -> NodeId {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    NodeId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+}
+
 pub fn text(data: String) -> Node {
-    Node { children: Vec::new(), node_type: NodeType::Text(data) }
+    Node { id: next_id(), children: Vec::new(), node_type: NodeType::Text(data), style_dirty: AtomicBool::new(false) }
 }
 
 pub fn elem(name: String, attrs: AttrMap, children: Vec<Node>) -> Node {
     Node {
+        id: next_id(),
         children, 
         node_type: NodeType::Element(ElementData { 
             tag_name: name, 
             attributes: attrs,
-        })
+        }),
+        style_dirty: AtomicBool::new(false),
     }
 }
\ No newline at end of file
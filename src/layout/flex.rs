@@ -0,0 +1,575 @@
+// 弹性布局（CSS Flexible Box Layout Module Level 1 第 9 节）：弹性容器的子盒子按照 order 排列在主轴上，放不下时换行
+// 行中剩余的空间按照 flex-grow 分配给项目，不足的空间按照 flex-shrink 从项目中收缩，再按照 justify-content 和 align-* 对齐
+use std::ops::Range;
+
+use crate::{
+    font::FontCollection,
+    style::types::{AlignItems, BoxSizing, ComputedStyle, ContentDistribution, Direction, Display, FlexBasis, FlexDirection, FlexWrap, LengthPercentageAuto, Overflow, Position},
+};
+
+use super::{inline, AnonymousBlock, BlockNode, CollapsedMargin, Dimensions, EdgeSizes, FloatContext, LayoutBox, Rect};
+
+// 布局弹性项目时需要的弹性容器的信息
+struct Container<'s> {
+    style: &'s ComputedStyle,
+    // 匿名的弹性项目使用初始的样式
+    initial: ComputedStyle,
+    // 主轴是否为水平方向
+    row: bool,
+    // 只有一行时交叉轴上的尺寸就是容器的尺寸
+    single_line: bool,
+    // 内容区，高度取决于内容时 height 为 None
+    x: f32,
+    y: f32,
+    width: f32,
+    height: Option<f32>,
+}
+
+// 一个弹性项目在布局过程中的尺寸，除了外边距以外都是内容区的尺寸
+struct FlexItem {
+    // 在弹性容器的子盒子中的位置
+    index: usize,
+    grow: f32,
+    shrink: f32,
+    // 主轴和交叉轴上两侧的外边距，水平方向为左右，垂直方向为上下，auto 为 None
+    main_margins: (Option<f32>, Option<f32>),
+    cross_margins: (Option<f32>, Option<f32>),
+    // 主轴和交叉轴上两侧的边框和内边距之和
+    main_edges: f32,
+    cross_edges: f32,
+    // flex-basis 决定的基础尺寸，以及限制在最小尺寸和最大尺寸之间的假想尺寸
+    base: f32,
+    hypothetical: f32,
+    min_main: f32,
+    max_main: f32,
+    // 分配剩余空间之后的尺寸，frozen 表示已经确定
+    target: f32,
+    frozen: bool,
+    // 交叉轴上的尺寸，横向的项目在布局之后才知道，definite_cross 为布局之前确定的尺寸
+    cross: f32,
+    definite_cross: Option<f32>,
+    min_cross: f32,
+    max_cross: f32,
+    align: AlignItems,
+    // 交叉轴上的尺寸是否拉伸到行的尺寸
+    stretched: bool,
+    // 外边距盒的顶部到第一条基线的距离
+    baseline: f32,
+}
+
+impl<'a> LayoutBox<'a> {
+    pub(super) fn is_flex_container(&self) -> bool {
+        matches!(self.box_type, BlockNode(node) if matches!(node.style.display, Display::Flex | Display::InlineFlex))
+    }
+
+    // 按照弹性布局排列子盒子，height 为弹性容器确定的高度，弹性容器的外边距不和子盒子的外边距合并
+    pub(super) fn layout_flex(&mut self, height: Option<f32>, fonts: &FontCollection) -> (CollapsedMargin, bool) {
+        let style = &self.get_style_node().style;
+        let content = self.dimensions.content;
+        let row = matches!(style.flex_direction, FlexDirection::Row | FlexDirection::RowReverse);
+        let container = Container {
+            style,
+            initial: ComputedStyle::default(),
+            row,
+            single_line: style.flex_wrap == FlexWrap::Nowrap,
+            x: content.x,
+            y: content.y,
+            width: content.width,
+            height,
+        };
+        // start 和 end 在 *-reverse 和 wrap-reverse 时对应 flex-end，从右到左的书写方向还要从另一侧开始排列
+        let reverse_main = matches!(style.flex_direction, FlexDirection::RowReverse | FlexDirection::ColumnReverse);
+        let reverse_cross = style.flex_wrap == FlexWrap::WrapReverse;
+        let rtl = style.direction == Direction::Rtl;
+        let (flip_main, flip_cross) = (reverse_main != (row && rtl), reverse_cross != (!row && rtl));
+        let column_gap = style.column_gap.resolve(content.width);
+        let row_gap = style.row_gap.try_resolve(height).unwrap_or(0.0);
+        let (main_gap, cross_gap) = if row { (column_gap, row_gap) } else { (row_gap, column_gap) };
+
+        // 脱离普通流的子盒子的静态位置为内容区的左上角
+        let mut items = Vec::new();
+        for (index, child) in self.children.iter_mut().enumerate() {
+            if child.is_out_of_flow() {
+                child.static_position = (content.x, content.y);
+                continue;
+            }
+            items.push(FlexItem::new(index, child, &container, fonts));
+        }
+
+        // 把项目放进行中，纵向的容器高度不确定时在 max-height 处换行
+        let available = if row { content.width } else { height.unwrap_or_else(|| self.clamp_height(f32::INFINITY, None)) };
+        let lines = break_lines(&items, available, main_gap, container.single_line);
+        let main_size = match height {
+            _ if row => content.width,
+            Some(height) => height,
+            None => {
+                let used = lines.iter().map(|line| {
+                    let items = &items[line.clone()];
+                    items.iter().map(|item| item.outer_main(item.hypothetical)).sum::<f32>() + gaps(main_gap, items.len())
+                });
+                self.clamp_height(used.fold(0.0, f32::max), None)
+            }
+        };
+
+        // 确定每个项目在主轴上的尺寸，再按照这个尺寸布局得到交叉轴上的尺寸
+        for line in &lines {
+            let items = &mut items[line.clone()];
+            let space = main_size - gaps(main_gap, items.len());
+            resolve_flexible_lengths(items, space);
+        }
+        for item in &mut items {
+            let cross = item.definite_cross;
+            item.layout(&mut self.children[item.index], &container, cross, fonts);
+        }
+
+        // 每行在交叉轴上的尺寸为其中最大的项目，按照基线对齐的项目在基线以上和以下分别取最大值
+        let mut line_sizes = Vec::new();
+        let mut line_baselines = Vec::new();
+        for line in &lines {
+            let (mut size, mut above, mut below) = (0.0f32, 0.0f32, 0.0f32);
+            for item in &items[line.clone()] {
+                if item.is_baseline_aligned() {
+                    above = above.max(item.baseline);
+                    below = below.max(item.outer_cross() - item.baseline);
+                } else {
+                    size = size.max(item.outer_cross());
+                }
+            }
+            line_sizes.push(size.max(above + below));
+            line_baselines.push(above);
+        }
+        let definite_cross = if row { height } else { Some(content.width) };
+        if container.single_line {
+            if let Some(size) = line_sizes.first_mut() {
+                *size = definite_cross.unwrap_or_else(|| self.clamp_height(*size, None));
+            }
+        }
+        let cross_size = definite_cross.unwrap_or_else(|| {
+            self.clamp_height(line_sizes.iter().sum::<f32>() + gaps(cross_gap, line_sizes.len()), None)
+        });
+
+        // 多行时按照 align-content 在行之间分配交叉轴上剩余的空间，normal 和 stretch 平均分给每一行
+        let (mut line_position, line_between) = if container.single_line {
+            (0.0, 0.0)
+        } else {
+            let free = cross_size - line_sizes.iter().sum::<f32>() - gaps(cross_gap, line_sizes.len());
+            match style.align_content {
+                ContentDistribution::Normal | ContentDistribution::Stretch if free > 0.0 => {
+                    let extra = free / line_sizes.len() as f32;
+                    line_sizes.iter_mut().for_each(|size| *size += extra);
+                    (0.0, 0.0)
+                }
+                mode => distribute(mode, free, line_sizes.len(), reverse_cross),
+            }
+        };
+
+        // 拉伸的项目按照行的尺寸重新布局
+        for (line, &line_size) in lines.iter().zip(&line_sizes) {
+            for item in &mut items[line.clone()] {
+                if !item.stretched {
+                    continue;
+                }
+                let (start, end) = item.cross_margins;
+                let cross = (line_size - start.unwrap_or(0.0) - end.unwrap_or(0.0) - item.cross_edges).min(item.max_cross).max(item.min_cross);
+                if cross != item.cross {
+                    item.layout(&mut self.children[item.index], &container, Some(cross), fonts);
+                }
+            }
+        }
+
+        for ((line, &line_size), &line_baseline) in lines.iter().zip(&line_sizes).zip(&line_baselines) {
+            let items = &mut items[line.clone()];
+            // 主轴上剩余的空间先分给 auto 的外边距，没有时按照 justify-content 分配
+            let used = items.iter().map(|item| item.outer_main(item.target)).sum::<f32>() + gaps(main_gap, items.len());
+            let free = main_size - used;
+            let auto_margins = items.iter()
+                .map(|item| item.main_margins.0.is_none() as usize + item.main_margins.1.is_none() as usize)
+                .sum::<usize>();
+            let (auto_margin, (mut position, between)) = if free > 0.0 && auto_margins > 0 {
+                (free / auto_margins as f32, (0.0, 0.0))
+            } else {
+                (0.0, distribute(style.justify_content, free, items.len(), reverse_main))
+            };
+
+            for item in items {
+                let main_margins = (item.main_margins.0.unwrap_or(auto_margin), item.main_margins.1.unwrap_or(auto_margin));
+                let outer_main = main_margins.0 + item.main_edges + item.target + main_margins.1;
+                let main = if flip_main { main_size - position - outer_main } else { position };
+                position += outer_main + main_gap + between;
+
+                // 交叉轴上剩余的空间先分给 auto 的外边距，没有时按照 align-self 对齐
+                let free = line_size - item.outer_cross();
+                let (cross_margins, offset) = match item.cross_margins {
+                    (None, None) => ((free.max(0.0) / 2.0, free.max(0.0) / 2.0), 0.0),
+                    (None, Some(end)) => ((free.max(0.0), end), 0.0),
+                    (Some(start), None) => ((start, free.max(0.0)), 0.0),
+                    (Some(start), Some(end)) => ((start, end), match item.align {
+                        AlignItems::Stretch | AlignItems::FlexStart => 0.0,
+                        AlignItems::FlexEnd => free,
+                        AlignItems::Center => free / 2.0,
+                        AlignItems::Start if reverse_cross => free,
+                        AlignItems::End if !reverse_cross => free,
+                        AlignItems::Start | AlignItems::End => 0.0,
+                        AlignItems::Baseline => line_baseline - item.baseline,
+                    }),
+                };
+                let outer_cross = cross_margins.0 + item.cross_edges + item.cross + cross_margins.1;
+                let cross = line_position + offset;
+                let cross = if flip_cross { cross_size - cross - outer_cross } else { cross };
+
+                // 把外边距盒移动到最终的位置
+                let child = &mut self.children[item.index];
+                let d = &mut child.dimensions;
+                let (x, y) = if row {
+                    (d.margin.left, d.margin.right, d.margin.top, d.margin.bottom) = (main_margins.0, main_margins.1, cross_margins.0, cross_margins.1);
+                    (main, cross)
+                } else {
+                    (d.margin.left, d.margin.right, d.margin.top, d.margin.bottom) = (cross_margins.0, cross_margins.1, main_margins.0, main_margins.1);
+                    (cross, main)
+                };
+                let margin_box = d.margin_box();
+                child.translate(content.x + x - margin_box.x, content.y + y - margin_box.y);
+                child.apply_relative_offset(content.width, height);
+            }
+            line_position += line_size + cross_gap + line_between;
+        }
+
+        self.dimensions.content.height = if row { cross_size } else { main_size };
+        (CollapsedMargin::default(), true)
+    }
+
+    // 弹性容器内容的最小宽度和最大宽度，横向不换行时为全部项目之和，换行时最小宽度为最宽的项目，纵向时都为最宽的项目
+    pub(super) fn flex_intrinsic_widths(&mut self, fonts: &FontCollection) -> (f32, f32) {
+        let style = &self.get_style_node().style;
+        let mut widths = Vec::new();
+        for child in &mut self.children {
+            widths.push(match child.box_type {
+                _ if child.is_out_of_flow() => continue,
+                AnonymousBlock => inline::intrinsic_widths(&mut child.children, style, fonts),
+                _ => child.outer_intrinsic_widths(fonts),
+            });
+        }
+        let widest = |iter: &mut dyn Iterator<Item = f32>| iter.fold(0.0, f32::max);
+        let (min, max) = (widest(&mut widths.iter().map(|w| w.0)), widest(&mut widths.iter().map(|w| w.1)));
+        if matches!(style.flex_direction, FlexDirection::Column | FlexDirection::ColumnReverse) {
+            return (min, max);
+        }
+        let gap = gaps(style.column_gap.try_resolve(None).unwrap_or(0.0), widths.len());
+        let max = widths.iter().map(|w| w.1).sum::<f32>() + gap;
+        match style.flex_wrap {
+            FlexWrap::Nowrap => (widths.iter().map(|w| w.0).sum::<f32>() + gap, max),
+            _ => (min, max),
+        }
+    }
+
+    // 弹性项目内容的最小宽度和最大宽度，匿名的弹性项目按照弹性容器的样式排列文字
+    fn flex_item_intrinsic_widths(&mut self, container: &Container, fonts: &FontCollection) -> (f32, f32) {
+        match self.box_type {
+            AnonymousBlock => inline::intrinsic_widths(&mut self.children, container.style, fonts),
+            _ => self.intrinsic_widths(fonts),
+        }
+    }
+
+    // 在弹性容器内容区的左上角按照宽度 width 和确定的高度 height 布局弹性项目，之后再移动到最终的位置
+    fn layout_flex_item(&mut self, container: &Container, width: f32, height: Option<f32>, fonts: &FontCollection) {
+        if let AnonymousBlock = self.box_type {
+            let containing_block = Dimensions {
+                content: Rect { x: container.x, y: container.y, width, height: 0.0 },
+                ..Default::default()
+            };
+            self.layout_anonymous_block(containing_block, container.style, &mut FloatContext::default(), fonts);
+            if let Some(height) = height {
+                self.dimensions.content.height = height;
+            }
+            return;
+        }
+        let d = &mut self.dimensions;
+        d.content.x = container.x + d.margin.left + d.border.left + d.padding.left;
+        d.content.y = container.y + d.margin.top + d.border.top + d.padding.top;
+        d.content.width = width;
+        d.content.height = 0.0;
+        let (margin, separated) = self.layout_formatting_context(height, fonts);
+        self.calculate_block_height(container.height, height, margin, separated, true);
+        if self.position() != Position::Static {
+            self.layout_positioned_descendants(Some(self.dimensions.padding_box()), None, fonts);
+        }
+    }
+}
+
+impl FlexItem {
+    // 计算项目的外边距、边框和内边距，以及主轴上的基础尺寸和最小尺寸，纵向的项目还要确定宽度
+    fn new(index: usize, layout_box: &mut LayoutBox, container: &Container, fonts: &FontCollection) -> FlexItem {
+        let style = match layout_box.box_type {
+            BlockNode(node) => &node.style,
+            _ => &container.initial,
+        };
+        // 百分比的外边距和内边距都相对于弹性容器的宽度计算
+        let basis = container.width;
+        let px = |value: &LengthPercentageAuto, basis: Option<f32>| match value.resolve(basis) {
+            LengthPercentageAuto::Px(f) => Some(f),
+            _ => None,
+        };
+        let margins = (
+            px(&style.margin.left, Some(basis)),
+            px(&style.margin.right, Some(basis)),
+            px(&style.margin.top, Some(basis)),
+            px(&style.margin.bottom, Some(basis)),
+        );
+        let d = &mut layout_box.dimensions;
+        d.margin = EdgeSizes {
+            left: margins.0.unwrap_or(0.0),
+            right: margins.1.unwrap_or(0.0),
+            top: margins.2.unwrap_or(0.0),
+            bottom: margins.3.unwrap_or(0.0),
+        };
+        d.border = EdgeSizes {
+            left: style.border_width.left,
+            right: style.border_width.right,
+            top: style.border_width.top,
+            bottom: style.border_width.bottom,
+        };
+        d.padding = EdgeSizes {
+            left: style.padding.left.resolve(basis),
+            right: style.padding.right.resolve(basis),
+            top: style.padding.top.resolve(basis),
+            bottom: style.padding.bottom.resolve(basis),
+        };
+        let horizontal_edges = d.border.left + d.border.right + d.padding.left + d.padding.right;
+        let vertical_edges = d.border.top + d.border.bottom + d.padding.top + d.padding.bottom;
+
+        // box-sizing 为 border-box 时尺寸属性指定的是边框盒，min-width 和 min-height 为 auto 时是 None
+        let content_size = |size: f32, edges: f32| match style.box_sizing {
+            BoxSizing::ContentBox => size,
+            BoxSizing::BorderBox => (size - edges).max(0.0),
+        };
+        let width = px(&style.width, Some(basis)).map(|width| content_size(width, horizontal_edges));
+        let height = px(&style.height, container.height).map(|height| content_size(height, vertical_edges));
+        let min_width = px(&style.min_width, Some(basis)).map(|width| content_size(width, horizontal_edges));
+        let min_height = px(&style.min_height, container.height).map(|height| content_size(height, vertical_edges));
+        let max_width = style.max_width.as_ref().and_then(|width| width.try_resolve(Some(basis)))
+            .map_or(f32::INFINITY, |width| content_size(width, horizontal_edges));
+        let max_height = style.max_height.as_ref().and_then(|height| height.try_resolve(container.height))
+            .map_or(f32::INFINITY, |height| content_size(height, vertical_edges));
+
+        let row = container.row;
+        let ((main, cross), (min_main, min_cross), (max_main, max_cross)) = if row {
+            ((width, height), (min_width, min_height), (max_width, max_height))
+        } else {
+            ((height, width), (min_height, min_width), (max_height, max_width))
+        };
+        let (main_margins, cross_margins) = if row {
+            ((margins.0, margins.1), (margins.2, margins.3))
+        } else {
+            ((margins.2, margins.3), (margins.0, margins.1))
+        };
+        let (main_edges, cross_edges) = if row { (horizontal_edges, vertical_edges) } else { (vertical_edges, horizontal_edges) };
+        let min_cross = min_cross.unwrap_or(0.0);
+
+        // 纵向的项目不知道高度基线，baseline 按照 flex-start 对齐
+        let align = match style.align_self.unwrap_or(container.style.align_items) {
+            AlignItems::Baseline if !row => AlignItems::FlexStart,
+            align => align,
+        };
+        let stretched = align == AlignItems::Stretch && cross.is_none() && cross_margins.0.is_some() && cross_margins.1.is_some();
+
+        // 纵向的项目先确定宽度：拉伸时在只有一行的容器中占满容器的宽度，否则使用收缩适应的宽度
+        // 横向的项目只有指定了高度时才确定
+        let definite_cross = match cross {
+            _ if row => cross.map(|height| height.min(max_cross).max(min_cross)),
+            Some(width) => Some(width.min(max_cross).max(min_cross)),
+            None => {
+                let available = basis - margins.0.unwrap_or(0.0) - margins.1.unwrap_or(0.0) - horizontal_edges;
+                let width = if stretched && container.single_line {
+                    available
+                } else {
+                    let (min_content, max_content) = layout_box.flex_item_intrinsic_widths(container, fonts);
+                    min_content.max(available).min(max_content)
+                };
+                Some(width.min(max_cross).max(min_cross))
+            }
+        };
+        let cross = definite_cross.unwrap_or(0.0);
+
+        // flex-basis 为 auto 时使用主轴方向的 width 或 height，百分比在容器的尺寸不确定时和 content 一样按照内容计算
+        let main_basis = if row { Some(basis) } else { container.height };
+        let specified = match style.flex_basis {
+            FlexBasis::Size(LengthPercentageAuto::Auto) => main,
+            FlexBasis::Size(ref size) => px(size, main_basis).map(|size| content_size(size, main_edges)),
+            FlexBasis::Content => None,
+        };
+        // 可以滚动的项目的最小尺寸为 0，其他项目不小于内容的最小尺寸，指定了尺寸时也不大于指定的尺寸
+        let scrollable = !matches!(style.overflow, Overflow::Visible | Overflow::Clip);
+        let (min_content, max_content) = match () {
+            _ if specified.is_some() && (min_main.is_some() || scrollable) => (0.0, 0.0),
+            _ if row => layout_box.flex_item_intrinsic_widths(container, fonts),
+            _ => {
+                layout_box.layout_flex_item(container, cross, None, fonts);
+                let height = layout_box.dimensions.content.height;
+                (height, height)
+            }
+        };
+        let base = specified.unwrap_or(max_content);
+        let min_main = match min_main {
+            Some(min) => min,
+            None if scrollable => 0.0,
+            None => main.map_or(min_content, |size| size.min(min_content)).min(max_main),
+        };
+        let hypothetical = base.min(max_main).max(min_main);
+
+        FlexItem {
+            index,
+            grow: style.flex_grow,
+            shrink: style.flex_shrink,
+            main_margins,
+            cross_margins,
+            main_edges,
+            cross_edges,
+            base,
+            hypothetical,
+            min_main,
+            max_main,
+            target: hypothetical,
+            frozen: false,
+            cross,
+            definite_cross,
+            min_cross,
+            max_cross,
+            align,
+            stretched,
+            baseline: 0.0,
+        }
+    }
+
+    // 按照主轴上的尺寸和交叉轴上确定的尺寸 cross 布局项目，再记录交叉轴上的尺寸和基线
+    fn layout(&mut self, layout_box: &mut LayoutBox, container: &Container, cross: Option<f32>, fonts: &FontCollection) {
+        if container.row {
+            layout_box.layout_flex_item(container, self.target, cross, fonts);
+            self.cross = layout_box.dimensions.content.height;
+        } else {
+            let width = cross.unwrap_or(self.cross);
+            layout_box.layout_flex_item(container, width, Some(self.target), fonts);
+            self.cross = width;
+        }
+        // 没有行盒时按照边框盒的底部对齐
+        let margin_box = layout_box.dimensions.margin_box();
+        let border_box = layout_box.dimensions.border_box();
+        self.baseline = layout_box.first_baseline().unwrap_or(border_box.y + border_box.height) - margin_box.y;
+    }
+
+    // 按照基线对齐且交叉轴上没有 auto 外边距
+    fn is_baseline_aligned(&self) -> bool {
+        self.align == AlignItems::Baseline && self.cross_margins.0.is_some() && self.cross_margins.1.is_some()
+    }
+
+    // 主轴上的尺寸为 size 时的外边距盒尺寸，auto 的外边距按照 0 计算
+    fn outer_main(&self, size: f32) -> f32 {
+        size + self.main_edges + self.main_margins.0.unwrap_or(0.0) + self.main_margins.1.unwrap_or(0.0)
+    }
+
+    fn outer_cross(&self) -> f32 {
+        self.cross + self.cross_edges + self.cross_margins.0.unwrap_or(0.0) + self.cross_margins.1.unwrap_or(0.0)
+    }
+}
+
+// 按照假想尺寸把项目依次放进行中，一行放不下时换到下一行，每行至少有一个项目
+fn break_lines(items: &[FlexItem], available: f32, gap: f32, single_line: bool) -> Vec<Range<usize>> {
+    if items.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    let (mut start, mut used) = (0, 0.0);
+    for (i, item) in items.iter().enumerate() {
+        let size = item.outer_main(item.hypothetical);
+        if i == start {
+            used = size;
+        } else if !single_line && used + gap + size > available {
+            lines.push(start..i);
+            start = i;
+            used = size;
+        } else {
+            used += gap + size;
+        }
+    }
+    lines.push(start..items.len());
+    lines
+}
+
+// 在一行中分配剩余的空间或者收缩超出的空间（9.7 节），space 为主轴上除去间隔之后的空间
+// 超出最小尺寸或最大尺寸的项目被限制后不再变化，其余的项目重新分配，直到全部项目的尺寸都确定
+fn resolve_flexible_lengths(items: &mut [FlexItem], space: f32) {
+    let growing = items.iter().map(|item| item.outer_main(item.hypothetical)).sum::<f32>() < space;
+    // 不能伸缩的项目以及基础尺寸已经超出了伸缩方向的项目直接使用假想尺寸
+    for item in items.iter_mut() {
+        let factor = if growing { item.grow } else { item.shrink };
+        item.frozen = factor == 0.0 || (growing && item.base > item.hypothetical) || (!growing && item.base < item.hypothetical);
+        item.target = if item.frozen { item.hypothetical } else { item.base };
+    }
+    let free_space = |items: &[FlexItem]| {
+        space - items.iter().map(|item| item.outer_main(if item.frozen { item.target } else { item.base })).sum::<f32>()
+    };
+    let initial_free = free_space(items);
+
+    let mut violations = vec![0.0; items.len()];
+    while items.iter().any(|item| !item.frozen) {
+        // 伸缩系数之和小于 1 时只分配剩余空间的一部分
+        let mut free = free_space(items);
+        let factors: f32 = items.iter().filter(|item| !item.frozen).map(|item| if growing { item.grow } else { item.shrink }).sum();
+        if factors < 1.0 && (initial_free * factors).abs() < free.abs() {
+            free = initial_free * factors;
+        }
+        // 剩余空间按照 flex-grow 分配，超出的空间按照 flex-shrink 乘以基础尺寸的比例收缩
+        let scaled: f32 = items.iter().filter(|item| !item.frozen).map(|item| item.shrink * item.base).sum();
+        for item in items.iter_mut().filter(|item| !item.frozen) {
+            item.target = if free == 0.0 {
+                item.base
+            } else if growing {
+                item.base + free * item.grow / factors
+            } else if scaled > 0.0 {
+                item.base + free * item.shrink * item.base / scaled
+            } else {
+                item.base
+            };
+        }
+        // 限制在最小尺寸和最大尺寸之间，总的调整量为正时固定变大的项目，为负时固定变小的项目，为 0 时全部固定
+        let mut total = 0.0;
+        for (item, violation) in items.iter_mut().zip(&mut violations) {
+            *violation = 0.0;
+            if !item.frozen {
+                let clamped = item.target.min(item.max_main).max(item.min_main).max(0.0);
+                *violation = clamped - item.target;
+                item.target = clamped;
+                total += *violation;
+            }
+        }
+        for (item, &violation) in items.iter_mut().zip(&violations) {
+            if total == 0.0 || (total > 0.0 && violation > 0.0) || (total < 0.0 && violation < 0.0) {
+                item.frozen = true;
+            }
+        }
+    }
+}
+
+// 按照 justify-content 或 align-content 分配 count 个项目或行以外的剩余空间 free，返回第一个之前和相邻两个之间增加的距离
+// reversed 表示主轴或交叉轴的方向和书写方向相反，这时 start 和 end 分别对应 flex-end 和 flex-start
+fn distribute(mode: ContentDistribution, free: f32, count: usize, reversed: bool) -> (f32, f32) {
+    let n = count as f32;
+    match mode {
+        ContentDistribution::Start if reversed => (free, 0.0),
+        ContentDistribution::End if !reversed => (free, 0.0),
+        ContentDistribution::Normal | ContentDistribution::Stretch | ContentDistribution::FlexStart
+            | ContentDistribution::Start | ContentDistribution::End => (0.0, 0.0),
+        ContentDistribution::FlexEnd => (free, 0.0),
+        ContentDistribution::Center => (free / 2.0, 0.0),
+        // 空间不足时 space-between 按照 flex-start 对齐，space-around 和 space-evenly 按照 center 对齐
+        ContentDistribution::SpaceBetween if free > 0.0 && count > 1 => (0.0, free / (n - 1.0)),
+        ContentDistribution::SpaceBetween => (0.0, 0.0),
+        ContentDistribution::SpaceAround if free > 0.0 => (free / n / 2.0, free / n),
+        ContentDistribution::SpaceEvenly if free > 0.0 => (free / (n + 1.0), free / (n + 1.0)),
+        ContentDistribution::SpaceAround | ContentDistribution::SpaceEvenly => (free / 2.0, 0.0),
+    }
+}
+
+// count 个项目或行之间的间隔之和
+fn gaps(gap: f32, count: usize) -> f32 {
+    gap * count.saturating_sub(1) as f32
+}
@@ -0,0 +1,127 @@
+// 浮动：浮动盒子脱离普通流，靠在包含块的左侧或右侧，后面的行盒在它旁边变短，clear 使盒子移动到浮动盒子下面
+// 同一个块格式化上下文中的浮动盒子互相影响，建立新的块格式化上下文的盒子包含它里面的浮动盒子
+use crate::{font::FontCollection, style::types::{Clear, Display, Float, Overflow}};
+
+use super::{CollapsedMargin, LayoutBox, Rect};
+
+// 块格式化上下文中已经放置的浮动盒子
+#[derive(Debug, Default)]
+pub struct FloatContext {
+    // 浮动盒子的外边距盒以及它靠在哪一侧，位置相对于文档
+    floats: Vec<(Rect, Float)>,
+}
+
+impl FloatContext {
+    // 把外边距盒的宽度和高度为 width、height 的浮动盒子放在不高于 y 的位置，返回外边距盒左上角的位置
+    // 浮动盒子不能高于前面的浮动盒子，和前面的浮动盒子放不下时向下移动
+    pub fn place(&mut self, float: Float, clear: Clear, (width, height): (f32, f32), y: f32, containing_block: Rect) -> (f32, f32) {
+        let mut y = self.floats.iter().map(|(rect, _)| rect.y).fold(y, f32::max);
+        if let Some(clearance) = self.clearance(clear) {
+            y = y.max(clearance);
+        }
+        let (left, available) = loop {
+            let (left, available) = self.band(y, height, containing_block.x, containing_block.width);
+            if width <= available {
+                break (left, available);
+            }
+            // 旁边没有浮动盒子时即使放不下也放在这里
+            match self.next_bottom(y, height) {
+                Some(bottom) => y = bottom,
+                None => break (left, available),
+            }
+        };
+        let x = match float {
+            Float::Right => left + available - width,
+            _ => left,
+        };
+        self.floats.push((Rect { x, y, width, height }, float));
+        (x, y)
+    }
+
+    // 从 y 开始高度为 height 的范围中，包含块从 x 开始宽度为 width 的内容区没有被浮动盒子占据的部分，返回它的左侧和宽度
+    pub fn band(&self, y: f32, height: f32, x: f32, width: f32) -> (f32, f32) {
+        let (mut left, mut right) = (x, x + width);
+        for (rect, float) in self.overlapping(y, height) {
+            match float {
+                Float::Left => left = left.max(rect.x + rect.width),
+                _ => right = right.min(rect.x),
+            }
+        }
+        (left, (right - left).max(0.0))
+    }
+
+    // 和这个范围重叠的浮动盒子中最先结束的底部，没有重叠的浮动盒子时返回 None
+    pub fn next_bottom(&self, y: f32, height: f32) -> Option<f32> {
+        self.overlapping(y, height).map(|(rect, _)| rect.y + rect.height).reduce(f32::min)
+    }
+
+    // clear 需要清除的浮动盒子中最低的底部，没有需要清除的浮动盒子时返回 None
+    pub fn clearance(&self, clear: Clear) -> Option<f32> {
+        self.floats.iter()
+            .filter(|(_, float)| match clear {
+                Clear::None => false,
+                Clear::Left => *float == Float::Left,
+                Clear::Right => *float == Float::Right,
+                Clear::Both => true,
+            })
+            .map(|(rect, _)| rect.y + rect.height)
+            .reduce(f32::max)
+    }
+
+    // 全部浮动盒子中最低的底部
+    pub fn bottom(&self) -> Option<f32> {
+        self.clearance(Clear::Both)
+    }
+
+    // 高度为 0 的范围和包含 y 的浮动盒子重叠
+    fn overlapping(&self, y: f32, height: f32) -> impl Iterator<Item = &(Rect, Float)> {
+        self.floats.iter().filter(move |(rect, _)| (rect.y < y + height || rect.y <= y) && y < rect.y + rect.height)
+    }
+}
+
+impl<'a> LayoutBox<'a> {
+    // 绝对定位和固定定位的盒子不浮动
+    pub(super) fn is_floated(&self) -> bool {
+        match self.box_type {
+            super::BlockNode(node) => node.style.float != Float::None && !self.is_out_of_flow(),
+            _ => false,
+        }
+    }
+
+    // 浮动盒子、绝对定位的盒子、overflow 不是 visible 的盒子、display: flow-root 的盒子以及弹性容器建立新的格式化上下文
+    // 它们的外边距不和子盒子的外边距合并，里面的浮动盒子不影响外面的内容
+    pub(super) fn establishes_formatting_context(&self) -> bool {
+        match self.box_type {
+            super::BlockNode(node) => self.is_floated() || self.is_out_of_flow()
+                || !matches!(node.style.overflow, Overflow::Visible | Overflow::Clip)
+                || matches!(node.style.display, Display::FlowRoot | Display::Flex | Display::InlineFlex),
+            _ => false,
+        }
+    }
+
+    // 在新的格式化上下文中布局子盒子，弹性容器按照弹性布局排列子盒子
+    // 否则子盒子在新的块格式化上下文中布局，高度取决于内容时包含其中的浮动盒子
+    pub(super) fn layout_formatting_context(&mut self, height: Option<f32>, fonts: &FontCollection) -> (CollapsedMargin, bool) {
+        if self.is_flex_container() {
+            return self.layout_flex(height, fonts);
+        }
+        let mut floats = FloatContext::default();
+        let (margin, separated) = self.layout_block_children(height, CollapsedMargin::default(), true, &mut floats, fonts);
+        let d = &mut self.dimensions;
+        match floats.bottom() {
+            Some(bottom) if bottom - d.content.y > d.content.height + margin.resolve() => {
+                d.content.height = bottom - d.content.y;
+                (CollapsedMargin::default(), true)
+            }
+            _ => (margin, separated),
+        }
+    }
+
+    // 把已经布局的浮动盒子放进 floats 中不高于 y 的位置
+    pub(super) fn place_float(&mut self, floats: &mut FloatContext, containing_block: Rect, y: f32) {
+        let style = &self.get_style_node().style;
+        let margin_box = self.dimensions.margin_box();
+        let (x, y) = floats.place(style.float, style.clear, (margin_box.width, margin_box.height), y, containing_block);
+        self.translate(x - margin_box.x, y - margin_box.y);
+    }
+}
@@ -0,0 +1,842 @@
+// 行内格式化上下文：把行内盒子中的文字拆分成不能换行的片段，按顺序放进行盒，一行放不下时在换行机会处换行
+// 每一行再按照双向文本的嵌入级别重新排列为显示顺序
+use std::{ptr, rc::Rc};
+
+use crate::{
+    font::{Font, FontCollection},
+    html::types::NodeType,
+    style::{StyledNode, types::{Clear, ComputedStyle, Direction, Display, Float, LineHeight, Position, UnicodeBidi, WhiteSpace}},
+    text::{bidi::{self, Level}, line_break::{self, LineBreakClass}, shaping},
+};
+
+use super::{positioned::relative_offset, AnonymousBlock, BlockNode, Dimensions, EdgeSizes, FloatContext, InlineNode, LayoutBox, Rect};
+
+// 一个制表符的宽度相当于几个空格
+const TAB_SIZE: usize = 8;
+
+// unicode-bidi 在元素的开始和结束处插入的双向格式字符
+const LRE: char = '\u{202A}';
+const RLE: char = '\u{202B}';
+const PDF: char = '\u{202C}';
+const LRO: char = '\u{202D}';
+const RLO: char = '\u{202E}';
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+// 行内元素中的块元素把文字分成不同的段落
+const PARAGRAPH_SEPARATOR: char = '\u{2029}';
+// 行内级的盒子在双向文本中作为一个中性的字符
+const OBJECT_REPLACEMENT: char = '\u{FFFC}';
+
+// 一行内容
+#[derive(Debug)]
+pub struct LineBox<'a> {
+    // 行盒的位置，宽度等于包含块的宽度减去旁边的浮动盒子占据的宽度
+    pub rect: Rect,
+    // 基线的 y 坐标
+    pub baseline: f32,
+    // 行中内容的宽度
+    pub used_width: f32,
+    // 按照显示顺序排列，外层元素的片段排在内层元素之前
+    pub fragments: Vec<Fragment<'a>>,
+}
+
+// 一个行内盒子在某一行中的部分
+#[derive(Debug)]
+pub struct Fragment<'a> {
+    pub node: &'a StyledNode<'a>,
+    pub rect: Rect,
+    pub kind: FragmentKind,
+}
+
+#[derive(Debug)]
+pub enum FragmentKind {
+    // 文本节点中的文字，已经过字形变换并按照显示顺序排列，rect 的高度为字体基线以上和以下的高度之和
+    Text { text: String, font: Font },
+    // 行内元素的边框盒，元素跨越多行或者被双向文本拆开时，左右边框只属于包含元素左侧和右侧的片段
+    Box { left: bool, right: bool },
+}
+
+// 包含一段内容的行内元素以及它们的字体，从外到内排列
+type Boxes<'a> = Rc<[(&'a StyledNode<'a>, Font)]>;
+
+// 行中的一段内容
+struct Piece<'a> {
+    kind: PieceKind<'a>,
+    width: f32,
+    // 双向文本的嵌入级别
+    level: Level,
+    boxes: Boxes<'a>,
+}
+
+enum PieceKind<'a> {
+    // 不能在中间换行的文字，text 为字形变换后的显示顺序
+    Word { node: &'a StyledNode<'a>, font: Font, text: String },
+    // collapsible 的空白由多个空白符合并而来，出现在行首和行尾时会被去掉
+    Space { node: &'a StyledNode<'a>, font: Font, text: String, collapsible: bool },
+    // 保留的换行符
+    Break(&'a StyledNode<'a>, Font),
+    // boxes 中最内层元素的开始或者结束，width 为这一侧外边距、边框和内边距的宽度之和
+    Edge,
+    // 已经布局的行内级盒子，ascent 为外边距盒的顶部到它的基线的距离，index 为它在 box_offsets 中的序号
+    Atomic { index: usize, margin_box: Rect, ascent: f32 },
+}
+
+// 在行内内容中遇到的浮动盒子，当前行放不下时等到下一行开始时再放置
+struct PendingFloat {
+    // 在 box_offsets 中的序号
+    index: usize,
+    float: Float,
+    clear: Clear,
+    margin_box: Rect,
+}
+
+// 匿名块中的全部文字组成的双向文本
+struct BidiText {
+    // 每个字符的嵌入级别，包括 unicode-bidi 插入的格式字符
+    levels: Vec<Level>,
+    // 段落的基础级别
+    base: Level,
+    // 按照文档顺序，每个文本节点的第一个字符在 levels 中的位置
+    text_starts: Vec<usize>,
+    // 按照文档顺序，每个行内级盒子对应的字符在 levels 中的位置
+    atomics: Vec<usize>,
+    // 按照文档顺序，每个行内元素开始和结束处的级别，取两侧字符中较低的级别，使元素的边和内容以及外面的文字排列在一起
+    edges: Vec<(Level, Level)>,
+}
+
+// 布局匿名块中的行内盒子，content 为匿名块的内容区域，style 为包含匿名块的块元素的样式
+// floats 为所在的块格式化上下文中的浮动盒子，行内内容中的浮动盒子也放进 floats
+// 返回全部行盒以及它们的总高度
+pub fn layout_lines<'a>(children: &mut [LayoutBox<'a>], content: Rect, style: &ComputedStyle, floats: &mut FloatContext, fonts: &FontCollection) -> (Vec<LineBox<'a>>, f32) {
+    let mut builder = LineBuilder {
+        fonts,
+        content,
+        y: content.y,
+        floats,
+        // 按照块元素的字体和行高估计一行的高度，用来查找行旁边的浮动盒子
+        strut: line_height(style, &fonts.font(style)),
+        line_left: content.x,
+        line_width: content.width,
+        pending_floats: Vec::new(),
+        box_offsets: Vec::new(),
+        lines: Vec::new(),
+        line: Vec::new(),
+        pending: Vec::new(),
+        boxes: Rc::new([]),
+        after_space: true,
+        previous: None,
+        bidi: BidiText::new(children, style),
+        next_text: 0,
+        next_edge: 0,
+        next_atomic: 0,
+    };
+    builder.start_line();
+    for child in children.iter_mut() {
+        builder.add_box(child);
+    }
+    builder.flush_word();
+    builder.finish_line();
+
+    let mut index = 0;
+    for child in children.iter_mut() {
+        move_boxes(child, &builder.box_offsets, &mut index, content.width);
+        assign_dimensions(child, &builder.lines, content.width);
+    }
+    let mut lines = builder.lines;
+    for child in children.iter_mut() {
+        offset_relative_boxes(child, &mut lines, content.width);
+    }
+    (lines, builder.y - content.y)
+}
+
+// 行内内容的最小宽度和最大宽度：在每个换行机会处都换行时最宽的一行，以及只在强制换行处换行时最宽的一行
+// 行内内容中的块元素按照它自己的最小宽度和最大宽度计算
+pub fn intrinsic_widths(children: &mut [LayoutBox], style: &ComputedStyle, fonts: &FontCollection) -> (f32, f32) {
+    let mut measure = |width: f32| {
+        let content = Rect { x: 0.0, y: 0.0, width, height: 0.0 };
+        let (lines, _) = layout_lines(children, content, style, &mut FloatContext::default(), fonts);
+        lines.iter().map(|line| line.used_width).fold(0.0, f32::max)
+    };
+    let (mut min, mut max) = (measure(0.0), measure(f32::INFINITY));
+    for child in children.iter_mut() {
+        let (block_min, block_max) = block_widths(child, fonts);
+        min = min.max(block_min);
+        max = max.max(block_max);
+    }
+    (min, max)
+}
+
+fn block_widths(layout_box: &mut LayoutBox, fonts: &FontCollection) -> (f32, f32) {
+    match layout_box.box_type {
+        _ if layout_box.is_out_of_flow() => (0.0, 0.0),
+        BlockNode(_) => layout_box.outer_intrinsic_widths(fonts),
+        InlineNode(_) | AnonymousBlock => layout_box.children.iter_mut()
+            .map(|child| block_widths(child, fonts))
+            .fold((0.0, 0.0), |(min, max), (child_min, child_max)| (f32::max(min, child_min), f32::max(max, child_max))),
+    }
+}
+
+impl BidiText {
+    fn new(children: &[LayoutBox], style: &ComputedStyle) -> BidiText {
+        let mut text = Vec::new();
+        let mut text_starts = Vec::new();
+        let mut atomics = Vec::new();
+        let mut ranges = Vec::new();
+        // 块元素的 bidi-override 作用于它的全部行内内容
+        let rtl = style.direction == Direction::Rtl;
+        let overridden = matches!(style.unicode_bidi, UnicodeBidi::BidiOverride | UnicodeBidi::IsolateOverride);
+        if overridden {
+            text.push(if rtl { RLO } else { LRO });
+        }
+        for child in children {
+            collect_text(child, &mut text, &mut text_starts, &mut atomics, &mut ranges);
+        }
+        if overridden {
+            text.push(PDF);
+        }
+
+        // plaintext 时段落的方向由内容决定
+        let base = match style.unicode_bidi {
+            UnicodeBidi::Plaintext => None,
+            _ => Some(rtl as Level),
+        };
+        let levels = bidi::levels(&text, base);
+        let base = base.unwrap_or_else(|| bidi::paragraph_level(&text));
+        let level = |index: Option<usize>| index.and_then(|i| levels.get(i)).copied().unwrap_or(base);
+        let between = |index: usize| level(index.checked_sub(1)).min(level(Some(index)));
+        let edges = ranges.into_iter().map(|(start, end)| (between(start), between(end))).collect();
+        BidiText { levels, base, text_starts, atomics, edges }
+    }
+}
+
+// 按照文档顺序收集文字，行内元素的前后插入 unicode-bidi 对应的格式字符，ranges 为每个行内元素的内容的范围
+fn collect_text(layout_box: &LayoutBox, text: &mut Vec<char>, text_starts: &mut Vec<usize>, atomics: &mut Vec<usize>, ranges: &mut Vec<(usize, usize)>) {
+    match layout_box.box_type {
+        InlineNode(node) => match node.node.node_type {
+            NodeType::Text(ref content) => {
+                text_starts.push(text.len());
+                // 合并空白时换行符只是普通的空白，不会分隔段落
+                let collapse = matches!(node.style.white_space, WhiteSpace::Normal | WhiteSpace::Nowrap);
+                text.extend(content.chars().map(|c| if collapse && c.is_ascii_whitespace() { ' ' } else { c }));
+            }
+            NodeType::Element(_) => {
+                let (open, close) = bidi_controls(&node.style);
+                text.extend(open);
+                let index = ranges.len();
+                ranges.push((text.len(), text.len()));
+                for child in &layout_box.children {
+                    collect_text(child, text, text_starts, atomics, ranges);
+                }
+                ranges[index].1 = text.len();
+                text.extend(close);
+            }
+        },
+        BlockNode(_) if layout_box.is_out_of_flow() || layout_box.is_floated() => {}
+        BlockNode(_) if is_atomic(layout_box) => {
+            atomics.push(text.len());
+            text.push(OBJECT_REPLACEMENT);
+        }
+        BlockNode(_) => text.push(PARAGRAPH_SEPARATOR),
+        AnonymousBlock => {
+            for child in &layout_box.children {
+                collect_text(child, text, text_starts, atomics, ranges);
+            }
+        }
+    }
+}
+
+// 行内元素的 unicode-bidi 在开始和结束处插入的格式字符
+fn bidi_controls(style: &ComputedStyle) -> (&'static [char], &'static [char]) {
+    let rtl = style.direction == Direction::Rtl;
+    match style.unicode_bidi {
+        UnicodeBidi::Normal => (&[], &[]),
+        UnicodeBidi::Embed => (if rtl { &[RLE] } else { &[LRE] }, &[PDF]),
+        UnicodeBidi::Isolate => (if rtl { &[RLI] } else { &[LRI] }, &[PDI]),
+        UnicodeBidi::BidiOverride => (if rtl { &[RLO] } else { &[LRO] }, &[PDF]),
+        UnicodeBidi::IsolateOverride => (if rtl { &[RLI, RLO] } else { &[LRI, LRO] }, &[PDF, PDI]),
+        UnicodeBidi::Plaintext => (&[FSI], &[PDI]),
+    }
+}
+
+struct LineBuilder<'a, 'f> {
+    fonts: &'f FontCollection,
+    content: Rect,
+    // 下一行的顶部
+    y: f32,
+    floats: &'f mut FloatContext,
+    // 估计的一行的高度
+    strut: f32,
+    // 当前行在浮动盒子以外的范围
+    line_left: f32,
+    line_width: f32,
+    // 当前行放不下，等到下一行开始时放置的浮动盒子
+    pending_floats: Vec<PendingFloat>,
+    // 按照文档顺序，行内内容中的每个浮动盒子和行内级盒子从布局的位置到放置的位置的偏移
+    box_offsets: Vec<(f32, f32)>,
+    lines: Vec<LineBox<'a>>,
+    // 已经放进当前行的内容
+    line: Vec<Piece<'a>>,
+    // 上一个换行机会之后的内容，它们必须放在同一行
+    pending: Vec<Piece<'a>>,
+    // 当前所在的行内元素
+    boxes: Boxes<'a>,
+    // 上一个字符是可以合并的空白，或者在一行的开头
+    after_space: bool,
+    // 上一个字符的换行类型，空白之后为 None
+    previous: Option<LineBreakClass>,
+    bidi: BidiText,
+    // 下一个文本节点、行内元素和行内级盒子在 bidi 中的序号
+    next_text: usize,
+    next_edge: usize,
+    next_atomic: usize,
+}
+
+impl<'a> LineBuilder<'a, '_> {
+    fn add_box(&mut self, layout_box: &mut LayoutBox<'a>) {
+        // 脱离普通流的盒子只记录静态位置，由包含块布局
+        if layout_box.is_out_of_flow() {
+            let used: f32 = self.line.iter().chain(&self.pending).map(|piece| piece.width).sum();
+            layout_box.static_position = (self.line_left + used, self.y);
+            return;
+        }
+        if layout_box.is_floated() {
+            self.add_float(layout_box);
+            return;
+        }
+        if is_atomic(layout_box) {
+            self.add_atomic(layout_box);
+            return;
+        }
+        match layout_box.box_type {
+            InlineNode(node) => match node.node.node_type {
+                NodeType::Text(ref text) => self.add_text(node, text),
+                NodeType::Element(_) => {
+                    let style = &node.style;
+                    let basis = self.content.width;
+                    let (start_level, end_level) = self.bidi.edges[self.next_edge];
+                    self.next_edge += 1;
+                    // 开始的一侧由元素的方向决定
+                    let start_left = style.direction == Direction::Ltr;
+
+                    let outer = self.boxes.clone();
+                    self.boxes = outer.iter().cloned().chain([(node, self.fonts.font(style))]).collect();
+                    let piece = self.piece(PieceKind::Edge, edge(style, basis, start_left).1, start_level);
+                    self.pending.push(piece);
+                    for child in &mut layout_box.children {
+                        self.add_box(child);
+                    }
+                    let piece = self.piece(PieceKind::Edge, edge(style, basis, !start_left).1, end_level);
+                    self.pending.push(piece);
+                    self.boxes = outer;
+                }
+            },
+            // 行内元素中的块元素单独占据几行，前后的内容分别放在它的上面和下面
+            BlockNode(_) => {
+                self.flush_word();
+                self.finish_line();
+                let containing_block = Dimensions {
+                    content: Rect { x: self.content.x, y: self.y, width: self.content.width, height: 0.0 },
+                    ..Default::default()
+                };
+                // 行内内容中的块元素按照包含块的高度不确定计算百分比高度
+                layout_box.layout(containing_block, None, self.fonts);
+                self.y += layout_box.dimensions.margin_box().height;
+                self.start_line();
+                self.after_space = true;
+                self.previous = None;
+            }
+            AnonymousBlock => {
+                for child in &mut layout_box.children {
+                    self.add_box(child);
+                }
+            }
+        }
+    }
+
+    // 浮动盒子和当前行中已有的内容放得下时放在当前行的顶部，当前行变短，否则等到下一行开始时放置
+    fn add_float(&mut self, layout_box: &mut LayoutBox<'a>) {
+        layout_box.layout_shrink_to_fit(self.content, None, self.y, self.fonts);
+        let style = &layout_box.get_style_node().style;
+        let float = PendingFloat {
+            index: self.box_offsets.len(),
+            float: style.float,
+            clear: style.clear,
+            margin_box: layout_box.dimensions.margin_box(),
+        };
+        self.box_offsets.push((0.0, 0.0));
+        let used: f32 = self.line.iter().chain(&self.pending).map(|piece| piece.width).sum();
+        if self.pending_floats.is_empty() && used + float.margin_box.width <= self.line_width {
+            self.place_float(float);
+            let (left, width) = self.floats.band(self.y, self.strut, self.content.x, self.content.width);
+            self.line_left = left;
+            self.line_width = width;
+        } else {
+            self.pending_floats.push(float);
+        }
+    }
+
+    fn place_float(&mut self, float: PendingFloat) {
+        let PendingFloat { index, float, clear, margin_box } = float;
+        let (x, y) = self.floats.place(float, clear, (margin_box.width, margin_box.height), self.y, self.content);
+        self.box_offsets[index] = (x - margin_box.x, y - margin_box.y);
+    }
+
+    // 行内级的盒子按照收缩适应的宽度布局，作为一个整体放进行中，前后都可以换行
+    // 它的第一条基线和行的基线对齐，没有基线时外边距盒的底部和行的基线对齐
+    fn add_atomic(&mut self, layout_box: &mut LayoutBox<'a>) {
+        layout_box.layout_shrink_to_fit(self.content, None, self.y, self.fonts);
+        let margin_box = layout_box.dimensions.margin_box();
+        let ascent = layout_box.first_baseline().map_or(margin_box.height, |baseline| baseline - margin_box.y);
+        let level = self.bidi.levels[self.bidi.atomics[self.next_atomic]];
+        self.next_atomic += 1;
+
+        let wrap = matches!(layout_box.get_style_node().style.white_space, WhiteSpace::Normal | WhiteSpace::PreWrap);
+        if wrap {
+            self.break_opportunity();
+        }
+        let piece = self.piece(PieceKind::Atomic { index: self.box_offsets.len(), margin_box, ascent }, margin_box.width, level);
+        self.box_offsets.push((0.0, 0.0));
+        self.pending.push(piece);
+        if wrap {
+            self.break_opportunity();
+        }
+        self.after_space = false;
+        self.previous = None;
+    }
+
+    // 开始新的一行：放置等待的浮动盒子，再计算这一行在浮动盒子以外的范围
+    fn start_line(&mut self) {
+        for float in std::mem::take(&mut self.pending_floats) {
+            self.place_float(float);
+        }
+        let (left, width) = self.floats.band(self.y, self.strut, self.content.x, self.content.width);
+        self.line_left = left;
+        self.line_width = width;
+    }
+
+    fn add_text(&mut self, node: &'a StyledNode<'a>, text: &str) {
+        let font = self.fonts.font(&node.style);
+        let start = self.bidi.text_starts[self.next_text];
+        self.next_text += 1;
+        let white_space = node.style.white_space;
+        let wrap = matches!(white_space, WhiteSpace::Normal | WhiteSpace::PreWrap);
+        let preserve = matches!(white_space, WhiteSpace::Pre | WhiteSpace::PreWrap);
+
+        let mut word = String::new();
+        let mut word_level = self.bidi.base;
+        for (i, c) in text.chars().enumerate() {
+            let level = self.bidi.levels[start + i];
+            match c {
+                // 连续的空白符（包括换行符）合并为一个空格
+                _ if !preserve && c.is_ascii_whitespace() => {
+                    self.push_word(node, &font, &mut word, word_level);
+                    if !self.after_space {
+                        self.push_space(node, &font, " ".to_string(), true, wrap, level);
+                        self.after_space = true;
+                    }
+                    self.previous = None;
+                }
+                // 保留每个空白符，换行符强制换行
+                ' ' | '\t' => {
+                    self.push_word(node, &font, &mut word, word_level);
+                    self.push_space(node, &font, c.to_string(), false, wrap, level);
+                    self.previous = None;
+                }
+                '\n' => {
+                    self.push_word(node, &font, &mut word, word_level);
+                    self.flush_word();
+                    let piece = self.piece(PieceKind::Break(node, font.clone()), 0.0, level);
+                    self.line.push(piece);
+                    self.finish_line();
+                    self.previous = None;
+                }
+                '\r' => {}
+                _ => {
+                    // 组合字符和前面的字符作为一个整体，前面没有字符时视为字母
+                    let class = match line_break::line_break_class(c) {
+                        LineBreakClass::CM if self.previous.is_some() => None,
+                        LineBreakClass::CM => Some(LineBreakClass::AL),
+                        class => Some(class),
+                    };
+                    if let Some(class) = class {
+                        if wrap && self.previous.is_some_and(|previous| line_break::break_between(previous, class)) {
+                            self.push_word(node, &font, &mut word, word_level);
+                            self.break_opportunity();
+                        }
+                        self.previous = Some(class);
+                    }
+                    // 嵌入级别不同的文字分开排列
+                    if level != word_level {
+                        self.push_word(node, &font, &mut word, word_level);
+                        word_level = level;
+                    }
+                    word.push(c);
+                }
+            }
+        }
+        self.push_word(node, &font, &mut word, word_level);
+    }
+
+    fn piece(&self, kind: PieceKind<'a>, width: f32, level: Level) -> Piece<'a> {
+        Piece { kind, width, level, boxes: self.boxes.clone() }
+    }
+
+    fn push_word(&mut self, node: &'a StyledNode<'a>, font: &Font, word: &mut String, level: Level) {
+        if word.is_empty() {
+            return;
+        }
+        let text = shaping::shape(&std::mem::take(word), bidi::is_rtl(level), font);
+        let width = font.measure(&text);
+        let piece = self.piece(PieceKind::Word { node, font: font.clone(), text }, width, level);
+        self.pending.push(piece);
+        self.after_space = false;
+    }
+
+    // wrap 为 true 时空白之后可以换行，空白本身总是留在上一行的末尾
+    fn push_space(&mut self, node: &'a StyledNode<'a>, font: &Font, text: String, collapsible: bool, wrap: bool, level: Level) {
+        // 行首可以合并的空白被去掉
+        let at_line_start = !self.line.iter().chain(&self.pending)
+            .any(|piece| matches!(piece.kind, PieceKind::Word { .. } | PieceKind::Space { .. } | PieceKind::Atomic { .. }));
+        if collapsible && at_line_start {
+            return;
+        }
+        let width = if text == "\t" { font.measure(" ") * TAB_SIZE as f32 } else { font.measure(&text) };
+        let space = self.piece(PieceKind::Space { node, font: font.clone(), text, collapsible }, width, level);
+        if wrap {
+            self.flush_word();
+            self.line.push(space);
+        } else {
+            self.pending.push(space);
+        }
+    }
+
+    // 文字中间的换行机会：之前的内容放进当前行，刚开始的行内元素留给之后的内容
+    fn break_opportunity(&mut self) {
+        let split = self.pending.iter().rposition(|piece| !is_start_edge(piece, &self.boxes)).map_or(0, |index| index + 1);
+        let opened = self.pending.split_off(split);
+        self.flush_word();
+        self.pending = opened;
+    }
+
+    // 把上一个换行机会之后的内容放进当前行，放不下时先换行
+    fn flush_word(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        // 行尾的空白不占用宽度，但是后面放进新的内容后，原来行尾的空白就到了行的中间，只有新内容末尾的空白不算
+        let trailing = self.pending.iter().rev().take_while(|piece| matches!(piece.kind, PieceKind::Space { .. })).map(|piece| piece.width).sum::<f32>();
+        let width: f32 = self.pending.iter().map(|piece| piece.width).sum::<f32>() - trailing;
+        let used: f32 = self.line.iter().map(|piece| piece.width).sum();
+        let has_word = self.line.iter().any(Piece::is_word);
+        if has_word && used + width > self.line_width {
+            self.finish_line();
+        }
+        // 行中还没有文字时也放不下，向下移动到旁边的浮动盒子结束的位置，直到放得下或者旁边没有浮动盒子
+        if !self.line.iter().any(Piece::is_word) {
+            let used: f32 = self.line.iter().map(|piece| piece.width).sum();
+            while used + width > self.line_width {
+                let Some(bottom) = self.floats.next_bottom(self.y, self.strut) else { break };
+                self.y = bottom;
+                self.start_line();
+            }
+        }
+        self.line.append(&mut self.pending);
+    }
+
+    // 结束当前行，按照显示顺序计算其中每个片段的位置
+    fn finish_line(&mut self) {
+        // 去掉行尾可以合并的空白
+        let keep = self.line.iter()
+            .rposition(|piece| !matches!(piece.kind, PieceKind::Space { collapsible: true, .. } | PieceKind::Edge))
+            .map_or(0, |index| index + 1);
+        let mut index = 0;
+        self.line.retain(|piece| {
+            index += 1;
+            index <= keep || !matches!(piece.kind, PieceKind::Space { collapsible: true, .. })
+        });
+
+        let mut line = std::mem::take(&mut self.line);
+        // 只有被去掉的空白和没有宽度的行内元素的行不占用高度
+        if !line.iter().any(Piece::is_visible) {
+            self.start_line();
+            return;
+        }
+
+        // 行尾的空白、制表符以及它前面的空白使用段落的级别
+        let base = self.bidi.base;
+        let mut reset = true;
+        for piece in line.iter_mut().rev() {
+            match piece.kind {
+                PieceKind::Space { ref text, .. } => {
+                    reset |= text == "\t";
+                    if reset {
+                        piece.level = base;
+                    }
+                }
+                PieceKind::Edge => {}
+                PieceKind::Word { .. } | PieceKind::Break(..) | PieceKind::Atomic { .. } => reset = false,
+            }
+        }
+
+        // 基线以上和以下的高度，由行中每个行内盒子的字体和行高决定
+        let (mut above, mut below) = (0.0f32, 0.0f32);
+        for piece in &line {
+            if let PieceKind::Atomic { margin_box, ascent, .. } = piece.kind {
+                above = above.max(ascent);
+                below = below.max(margin_box.height - ascent);
+            }
+            let struts = piece.boxes.iter().map(|(node, font)| (*node, font)).chain(piece.strut());
+            for (node, font) in struts {
+                let (ascent, descent) = (font.ascent(), font.descent());
+                let half_leading = (line_height(&node.style, font) - (ascent + descent)) / 2.0;
+                above = above.max(ascent + half_leading);
+                below = below.max(descent + half_leading);
+            }
+        }
+        let baseline = self.y + above;
+        let basis = self.content.width;
+
+        // 按照级别排列为显示顺序，从右到左的段落靠右对齐
+        let order = bidi::visual_order(&line.iter().map(|piece| piece.level).collect::<Vec<_>>());
+        let mut slots: Vec<Option<Piece<'a>>> = line.into_iter().map(Some).collect();
+        let line: Vec<Piece<'a>> = order.into_iter().map(|index| slots[index].take().unwrap()).collect();
+        let used: f32 = line.iter().map(|piece| piece.width).sum();
+        let mut x = if bidi::is_rtl(base) { self.line_left + self.line_width - used } else { self.line_left };
+
+        let mut fragments: Vec<Fragment<'a>> = Vec::new();
+        // 当前打开的行内元素：元素，它的片段在 fragments 中的位置，片段中是否已经有内容
+        let mut stack: Vec<(&'a StyledNode<'a>, usize, bool)> = Vec::new();
+        for piece in line {
+            // 结束不包含这段内容的元素的片段，为新进入的元素开始片段
+            let common = stack.iter().zip(piece.boxes.iter())
+                .take_while(|((open, _, _), (node, _))| ptr::eq(*open, *node))
+                .count();
+            for (_, index, _) in stack.drain(common..) {
+                let fragment = &mut fragments[index];
+                fragment.rect.width = x - fragment.rect.x;
+            }
+            for (node, font) in &piece.boxes[common..] {
+                for entry in &mut stack {
+                    entry.2 = true;
+                }
+                stack.push((node, fragments.len(), false));
+                fragments.push(box_fragment(node, font, x, baseline, basis));
+            }
+
+            let width = piece.width;
+            match piece.kind {
+                // 片段中还没有内容时是元素的左侧，否则是右侧
+                PieceKind::Edge => {
+                    let (node, index, has_content) = *stack.last().expect("edge outside of its element");
+                    let fragment = &mut fragments[index];
+                    let FragmentKind::Box { ref mut left, ref mut right } = fragment.kind else { unreachable!() };
+                    if has_content {
+                        let (margin, width) = edge(&node.style, basis, false);
+                        *right = true;
+                        x += width;
+                        fragment.rect.width = x - margin - fragment.rect.x;
+                        stack.pop();
+                    } else {
+                        let (margin, width) = edge(&node.style, basis, true);
+                        *left = true;
+                        fragment.rect.x = x + margin;
+                        x += width;
+                        stack.last_mut().unwrap().2 = true;
+                    }
+                }
+                PieceKind::Word { node, font, text } | PieceKind::Space { node, font, text, .. } => {
+                    for entry in &mut stack {
+                        entry.2 = true;
+                    }
+                    match fragments.last_mut() {
+                        // 同一个文本节点中相邻的文字合并为一个片段
+                        Some(Fragment { node: last, rect, kind: FragmentKind::Text { text: last_text, .. } }) if ptr::eq(*last, node) => {
+                            last_text.push_str(&text);
+                            rect.width += width;
+                        }
+                        _ => {
+                            let rect = Rect { x, y: baseline - font.ascent(), width, height: font.ascent() + font.descent() };
+                            fragments.push(Fragment { node, rect, kind: FragmentKind::Text { text, font } });
+                        }
+                    }
+                    x += width;
+                }
+                PieceKind::Atomic { index, margin_box, ascent } => {
+                    for entry in &mut stack {
+                        entry.2 = true;
+                    }
+                    self.box_offsets[index] = (x - margin_box.x, baseline - ascent - margin_box.y);
+                    x += width;
+                }
+                PieceKind::Break(..) => {}
+            }
+        }
+        // 没有结束的行内元素延续到下一行
+        for (_, index, _) in stack {
+            let fragment = &mut fragments[index];
+            fragment.rect.width = x - fragment.rect.x;
+        }
+
+        let rect = Rect { x: self.line_left, y: self.y, width: self.line_width, height: above + below };
+        self.y += rect.height;
+        self.lines.push(LineBox { rect, baseline, used_width: used, fragments });
+        self.start_line();
+    }
+}
+
+impl<'a> Piece<'a> {
+    fn is_visible(&self) -> bool {
+        match self.kind {
+            PieceKind::Word { .. } | PieceKind::Space { .. } | PieceKind::Break(..) | PieceKind::Atomic { .. } => true,
+            PieceKind::Edge => self.width > 0.0,
+        }
+    }
+
+    // 文字和行内级盒子，行中有它们时放不下的内容需要换行
+    fn is_word(&self) -> bool {
+        matches!(self.kind, PieceKind::Word { .. } | PieceKind::Atomic { .. })
+    }
+
+    // 除了所在的行内元素以外，影响行高的文本节点
+    fn strut(&self) -> Option<(&'a StyledNode<'a>, &Font)> {
+        match self.kind {
+            PieceKind::Word { node, ref font, .. } | PieceKind::Space { node, ref font, .. } | PieceKind::Break(node, ref font) => Some((node, font)),
+            PieceKind::Edge | PieceKind::Atomic { .. } => None,
+        }
+    }
+}
+
+// 是否是 boxes 中的元素（当前还没有结束的元素）开始处的边
+fn is_start_edge(piece: &Piece, boxes: &Boxes) -> bool {
+    matches!(piece.kind, PieceKind::Edge) && piece.boxes.len() <= boxes.len()
+        && piece.boxes.iter().zip(boxes.iter()).all(|((a, _), (b, _))| ptr::eq(*a, *b))
+}
+
+// 元素左侧或者右侧的外边距，以及外边距、边框和内边距的宽度之和
+fn edge(style: &ComputedStyle, basis: f32, left: bool) -> (f32, f32) {
+    if left {
+        let margin = style.margin.left.resolve(Some(basis)).to_px();
+        (margin, margin + style.border_width.left + style.padding.left.resolve(basis))
+    } else {
+        let margin = style.margin.right.resolve(Some(basis)).to_px();
+        (margin, margin + style.border_width.right + style.padding.right.resolve(basis))
+    }
+}
+
+// 行内元素在一行中的边框盒，位置和宽度在遇到元素的边或者片段结束时确定，垂直方向的边框和内边距不影响行高
+fn box_fragment<'a>(node: &'a StyledNode<'a>, font: &Font, x: f32, baseline: f32, basis: f32) -> Fragment<'a> {
+    let style = &node.style;
+    let (ascent, descent) = (font.ascent(), font.descent());
+    let top = style.border_width.top + style.padding.top.resolve(basis);
+    let bottom = style.border_width.bottom + style.padding.bottom.resolve(basis);
+    Fragment {
+        node,
+        rect: Rect { x, y: baseline - ascent - top, width: 0.0, height: top + ascent + descent + bottom },
+        kind: FragmentKind::Box { left: false, right: false },
+    }
+}
+
+// 行内内容中作为一个整体排列的行内级盒子，目前只有 display: inline-flex 的弹性容器
+fn is_atomic(layout_box: &LayoutBox) -> bool {
+    matches!(layout_box.box_type, BlockNode(node) if node.style.display == Display::InlineFlex)
+}
+
+// 按照布局时的顺序把行内内容中的浮动盒子和行内级盒子移动到放置的位置，它们里面的盒子跟着一起移动
+fn move_boxes(layout_box: &mut LayoutBox, offsets: &[(f32, f32)], index: &mut usize, basis: f32) {
+    match layout_box.box_type {
+        _ if layout_box.is_out_of_flow() => {}
+        BlockNode(_) if layout_box.is_floated() || is_atomic(layout_box) => {
+            let (dx, dy) = offsets[*index];
+            *index += 1;
+            layout_box.translate(dx, dy);
+            layout_box.apply_relative_offset(basis, None);
+        }
+        BlockNode(_) => {}
+        InlineNode(_) | AnonymousBlock => {
+            for child in &mut layout_box.children {
+                move_boxes(child, offsets, index, basis);
+            }
+        }
+    }
+}
+
+// 行内盒子的尺寸为它在各行中的片段的外接矩形
+fn assign_dimensions(layout_box: &mut LayoutBox, lines: &[LineBox], basis: f32) {
+    let InlineNode(node) = layout_box.box_type else { return };
+    let rects = lines.iter().flat_map(|line| &line.fragments)
+        .filter(|fragment| ptr::eq(fragment.node, node))
+        .map(|fragment| fragment.rect);
+    let Some(bounds) = rects.reduce(union) else { return };
+
+    let style = &node.style;
+    let d = &mut layout_box.dimensions;
+    if let NodeType::Element(_) = node.node.node_type {
+        d.border = EdgeSizes {
+            left: style.border_width.left,
+            right: style.border_width.right,
+            top: style.border_width.top,
+            bottom: style.border_width.bottom,
+        };
+        d.padding = EdgeSizes {
+            left: style.padding.left.resolve(basis),
+            right: style.padding.right.resolve(basis),
+            top: style.padding.top.resolve(basis),
+            bottom: style.padding.bottom.resolve(basis),
+        };
+        d.margin.left = style.margin.left.resolve(Some(basis)).to_px();
+        d.margin.right = style.margin.right.resolve(Some(basis)).to_px();
+        d.content = Rect {
+            x: bounds.x + d.border.left + d.padding.left,
+            y: bounds.y + d.border.top + d.padding.top,
+            width: bounds.width - d.border.left - d.padding.left - d.border.right - d.padding.right,
+            height: bounds.height - d.border.top - d.padding.top - d.border.bottom - d.padding.bottom,
+        };
+    } else {
+        d.content = bounds;
+    }
+    for child in &mut layout_box.children {
+        assign_dimensions(child, lines, basis);
+    }
+}
+
+// 相对定位的行内元素和它里面的内容一起移动，包含块为匿名块所在的块元素，它的高度还不确定
+fn offset_relative_boxes(layout_box: &mut LayoutBox, lines: &mut [LineBox], basis: f32) {
+    let InlineNode(node) = layout_box.box_type else { return };
+    if node.style.position == Position::Relative {
+        let (dx, dy) = relative_offset(&node.style, basis, None);
+        for fragment in lines.iter_mut().flat_map(|line| &mut line.fragments) {
+            if contains(node, fragment.node) {
+                fragment.rect.x += dx;
+                fragment.rect.y += dy;
+            }
+        }
+        layout_box.translate(dx, dy);
+    }
+    for child in &mut layout_box.children {
+        offset_relative_boxes(child, lines, basis);
+    }
+}
+
+// descendant 是否是 node 或者它的子孙
+fn contains(node: &StyledNode, descendant: &StyledNode) -> bool {
+    ptr::eq(node, descendant) || node.children.iter().any(|child| contains(child, descendant))
+}
+
+fn union(a: Rect, b: Rect) -> Rect {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    let right = (a.x + a.width).max(b.x + b.width);
+    let bottom = (a.y + a.height).max(b.y + b.height);
+    Rect { x, y, width: right - x, height: bottom - y }
+}
+
+// line-height: normal 由字体决定
+fn line_height(style: &ComputedStyle, font: &Font) -> f32 {
+    match style.line_height {
+        LineHeight::Normal => font.normal_line_height(),
+        LineHeight::Number(n) => style.font_size * n,
+        LineHeight::Px(px) => px,
+    }
+}
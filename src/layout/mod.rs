@@ -0,0 +1,695 @@
+/**
+ * 该模块负责将‘样式树’生成‘布局树’，结构如下：
+ * {
+ *    dimensions：当前节点的尺寸信息，
+ *    box_type：节点类型，块状或内联，
+ *    children：子节点信息
+ * }
+ */
+use std::default::Default;
+
+use crate::{font::FontCollection, html::types::NodeType, style::{StyledNode, types::{BoxSizing, ComputedStyle, Display, Float, LengthPercentageAuto, Position}}};
+
+pub use self::BoxType::{AnonymousBlock, InlineNode, BlockNode};
+use self::float::FloatContext;
+pub use self::inline::{Fragment, FragmentKind, LineBox};
+pub use self::positioned::relative_offset;
+
+pub mod inline;
+mod flex;
+mod float;
+mod positioned;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Dimensions {
+    // 内容区域相对于文档的位置
+    pub content: Rect,
+    // 边缘信息
+    pub padding: EdgeSizes,
+    pub border: EdgeSizes,
+    pub margin: EdgeSizes,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+// 相邻的垂直外边距合并后的外边距：正的外边距取最大值，负的外边距取绝对值最大的，结果为两者之和
+#[derive(Debug, Default, Copy, Clone)]
+struct CollapsedMargin {
+    positive: f32,
+    negative: f32,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct EdgeSizes {
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+pub struct LayoutBox<'a> {
+    pub dimensions: Dimensions,
+    pub box_type: BoxType<'a>,
+    pub children: Vec<LayoutBox<'a>>,
+    // 匿名块中行内内容排列成的行，其他盒子为空
+    pub lines: Vec<LineBox<'a>>,
+    // 绝对定位和固定定位的盒子在普通流中的位置（外边距盒的左上角），top、left 等为 auto 时使用
+    static_position: (f32, f32),
+}
+
+// 一个或多个行内元素默认会生成一个 AnonymousBlock 匿名块容器
+pub enum BoxType<'a> {
+    BlockNode(&'a StyledNode<'a>),
+    InlineNode(&'a StyledNode<'a>),
+    AnonymousBlock,
+}
+
+impl<'a> LayoutBox<'a> {
+    // 构造函数
+    fn new(box_type: BoxType) -> LayoutBox {
+        LayoutBox { 
+            box_type, 
+            dimensions: Default::default(), 
+            children: Vec::new(),
+            lines: Vec::new(),
+            static_position: (0.0, 0.0),
+        }
+    }
+
+    // 获取样式节点
+    fn get_style_node(&self) -> &'a StyledNode<'a> {
+        match self.box_type {
+            BlockNode(node) | InlineNode(node) => node,
+            AnonymousBlock => panic!("Anonymous block box has no style node")
+        }
+    }
+
+    // 匿名块总是按照 static 处理
+    fn position(&self) -> Position {
+        match self.box_type {
+            BlockNode(node) | InlineNode(node) => node.style.position,
+            AnonymousBlock => Position::Static,
+        }
+    }
+
+    // 绝对定位和固定定位的盒子不参与普通流的布局
+    fn is_out_of_flow(&self) -> bool {
+        matches!(self.position(), Position::Absolute | Position::Fixed)
+    }
+}
+
+// 转换样式树到布局树（containing_block 为外部容器的尺寸）
+// fonts 用于测量和排列文字
+pub fn layout_tree<'a>(node: &'a StyledNode<'a>, mut containing_block: Dimensions, fonts: &FontCollection) -> LayoutBox<'a> {
+    // 外部容器（视口）的高度是确定的，根元素的百分比高度相对于它计算
+    let viewport = containing_block.content;
+    // 布局高度从 0 开始计算
+    containing_block.content.height = 0.0;
+    let mut root_box = build_layout_tree(node, false);
+    root_box.layout(containing_block, Some(viewport.height), fonts);
+
+    // 没有定位祖先的绝对定位盒子和固定定位的盒子相对于视口布局，没有祖先滚动容器的粘性定位盒子相对于视口调整位置
+    let absolute = (root_box.position() == Position::Static).then_some(viewport);
+    root_box.layout_positioned_descendants(absolute, Some(viewport), fonts);
+    root_box.apply_sticky_offsets(viewport);
+    root_box
+}
+
+// 构建布局树但是不进行计算，blockified 为 true 时行内元素也生成块盒子
+fn build_layout_tree<'a>(style_node: &'a StyledNode<'a>, blockified: bool) -> LayoutBox<'a> {
+    // 创建根盒子
+    let style = &style_node.style;
+    let mut root = LayoutBox::new(match style.display {
+        Display::Block | Display::FlowRoot | Display::Flex | Display::InlineFlex => BlockNode(style_node),
+        Display::Inline if blockified || matches!(style.position, Position::Absolute | Position::Fixed) || style.float != Float::None => {
+            BlockNode(style_node)
+        }
+        Display::Inline => InlineNode(style_node),
+        Display::None => panic!("Root node has display: none.")
+    });
+
+    // 弹性容器的每个子元素都是块级的弹性项目，连续的文字放进匿名的弹性项目，只有空白的文字被忽略
+    // 弹性项目按照 order 从小到大排列，相同时按照文档顺序
+    if root.is_flex_container() {
+        for child in &style_node.children {
+            match child.node.node_type {
+                _ if child.style.display == Display::None => {}
+                NodeType::Text(ref text) if text.trim().is_empty() => {}
+                NodeType::Text(_) => root.get_inline_container().children.push(build_layout_tree(child, false)),
+                NodeType::Element(_) => root.children.push(build_layout_tree(child, true)),
+            }
+        }
+        root.children.sort_by_key(|child| match child.box_type {
+            BlockNode(node) => node.style.order,
+            _ => 0,
+        });
+        return root;
+    }
+
+    // 递归遍历子盒子
+    // 绝对定位、固定定位和浮动的行内元素按照块元素布局，但是留在行内内容中以确定它的静态位置和所在的行
+    // 前面有行内内容的浮动块元素也放进同一个匿名块，和前面的文字排在同一行
+    for child in &style_node.children {
+        match child.style.display {
+            Display::Block | Display::FlowRoot | Display::Flex if child.style.float != Float::None && root.ends_with_inline_content() => {
+                root.get_inline_container().children.push(build_layout_tree(child, false))
+            }
+            Display::Block | Display::FlowRoot | Display::Flex => root.children.push(build_layout_tree(child, false)),
+            Display::Inline | Display::InlineFlex => root.get_inline_container().children.push(build_layout_tree(child, false)),
+            Display::None => {}
+        }
+    }
+
+    root
+}
+
+impl<'a> LayoutBox<'a> {
+    // 计算尺寸，containing_block 的高度为其中已经布局的内容的高度，containing_height 为包含块确定的高度
+    // 盒子的外边距不和外面的外边距合并，也不和子盒子的外边距合并
+    fn layout(&mut self, containing_block: Dimensions, containing_height: Option<f32>, fonts: &FontCollection) {
+        match self.box_type {
+            BlockNode(_) => {
+                self.layout_block(containing_block, containing_height, CollapsedMargin::default(), true, &mut FloatContext::default(), fonts);
+                self.apply_relative_offset(containing_block.content.width, containing_height);
+            }
+            // 匿名块需要父元素的样式，由父元素布局
+            AnonymousBlock => panic!("Anonymous block box is laid out by its parent"),
+            // 行内盒子由包含它的匿名块排列
+            InlineNode(_) => {}
+        }
+    }
+
+    // 匿名块占满包含块的宽度，高度为其中全部行的高度，style 为父元素的样式，行盒在 floats 中的浮动盒子旁边变短
+    fn layout_anonymous_block(&mut self, containing_block: Dimensions, style: &ComputedStyle, floats: &mut FloatContext, fonts: &FontCollection) {
+        let d = &mut self.dimensions;
+        d.content.x = containing_block.content.x;
+        d.content.y = containing_block.content.y + containing_block.content.height;
+        d.content.width = containing_block.content.width;
+
+        let (lines, height) = inline::layout_lines(&mut self.children, d.content, style, floats, fonts);
+        d.content.height = height;
+        self.lines = lines;
+    }
+
+    // containing_height 为包含块的高度，包含块的高度取决于内容时为 None
+    // margin 为上方还没有确定的外边距，independent 为 true 时外边距不和子盒子的外边距合并
+    // floats 为盒子所在的块格式化上下文中的浮动盒子，盒子建立新的块格式化上下文时不使用
+    // 返回下方还没有确定的外边距，以及盒子的上下外边距是否合并在了一起
+    fn layout_block(&mut self, containing_block: Dimensions, containing_height: Option<f32>, margin: CollapsedMargin, independent: bool, floats: &mut FloatContext, fonts: &FontCollection) -> (CollapsedMargin, bool) {
+        let independent = independent || self.establishes_formatting_context();
+        // 计算盒子的宽度
+        self.calculate_block_width(containing_block);
+        // 计算盒子定位
+        let margin = self.calculate_block_position(containing_block, margin, independent);
+        // 高度确定时，子盒子的百分比高度相对于它计算
+        let height = self.definite_height(containing_height);
+        // 递归计算子框
+        let (margin, separated) = if independent {
+            self.layout_formatting_context(height, fonts)
+        } else {
+            self.layout_block_children(height, margin, false, floats, fonts)
+        };
+        // 计算高度
+        let result = self.calculate_block_height(containing_height, height, margin, separated, independent);
+        // 定位元素是绝对定位后代的包含块
+        if self.position() != Position::Static {
+            self.layout_positioned_descendants(Some(self.dimensions.padding_box()), None, fonts);
+        }
+        result
+    }
+
+    fn calculate_block_width(&mut self, containing_block: Dimensions) {
+        let style = &self.get_style_node().style;
+
+        // 百分比和 calc() 相对于包含块的宽度计算
+        let basis = containing_block.content.width;
+
+        let margin_left = style.margin.left.resolve(Some(basis));
+        let margin_right = style.margin.right.resolve(Some(basis));
+
+        let border_left = style.border_width.left;
+        let border_right = style.border_width.right;
+
+        let padding_left = style.padding.left.resolve(basis);
+        let padding_right = style.padding.right.resolve(basis);
+
+        // box-sizing 为 border-box 时，width、min-width 和 max-width 指定的是边框盒的宽度
+        let edges = border_left + border_right + padding_left + padding_right;
+        let content_width = |width: f32| match style.box_sizing {
+            BoxSizing::ContentBox => width,
+            BoxSizing::BorderBox => (width - edges).max(0.0),
+        };
+        let width = match style.width.resolve(Some(basis)) {
+            LengthPercentageAuto::Px(width) => Some(content_width(width)),
+            _ => None,
+        };
+        let min_width = content_width(style.min_width.resolve(Some(basis)).to_px());
+        let max_width = style.max_width.as_ref().map(|width| content_width(width.resolve(basis)));
+
+        // 先按照 width 计算，宽度大于 max-width 时按照 max-width 重新计算，再小于 min-width 时按照 min-width 重新计算
+        let mut used = block_width(width, &margin_left, &margin_right, edges, basis);
+        if let Some(max_width) = max_width {
+            if used.0 > max_width {
+                used = block_width(Some(max_width), &margin_left, &margin_right, edges, basis);
+            }
+        }
+        if used.0 < min_width {
+            used = block_width(Some(min_width), &margin_left, &margin_right, edges, basis);
+        }
+        let (width, margin_left, margin_right) = used;
+
+        let d = &mut self.dimensions;
+        d.content.width = width;
+
+        d.padding.left = padding_left;
+        d.padding.right = padding_right;
+
+        d.border.left = border_left;
+        d.border.right = border_right;
+
+        d.margin.left = margin_left;
+        d.margin.right = margin_right;
+    }
+
+    // 在包含块的左侧、y 的位置布局浮动盒子和行内级的弹性容器，之后再移动到最终的位置
+    // 宽度为 auto 时使用收缩适应的宽度，auto 的外边距视为 0
+    fn layout_shrink_to_fit(&mut self, containing_block: Rect, containing_height: Option<f32>, y: f32, fonts: &FontCollection) {
+        let style = &self.get_style_node().style;
+        let basis = containing_block.width;
+
+        let d = &mut self.dimensions;
+        d.margin.left = style.margin.left.resolve(Some(basis)).to_px();
+        d.margin.right = style.margin.right.resolve(Some(basis)).to_px();
+        d.margin.top = style.margin.top.resolve(Some(basis)).to_px();
+        d.margin.bottom = style.margin.bottom.resolve(Some(basis)).to_px();
+        d.border.left = style.border_width.left;
+        d.border.right = style.border_width.right;
+        d.border.top = style.border_width.top;
+        d.border.bottom = style.border_width.bottom;
+        d.padding.left = style.padding.left.resolve(basis);
+        d.padding.right = style.padding.right.resolve(basis);
+        d.padding.top = style.padding.top.resolve(basis);
+        d.padding.bottom = style.padding.bottom.resolve(basis);
+        let edges = d.border.left + d.border.right + d.padding.left + d.padding.right;
+        let available = basis - d.margin.left - d.margin.right - edges;
+
+        let content_width = |width: f32| match style.box_sizing {
+            BoxSizing::ContentBox => width,
+            BoxSizing::BorderBox => (width - edges).max(0.0),
+        };
+        let width = match style.width.resolve(Some(basis)) {
+            LengthPercentageAuto::Px(width) => content_width(width),
+            _ => {
+                let (min_content, max_content) = self.intrinsic_widths(fonts);
+                min_content.max(available).min(max_content)
+            }
+        };
+        // 限制在 min-width 和 max-width 之间
+        let max_width = style.max_width.as_ref().map_or(f32::INFINITY, |width| content_width(width.resolve(basis)));
+        let min_width = content_width(style.min_width.resolve(Some(basis)).to_px());
+
+        let d = &mut self.dimensions;
+        d.content.width = width.min(max_width).max(min_width);
+        d.content.x = containing_block.x + d.margin.left + d.border.left + d.padding.left;
+        d.content.y = y + d.margin.top + d.border.top + d.padding.top;
+        d.content.height = 0.0;
+
+        let height = self.definite_height(containing_height);
+        let (margin, separated) = self.layout_formatting_context(height, fonts);
+        self.calculate_block_height(containing_height, height, margin, separated, true);
+        if self.position() != Position::Static {
+            self.layout_positioned_descendants(Some(self.dimensions.padding_box()), None, fonts);
+        }
+    }
+
+    // 返回和第一个子盒子的上外边距合并的外边距
+    fn calculate_block_position(&mut self, containing_block: Dimensions, margin: CollapsedMargin, independent: bool) -> CollapsedMargin {
+        let style = &self.get_style_node().style;
+        let d = &mut self.dimensions;
+
+        // 垂直方向的百分比外边距和内边距也相对于包含块的宽度计算
+        let basis = containing_block.content.width;
+
+        // 如果 margin-top、margin-bottom 是 auto，则使用 0
+        d.margin.top = style.margin.top.resolve(Some(basis)).to_px();
+        d.margin.bottom = style.margin.bottom.resolve(Some(basis)).to_px();
+        
+        d.border.top = style.border_width.top;
+        d.border.bottom = style.border_width.bottom;
+
+        d.padding.top = style.padding.top.resolve(basis);
+        d.padding.bottom = style.padding.bottom.resolve(basis);
+
+        d.content.x = containing_block.content.x + d.margin.left + d.border.left + d.padding.left;
+        // 放在包含块中已经布局的兄弟盒子下面，上外边距和前面的兄弟盒子的下外边距合并
+        let margin = margin.adjoin(CollapsedMargin::new(d.margin.top));
+        d.content.y = containing_block.content.y + containing_block.content.height;
+        // 计算最小宽度和最大宽度时可能已经布局过，高度重新从 0 开始计算
+        d.content.height = 0.0;
+        if independent || d.border.top != 0.0 || d.padding.top != 0.0 {
+            d.content.y += margin.resolve() + d.border.top + d.padding.top;
+            CollapsedMargin::default()
+        } else {
+            // 没有上边框和上内边距时，外边距还要和第一个子盒子的上外边距合并，等子盒子布局后再确定位置
+            margin
+        }
+    }
+
+    // height 为盒子确定的高度，margin 为第一个子盒子上方的外边距，floats 为盒子所在的块格式化上下文中的浮动盒子
+    // 返回最后一个子盒子下方的外边距，以及子盒子是否把盒子的上下外边距分开
+    fn layout_block_children(&mut self, height: Option<f32>, mut margin: CollapsedMargin, independent: bool, floats: &mut FloatContext, fonts: &FontCollection) -> (CollapsedMargin, bool) {
+        let style = &self.get_style_node().style;
+        let d = &mut self.dimensions;
+        // 有上边框或上内边距时，盒子的上外边距已经确定
+        let mut settled = independent || d.border.top != 0.0 || d.padding.top != 0.0;
+        let mut separated = false;
+        for child in &mut self.children {
+            // 脱离普通流的盒子只记录静态位置，由包含块布局
+            if child.is_out_of_flow() {
+                child.static_position = (d.content.x, d.content.y + d.content.height + margin.resolve());
+                continue;
+            }
+            // 浮动盒子放在前面的外边距下面，不影响后面的盒子的位置和外边距合并
+            if child.is_floated() {
+                let y = d.content.y + d.content.height + margin.resolve();
+                child.layout_shrink_to_fit(d.content, height, y, fonts);
+                child.place_float(floats, d.content, y);
+                child.apply_relative_offset(d.content.width, height);
+                continue;
+            }
+            // 有间隙的盒子上边框至少在这个位置
+            let mut cleared = f32::NEG_INFINITY;
+            let through = match child.box_type {
+                AnonymousBlock => {
+                    let mut containing_block = *d;
+                    containing_block.content.height += margin.resolve();
+                    child.layout_anonymous_block(containing_block, style, floats, fonts);
+                    // 没有行的匿名块不影响外边距合并
+                    if child.dimensions.content.height == 0.0 {
+                        continue;
+                    }
+                    margin = CollapsedMargin::default();
+                    false
+                }
+                _ => {
+                    let child_style = &child.get_style_node().style;
+                    let margin_top = CollapsedMargin::new(child_style.margin.top.resolve(Some(d.content.width)).to_px());
+                    // clear 的盒子和前面的外边距合并后仍然在需要清除的浮动盒子旁边时，加上间隙使上边框移动到浮动盒子下面
+                    // 有间隙时前面的外边距不再和盒子的上外边距合并
+                    if let Some(bottom) = floats.clearance(child_style.clear) {
+                        if d.content.y + d.content.height + margin.adjoin(margin_top).resolve() < bottom {
+                            if !settled {
+                                d.content.y += margin.resolve();
+                                settled = true;
+                            }
+                            margin = CollapsedMargin::default();
+                            d.content.height = bottom - margin_top.resolve() - d.content.y;
+                            cleared = bottom;
+                        }
+                    }
+                    let mut containing_block = *d;
+                    // 建立新的块格式化上下文的盒子不和浮动盒子重叠，放在上边框旁边的浮动盒子以外的空间中
+                    if child.establishes_formatting_context() {
+                        let y = d.content.y + d.content.height + margin.adjoin(margin_top).resolve();
+                        let (x, width) = floats.band(y, 0.0, d.content.x, d.content.width);
+                        containing_block.content.x = x;
+                        containing_block.content.width = width;
+                    }
+                    let (bottom, through) = child.layout_block(containing_block, height, margin, false, floats, fonts);
+                    margin = bottom;
+                    through && cleared == f32::NEG_INFINITY
+                }
+            };
+            // 上下外边距合并在一起的子盒子高度为 0，外边距继续和后面的合并
+            if !through {
+                let border_box = child.dimensions.border_box();
+                // 第一个有内容的子盒子的上外边距和盒子的上外边距合并，盒子的内容区从子盒子的边框开始
+                if !settled {
+                    d.content.y = border_box.y;
+                    settled = true;
+                }
+                separated = true;
+                // 计算高度
+                d.content.height = (border_box.y + border_box.height).max(cleared) - d.content.y;
+            }
+            // 相对定位的偏移不影响后面的盒子
+            child.apply_relative_offset(d.content.width, height);
+        }
+        (margin, separated)
+    }
+
+    // 高度为长度，或者是包含块高度确定时的百分比，返回限制在 min-height 和 max-height 之间的内容区高度
+    // 否则高度取决于内容，返回 None
+    fn definite_height(&self, containing_height: Option<f32>) -> Option<f32> {
+        match self.get_style_node().style.height.resolve(containing_height) {
+            LengthPercentageAuto::Px(height) => Some(self.clamp_height(self.content_height(height), containing_height)),
+            _ => None,
+        }
+    }
+
+    // box-sizing 为 border-box 时，height、min-height 和 max-height 指定的是边框盒的高度
+    fn content_height(&self, height: f32) -> f32 {
+        let d = &self.dimensions;
+        match self.get_style_node().style.box_sizing {
+            BoxSizing::ContentBox => height,
+            BoxSizing::BorderBox => (height - d.border.top - d.border.bottom - d.padding.top - d.padding.bottom).max(0.0),
+        }
+    }
+
+    // 高度大于 max-height 时使用 max-height，再小于 min-height 时使用 min-height
+    // 包含块的高度不确定时，百分比的 min-height 视为 0，max-height 视为 none
+    fn clamp_height(&self, height: f32, containing_height: Option<f32>) -> f32 {
+        let style = &self.get_style_node().style;
+        let max_height = style.max_height.as_ref().and_then(|h| h.try_resolve(containing_height));
+        let min_height = style.min_height.resolve(containing_height).to_px();
+        let height = max_height.map_or(height, |max_height| height.min(self.content_height(max_height)));
+        height.max(self.content_height(min_height))
+    }
+
+    // height 为盒子确定的高度，margin 为最后一个子盒子下方的外边距，separated 表示子盒子是否把盒子的上下外边距分开
+    fn calculate_block_height(&mut self, containing_height: Option<f32>, height: Option<f32>, margin: CollapsedMargin, separated: bool, independent: bool) -> (CollapsedMargin, bool) {
+        let d = self.dimensions;
+        let settled = independent || d.border.top != 0.0 || d.padding.top != 0.0;
+        let bottom = CollapsedMargin::new(d.margin.bottom);
+
+        // 没有下边框和下内边距且高度取决于内容时，最后一个子盒子的下外边距和盒子的下外边距合并
+        let open_bottom = !independent && height.is_none() && d.border.bottom == 0.0 && d.padding.bottom == 0.0;
+        let content_height = match height {
+            // 如果高度是确定的，则使用该值
+            Some(height) => height,
+            // 否则最后一个子盒子的下外边距留在盒子里面
+            None if !open_bottom && (settled || separated) => self.clamp_height(d.content.height + margin.resolve(), containing_height),
+            None => self.clamp_height(d.content.height, containing_height),
+        };
+        let d = &mut self.dimensions;
+        d.content.height = content_height;
+
+        if !settled && !separated {
+            // 没有内容且没有上下边框和内边距的盒子，上下外边距和子盒子的外边距全部合并在一起
+            if d.content.height == 0.0 && d.border.bottom == 0.0 && d.padding.bottom == 0.0 {
+                return (margin.adjoin(bottom), true);
+            }
+            // 否则子盒子的外边距都和盒子的上外边距合并
+            d.content.y += margin.resolve();
+            return (bottom, false);
+        }
+        if open_bottom {
+            return (margin.adjoin(bottom), false);
+        }
+        (bottom, false)
+    }
+
+    // 内容的最小宽度和最大宽度（min-content 和 max-content），即在每个换行机会处都换行和只在强制换行处换行时最宽的子盒子
+    fn intrinsic_widths(&mut self, fonts: &FontCollection) -> (f32, f32) {
+        if self.is_flex_container() {
+            return self.flex_intrinsic_widths(fonts);
+        }
+        let style = &self.get_style_node().style;
+        let (mut min, mut max) = (0.0f32, 0.0f32);
+        for child in &mut self.children {
+            let (child_min, child_max) = match child.box_type {
+                _ if child.is_out_of_flow() => continue,
+                AnonymousBlock => inline::intrinsic_widths(&mut child.children, style, fonts),
+                _ => child.outer_intrinsic_widths(fonts),
+            };
+            min = min.max(child_min);
+            max = max.max(child_max);
+        }
+        (min, max)
+    }
+
+    // 盒子在包含块中占据的最小宽度和最大宽度，包括外边距、边框和内边距，其中的百分比视为 0
+    fn outer_intrinsic_widths(&mut self, fonts: &FontCollection) -> (f32, f32) {
+        let style = &self.get_style_node().style;
+        let edges = style.border_width.left + style.border_width.right + style.padding.left.resolve(0.0) + style.padding.right.resolve(0.0);
+        let margins = style.margin.left.resolve(Some(0.0)).to_px() + style.margin.right.resolve(Some(0.0)).to_px();
+        let content_width = |width: f32| match style.box_sizing {
+            BoxSizing::ContentBox => width,
+            BoxSizing::BorderBox => (width - edges).max(0.0),
+        };
+        let (min, max) = match style.width.resolve(None) {
+            LengthPercentageAuto::Px(width) => (content_width(width), content_width(width)),
+            _ => self.intrinsic_widths(fonts),
+        };
+        // 限制在 min-width 和 max-width 之间
+        let max_width = style.max_width.as_ref().and_then(|width| width.try_resolve(None)).map_or(f32::INFINITY, content_width);
+        let min_width = content_width(style.min_width.resolve(None).to_px());
+        let clamp = |width: f32| width.min(max_width).max(min_width);
+        (clamp(min) + edges + margins, clamp(max) + edges + margins)
+    }
+
+    // 盒子中第一个行盒的基线，没有行盒时返回 None
+    fn first_baseline(&self) -> Option<f32> {
+        if let Some(line) = self.lines.first() {
+            return Some(line.baseline);
+        }
+        self.children.iter()
+            .filter(|child| !child.is_out_of_flow() && !child.is_floated())
+            .find_map(LayoutBox::first_baseline)
+    }
+
+    // 把盒子和它的全部子孙移动 (dx, dy)，包括匿名块中的行
+    fn translate(&mut self, dx: f32, dy: f32) {
+        self.dimensions.content.x += dx;
+        self.dimensions.content.y += dy;
+        self.static_position.0 += dx;
+        self.static_position.1 += dy;
+        for line in &mut self.lines {
+            line.rect.x += dx;
+            line.rect.y += dy;
+            line.baseline += dy;
+            for fragment in &mut line.fragments {
+                fragment.rect.x += dx;
+                fragment.rect.y += dy;
+            }
+        }
+        for child in &mut self.children {
+            child.translate(dx, dy);
+        }
+    }
+
+    // 最后一个子盒子是匿名块，或者盒子本身就是行内盒子
+    fn ends_with_inline_content(&self) -> bool {
+        match self.box_type {
+            InlineNode(_) | AnonymousBlock => true,
+            BlockNode(_) => matches!(self.children.last(), Some(&LayoutBox { box_type: AnonymousBlock, .. })),
+        }
+    }
+
+    // 创建匿名块容器
+    fn get_inline_container(&mut self) -> &mut LayoutBox<'a> {
+        match self.box_type {
+            // 如果自己本身是内联元素则不用生成
+            InlineNode(_) | AnonymousBlock => self,
+            // 如果自己是块状元素则需要生成匿名块元素
+            BlockNode(_) => {
+                // 如果前一个内联元素已经生成过匿名块元素，则直接复用
+                match self.children.last() {
+                    Some(&LayoutBox { box_type: AnonymousBlock, .. }) => {},
+                    _ => self.children.push(LayoutBox::new(AnonymousBlock))
+                }
+                self.children.last_mut().unwrap()
+            }
+        }
+    }
+}
+
+impl CollapsedMargin {
+    fn new(margin: f32) -> CollapsedMargin {
+        CollapsedMargin { positive: margin.max(0.0), negative: margin.min(0.0) }
+    }
+
+    fn adjoin(self, other: CollapsedMargin) -> CollapsedMargin {
+        CollapsedMargin {
+            positive: self.positive.max(other.positive),
+            negative: self.negative.min(other.negative),
+        }
+    }
+
+    fn resolve(self) -> f32 {
+        self.positive + self.negative
+    }
+}
+
+impl Rect {
+    pub fn expanded_by(self, edge: EdgeSizes) -> Rect {
+        Rect { 
+            x: self.x - edge.left,
+            y: self.y - edge.top,
+            width: self.width + edge.left + edge.right,
+            height: self.height + edge.top + edge.bottom,
+        }
+    }
+}
+
+// 非常优美的写法，从内容区依次向外扩张尺寸
+impl Dimensions {
+    pub fn padding_box(self) -> Rect {
+        self.content.expanded_by(self.padding)
+    }
+
+    pub fn border_box(self) -> Rect {
+        self.padding_box().expanded_by(self.border)
+    }
+
+    pub fn margin_box(self) -> Rect {
+        self.border_box().expanded_by(self.margin)
+    }
+}
+
+// 计算块盒子的宽度和左右外边距，使它们和左右边框、内边距的宽度 edges 之和等于包含块的宽度
+// width 为 None 时表示 auto
+fn block_width(width: Option<f32>, margin_left: &LengthPercentageAuto, margin_right: &LengthPercentageAuto, edges: f32, containing_width: f32) -> (f32, f32, f32) {
+    let auto = LengthPercentageAuto::Auto;
+    let mut margin_left = margin_left.clone();
+    let mut margin_right = margin_right.clone();
+
+    let total = sum([margin_left.to_px(), margin_right.to_px(), edges, width.unwrap_or(0.0)].into_iter());
+
+    // 如果宽度不是 auto，并且总长大于盒子宽度，则视 merge 的 auto 为 0
+    if width.is_some() && total > containing_width {
+        if margin_left == auto {
+            margin_left = LengthPercentageAuto::Px(0.0);
+        }
+        if margin_right == auto {
+            margin_right = LengthPercentageAuto::Px(0.0);
+        }
+    }
+
+    // 溢出或剩余的空间
+    let underflow = containing_width - total;
+
+    // 调整尺寸使 width 和 total 相等
+    match (width, margin_left == auto, margin_right == auto) {
+        // 如果都为 false，则代表过度约束，计算 margin_right 的值
+        (Some(width), false, false) => (width, margin_left.to_px(), margin_right.to_px() + underflow),
+        // 如果 margin 恰好有一个尺寸使 auto，则计算它使其自等
+        (Some(width), false, true) => (width, margin_left.to_px(), underflow),
+        (Some(width), true, false) => (width, underflow, margin_right.to_px()),
+        // 如果 width 是 auto，则其它的 auto 将成为 0
+        (None, _, _) => {
+            if underflow >= 0.0 {
+                // 展开宽度填满容器
+                (underflow, margin_left.to_px(), margin_right.to_px())
+            } else {
+                // 宽度不能是负的，调整右边距
+                (0.0, margin_left.to_px(), margin_right.to_px() + underflow)
+            }
+        }
+        // 如果 margin-left 和 margin-right 都是 auto，则每个一半的值
+        (Some(width), true, true) => (width, underflow / 2.0, underflow / 2.0),
+    }
+}
+
+fn sum<I>(iter: I) -> f32 where I: Iterator<Item=f32> {
+    iter.fold(0., |a, b| a + b)
+}
\ No newline at end of file
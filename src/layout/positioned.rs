@@ -0,0 +1,307 @@
+// 定位布局：相对定位的盒子在普通流中的位置上偏移，绝对定位和固定定位的盒子脱离普通流，相对于包含块布局
+// 粘性定位的盒子在最近的祖先滚动容器中尽量保持在 top、right、bottom、left 指定的范围以内，页面没有滚动，没有祖先滚动容器时为视口
+use crate::{font::FontCollection, style::types::{BoxSizing, ComputedStyle, LengthPercentageAuto, Overflow, Position}};
+
+use super::{LayoutBox, Rect};
+
+impl<'a> LayoutBox<'a> {
+    // 布局包含块为 absolute 的绝对定位后代，以及包含块为视口 fixed 的固定定位后代，为 None 时跳过
+    // 定位元素是它里面的绝对定位盒子的包含块，它们在定位元素布局时已经布局过了
+    pub(super) fn layout_positioned_descendants(&mut self, absolute: Option<Rect>, fixed: Option<Rect>, fonts: &FontCollection) {
+        for child in &mut self.children {
+            let containing_block = match child.position() {
+                Position::Absolute => absolute,
+                Position::Fixed => fixed,
+                // 普通流中的盒子继续查找
+                Position::Static => {
+                    child.layout_positioned_descendants(absolute, fixed, fonts);
+                    continue;
+                }
+                Position::Relative | Position::Sticky => None,
+            };
+            if let Some(containing_block) = containing_block {
+                child.layout_absolute(containing_block, fonts);
+            }
+            // 定位元素中的固定定位后代仍然相对于视口布局
+            if fixed.is_some() {
+                child.layout_positioned_descendants(None, fixed, fonts);
+            }
+        }
+    }
+
+    // 绝对定位的盒子在包含块中的位置和尺寸由 top、right、bottom、left、宽度、高度和外边距共同决定（CSS 2.1 10.3.7 和 10.6.4）
+    // 按照从左到右的方向处理
+    fn layout_absolute(&mut self, containing_block: Rect, fonts: &FontCollection) {
+        let style = &self.get_style_node().style;
+        // 百分比的外边距、内边距以及 left、right 和宽度相对于包含块的宽度计算，top、bottom 和高度相对于包含块的高度计算
+        let (basis, vertical_basis) = (containing_block.width, containing_block.height);
+        let resolve = |value: &LengthPercentageAuto, basis: f32| match value.resolve(Some(basis)) {
+            LengthPercentageAuto::Px(f) => Some(f),
+            _ => None,
+        };
+
+        let d = &mut self.dimensions;
+        d.border.left = style.border_width.left;
+        d.border.right = style.border_width.right;
+        d.border.top = style.border_width.top;
+        d.border.bottom = style.border_width.bottom;
+        d.padding.left = style.padding.left.resolve(basis);
+        d.padding.right = style.padding.right.resolve(basis);
+        d.padding.top = style.padding.top.resolve(basis);
+        d.padding.bottom = style.padding.bottom.resolve(basis);
+        let edges = d.border.left + d.border.right + d.padding.left + d.padding.right;
+        let vertical_edges = d.border.top + d.border.bottom + d.padding.top + d.padding.bottom;
+
+        // 宽度为 auto 时使用收缩适应的宽度，不超过内容的最大宽度，也不小于内容的最小宽度
+        let content_width = |width: f32| match style.box_sizing {
+            BoxSizing::ContentBox => width,
+            BoxSizing::BorderBox => (width - edges).max(0.0),
+        };
+        let (min_content, max_content) = self.intrinsic_widths(fonts);
+        let horizontal = |width: Option<f32>| solve_absolute(
+            (resolve(&style.inset.left, basis), resolve(&style.inset.right, basis)),
+            width,
+            (resolve(&style.margin.left, basis), resolve(&style.margin.right, basis)),
+            basis - edges,
+            self.static_position.0 - containing_block.x,
+            |available| min_content.max(available).min(max_content),
+            true,
+        );
+
+        // 先按照 width 计算，宽度大于 max-width 时按照 max-width 重新计算，再小于 min-width 时按照 min-width 重新计算
+        let mut used = horizontal(resolve(&style.width, basis).map(content_width));
+        if let Some(max_width) = style.max_width.as_ref().map(|width| content_width(width.resolve(basis))) {
+            if used.2 > max_width {
+                used = horizontal(Some(max_width));
+            }
+        }
+        let min_width = content_width(style.min_width.resolve(Some(basis)).to_px());
+        if used.2 < min_width {
+            used = horizontal(Some(min_width));
+        }
+        let (left, margin_left, width, margin_right) = used;
+
+        let d = &mut self.dimensions;
+        d.content.width = width;
+        d.margin.left = margin_left;
+        d.margin.right = margin_right;
+        d.content.x = containing_block.x + left + margin_left + d.border.left + d.padding.left;
+
+        // 高度为长度或者 top 和 bottom 都不是 auto 时，高度在布局内容之前就可以确定
+        let top = resolve(&style.inset.top, vertical_basis);
+        let bottom = resolve(&style.inset.bottom, vertical_basis);
+        let margin_top = resolve(&style.margin.top, basis);
+        let margin_bottom = resolve(&style.margin.bottom, basis);
+        let height = match style.height.resolve(Some(vertical_basis)) {
+            LengthPercentageAuto::Px(height) => Some(self.content_height(height)),
+            _ => None,
+        };
+        let definite = match (height, top, bottom) {
+            (Some(height), _, _) => Some(height),
+            (None, Some(top), Some(bottom)) => {
+                let margins = margin_top.unwrap_or(0.0) + margin_bottom.unwrap_or(0.0);
+                Some((vertical_basis - top - bottom - margins - vertical_edges).max(0.0))
+            }
+            _ => None,
+        }.map(|height| self.clamp_height(height, Some(vertical_basis)));
+
+        // 在包含块的顶部布局内容，确定位置之后再移动
+        let d = &mut self.dimensions;
+        d.content.y = containing_block.y + d.border.top + d.padding.top;
+        d.content.height = 0.0;
+        let provisional = d.content.y;
+        let (margin, separated) = self.layout_formatting_context(definite, fonts);
+        self.calculate_block_height(Some(vertical_basis), definite, margin, separated, true);
+
+        let content_height = self.dimensions.content.height;
+        let (top, margin_top, _, margin_bottom) = solve_absolute(
+            (top, bottom),
+            height.map(|_| content_height),
+            (margin_top, margin_bottom),
+            vertical_basis - vertical_edges,
+            self.static_position.1 - containing_block.y,
+            |_| content_height,
+            false,
+        );
+        let d = &mut self.dimensions;
+        d.margin.top = margin_top;
+        d.margin.bottom = margin_bottom;
+        let y = containing_block.y + top + margin_top + d.border.top + d.padding.top;
+        self.translate(0.0, y - provisional);
+
+        self.layout_positioned_descendants(Some(self.dimensions.padding_box()), None, fonts);
+    }
+
+    // 相对定位的盒子布局之后，和它的内容一起偏移，不影响其他盒子的位置
+    pub(super) fn apply_relative_offset(&mut self, containing_width: f32, containing_height: Option<f32>) {
+        if self.position() == Position::Relative {
+            let (dx, dy) = relative_offset(&self.get_style_node().style, containing_width, containing_height);
+            self.translate(dx, dy);
+        }
+    }
+
+    // 粘性定位的盒子离开滚动容器减去 top、right、bottom、left 之后的范围时，移动到范围以内，但是不能移出它的包含块
+    // scrollport 为最近的祖先滚动容器的内边距盒，向下遍历时遇到新的滚动容器就替换它
+    pub(super) fn apply_sticky_offsets(&mut self, scrollport: Rect) {
+        let containing_block = self.dimensions.content;
+        for child in &mut self.children {
+            if child.position() == Position::Sticky {
+                let (dx, dy) = sticky_offset(child, containing_block, scrollport);
+                child.translate(dx, dy);
+            }
+            let scrollport = if child.is_scroll_container() { child.dimensions.padding_box() } else { scrollport };
+            child.apply_sticky_offsets(scrollport);
+        }
+    }
+
+    // overflow 为 auto、scroll 或 hidden 的盒子是滚动容器，clip 不能滚动
+    fn is_scroll_container(&self) -> bool {
+        match self.box_type {
+            super::BlockNode(node) | super::InlineNode(node) => !matches!(node.style.overflow, Overflow::Visible | Overflow::Clip),
+            super::AnonymousBlock => false,
+        }
+    }
+}
+
+// left 和 right 都不是 auto 时忽略 right，top 和 bottom 都不是 auto 时忽略 bottom，包含块的高度不确定时百分比的 top 和 bottom 视为 auto
+pub fn relative_offset(style: &ComputedStyle, containing_width: f32, containing_height: Option<f32>) -> (f32, f32) {
+    let offset = |start: &LengthPercentageAuto, end: &LengthPercentageAuto, basis: Option<f32>| {
+        match (start.resolve(basis), end.resolve(basis)) {
+            (LengthPercentageAuto::Px(start), _) => start,
+            (_, LengthPercentageAuto::Px(end)) => -end,
+            _ => 0.0,
+        }
+    };
+    (
+        offset(&style.inset.left, &style.inset.right, Some(containing_width)),
+        offset(&style.inset.top, &style.inset.bottom, containing_height),
+    )
+}
+
+fn sticky_offset(layout_box: &LayoutBox, containing_block: Rect, scrollport: Rect) -> (f32, f32) {
+    let style = &layout_box.get_style_node().style;
+    let border_box = layout_box.dimensions.border_box();
+    let margin_box = layout_box.dimensions.margin_box();
+    let resolve = |value: &LengthPercentageAuto, basis: f32| match value.resolve(Some(basis)) {
+        LengthPercentageAuto::Px(f) => Some(f),
+        _ => None,
+    };
+
+    // 一个方向上的偏移：start 和 end 为盒子的边框在这个方向上的范围，可以移动的距离受盒子的外边距盒到包含块边缘的距离限制
+    // 两侧同时超出范围时开始的一侧优先
+    let offset = |(start, end): (f32, f32), (min, max): (Option<f32>, Option<f32>), (backward, forward): (f32, f32)| {
+        if let Some(min) = min.filter(|&min| start < min) {
+            return (min - start).min(forward).max(0.0);
+        }
+        if let Some(max) = max.filter(|&max| end > max) {
+            return -(end - max).min(backward).max(0.0);
+        }
+        0.0
+    };
+    let dx = offset(
+        (border_box.x, border_box.x + border_box.width),
+        (
+            resolve(&style.inset.left, scrollport.width).map(|left| scrollport.x + left),
+            resolve(&style.inset.right, scrollport.width).map(|right| scrollport.x + scrollport.width - right),
+        ),
+        (margin_box.x - containing_block.x, containing_block.x + containing_block.width - margin_box.x - margin_box.width),
+    );
+    let dy = offset(
+        (border_box.y, border_box.y + border_box.height),
+        (
+            resolve(&style.inset.top, scrollport.height).map(|top| scrollport.y + top),
+            resolve(&style.inset.bottom, scrollport.height).map(|bottom| scrollport.y + scrollport.height - bottom),
+        ),
+        (margin_box.y - containing_block.y, containing_block.y + containing_block.height - margin_box.y - margin_box.height),
+    );
+    (dx, dy)
+}
+
+// 解一个方向上的约束：start + margin_start + size + margin_end + end = space，None 表示 auto
+// space 为包含块的尺寸减去盒子的边框和内边距，static_start 为盒子的静态位置
+// fit 根据可用空间计算 auto 的尺寸，horizontal 为 true 时两侧的 auto 外边距不能是负的
+// 返回 start、margin_start、size 和 margin_end
+fn solve_absolute<F>(
+    (start, end): (Option<f32>, Option<f32>),
+    size: Option<f32>,
+    (margin_start, margin_end): (Option<f32>, Option<f32>),
+    space: f32,
+    static_start: f32,
+    fit: F,
+    horizontal: bool,
+) -> (f32, f32, f32, f32) where F: Fn(f32) -> f32 {
+    if let (Some(start), Some(size), Some(end)) = (start, size, end) {
+        let free = space - start - size - end;
+        return match (margin_start, margin_end) {
+            // 两侧的外边距都是 auto 时平分剩余的空间
+            (None, None) if horizontal && free < 0.0 => (start, 0.0, size, free),
+            (None, None) => (start, free / 2.0, size, free / 2.0),
+            (None, Some(margin_end)) => (start, free - margin_end, size, margin_end),
+            (Some(margin_start), None) => (start, margin_start, size, free - margin_start),
+            // 过度约束时忽略 end
+            (Some(margin_start), Some(margin_end)) => (start, margin_start, size, margin_end),
+        };
+    }
+
+    // 其他情况 auto 的外边距视为 0
+    let (margin_start, margin_end) = (margin_start.unwrap_or(0.0), margin_end.unwrap_or(0.0));
+    let available = |start: f32, end: f32| space - start - end - margin_start - margin_end;
+    match (start, size, end) {
+        // start 为 auto 时使用静态位置
+        (None, None, None) => (static_start, margin_start, fit(available(static_start, 0.0)), margin_end),
+        (None, Some(size), None) => (static_start, margin_start, size, margin_end),
+        (None, None, Some(end)) => {
+            let size = fit(available(0.0, end));
+            (available(0.0, end) - size, margin_start, size, margin_end)
+        }
+        (None, Some(size), Some(end)) => (available(0.0, end) - size, margin_start, size, margin_end),
+        (Some(start), None, None) => (start, margin_start, fit(available(start, 0.0)), margin_end),
+        (Some(start), None, Some(end)) => (start, margin_start, available(start, end).max(0.0), margin_end),
+        (Some(start), Some(size), _) => (start, margin_start, size, margin_end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::solve_absolute;
+
+    // 可用空间 100，静态位置 7，内容的最大宽度 30
+    fn solve(insets: (Option<f32>, Option<f32>), size: Option<f32>, margins: (Option<f32>, Option<f32>), horizontal: bool) -> (f32, f32, f32, f32) {
+        solve_absolute(insets, size, margins, 100.0, 7.0, |available| available.min(30.0), horizontal)
+    }
+
+    #[test]
+    fn each_combination_of_auto_insets_and_size() {
+        let margins = (Some(2.0), Some(3.0));
+        // start 为 auto 时使用静态位置，尺寸为 auto 时收缩适应
+        assert_eq!(solve((None, None), None, margins, true), (7.0, 2.0, 30.0, 3.0));
+        assert_eq!(solve((None, None), Some(40.0), margins, true), (7.0, 2.0, 40.0, 3.0));
+        // 只有 end 时从 end 向前计算 start
+        assert_eq!(solve((None, Some(10.0)), None, margins, true), (55.0, 2.0, 30.0, 3.0));
+        assert_eq!(solve((None, Some(10.0)), Some(40.0), margins, true), (45.0, 2.0, 40.0, 3.0));
+        assert_eq!(solve((Some(10.0), None), None, margins, true), (10.0, 2.0, 30.0, 3.0));
+        assert_eq!(solve((Some(10.0), None), Some(40.0), margins, true), (10.0, 2.0, 40.0, 3.0));
+        // start 和 end 都确定时尺寸填满剩余的空间
+        assert_eq!(solve((Some(10.0), Some(20.0)), None, margins, true), (10.0, 2.0, 65.0, 3.0));
+        assert_eq!(solve((Some(60.0), Some(60.0)), None, margins, true), (60.0, 2.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn auto_margins_take_the_free_space() {
+        let (insets, size) = ((Some(10.0), Some(20.0)), Some(40.0));
+        assert_eq!(solve(insets, size, (None, None), true), (10.0, 15.0, 40.0, 15.0));
+        assert_eq!(solve(insets, size, (None, Some(3.0)), true), (10.0, 27.0, 40.0, 3.0));
+        assert_eq!(solve(insets, size, (Some(2.0), None), true), (10.0, 2.0, 40.0, 28.0));
+        // 有 auto 的位置或尺寸时 auto 的外边距为 0
+        assert_eq!(solve((Some(10.0), None), size, (None, None), true), (10.0, 0.0, 40.0, 0.0));
+    }
+
+    #[test]
+    fn over_constrained() {
+        // 外边距都确定时忽略 end
+        assert_eq!(solve((Some(10.0), Some(20.0)), Some(40.0), (Some(2.0), Some(3.0)), true), (10.0, 2.0, 40.0, 3.0));
+        // 剩余空间为负时，水平方向的 margin-start 为 0，垂直方向仍然平分
+        assert_eq!(solve((Some(10.0), Some(20.0)), Some(100.0), (None, None), true), (10.0, 0.0, 100.0, -30.0));
+        assert_eq!(solve((Some(10.0), Some(20.0)), Some(100.0), (None, None), false), (10.0, -15.0, 100.0, -15.0));
+    }
+}
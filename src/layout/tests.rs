@@ -0,0 +1,930 @@
+// 块格式化上下文的测试：垂直外边距合并的用例来自 CSS 2.1 第 8.3.1 节，浮动和 clear 的用例来自第 9.5 节
+// 弹性布局的用例来自 CSS Flexible Box Layout Module Level 1
+use std::collections::HashMap;
+
+use crate::{css, font::{testing, FontCollection}, html, painting, style};
+use crate::css::types::Color;
+use crate::css::media::MediaEnvironment;
+use crate::html::types::NodeType;
+
+use super::{layout_tree, AnonymousBlock, BlockNode, Dimensions, FragmentKind, LayoutBox, Rect};
+
+// 布局文档，返回每个带有 id 的块盒子的边框盒
+fn layout(source: &str, stylesheet: &str) -> HashMap<String, Rect> {
+    let mut boxes = HashMap::new();
+    with_layout(source, stylesheet, |layout_root| collect(layout_root, &mut boxes));
+    boxes
+}
+
+// 布局文档，按照文档顺序返回全部行盒
+fn lines(source: &str, stylesheet: &str) -> Vec<Rect> {
+    let mut lines = Vec::new();
+    with_layout(source, stylesheet, |layout_root| collect_lines(layout_root, &mut lines));
+    lines
+}
+
+fn with_layout<F>(source: &str, stylesheet: &str, f: F) where F: FnOnce(&LayoutBox) {
+    let root_node = html::parser::parse(source.to_string());
+    let stylesheets = vec![css::parser::parse(format!("html, div {{ display: block; }} {}", stylesheet))];
+    let media = MediaEnvironment::new(800.0, 600.0);
+    let style_root = style::style_tree(&root_node, &stylesheets, &media);
+    let mut fonts = FontCollection::new();
+    fonts.load_font_faces(&stylesheets, &media);
+    let mut viewport: Dimensions = Default::default();
+    viewport.content.width = 800.0;
+    viewport.content.height = 600.0;
+    f(&layout_tree(&style_root, viewport, &fonts));
+}
+
+fn collect(layout_box: &LayoutBox, boxes: &mut HashMap<String, Rect>) {
+    if let BlockNode(node) = layout_box.box_type {
+        if let NodeType::Element(ref data) = node.node.node_type {
+            if let Some(id) = data.id() {
+                boxes.insert(id.clone(), layout_box.dimensions.border_box());
+            }
+        }
+    }
+    for child in &layout_box.children {
+        collect(child, boxes);
+    }
+}
+
+fn collect_lines(layout_box: &LayoutBox, lines: &mut Vec<Rect>) {
+    if let AnonymousBlock = layout_box.box_type {
+        lines.extend(layout_box.lines.iter().map(|line| line.rect));
+    }
+    for child in &layout_box.children {
+        collect_lines(child, lines);
+    }
+}
+
+// 布局文档，按照文档顺序返回每一行中的文字片段
+fn texts(source: &str, stylesheet: &str) -> Vec<Vec<(String, Rect)>> {
+    let mut lines = Vec::new();
+    with_layout(source, stylesheet, |layout_root| collect_texts(layout_root, &mut lines));
+    lines
+}
+
+fn collect_texts(layout_box: &LayoutBox, lines: &mut Vec<Vec<(String, Rect)>>) {
+    for line in &layout_box.lines {
+        lines.push(line.fragments.iter().filter_map(|fragment| match fragment.kind {
+            FragmentKind::Text { ref text, .. } => Some((text.clone(), fragment.rect)),
+            FragmentKind::Box { .. } => None,
+        }).collect());
+    }
+    for child in &layout_box.children {
+        collect_texts(child, lines);
+    }
+}
+
+fn bottom(rect: &Rect) -> f32 {
+    rect.y + rect.height
+}
+
+#[test]
+fn adjacent_siblings() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a { height: 10px; margin-bottom: 20px; } #b { height: 10px; margin-top: 30px; }",
+    );
+    assert_eq!(boxes["b"].y, bottom(&boxes["a"]) + 30.0);
+}
+
+#[test]
+fn positive_and_negative_margins() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a { height: 10px; margin-bottom: 20px; } #b { height: 10px; margin-top: -5px; }",
+    );
+    assert_eq!(boxes["b"].y, bottom(&boxes["a"]) + 15.0);
+}
+
+#[test]
+fn negative_margins() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a { height: 10px; margin-bottom: -10px; } #b { height: 10px; margin-top: -20px; }",
+    );
+    assert_eq!(boxes["b"].y, bottom(&boxes["a"]) - 20.0);
+}
+
+#[test]
+fn parent_and_first_child() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="child"></div></div></html>"#,
+        "#parent { margin-top: 10px; } #child { height: 10px; margin-top: 30px; }",
+    );
+    assert_eq!(boxes["parent"].y, 30.0);
+    assert_eq!(boxes["child"].y, 30.0);
+    assert_eq!(boxes["parent"].height, 10.0);
+}
+
+#[test]
+fn nested_first_children() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="outer"><div id="inner"><div id="child"></div></div></div></html>"#,
+        "#a { height: 10px; margin-bottom: 5px; } #outer { margin-top: 10px; } #inner { margin-top: -15px; } \
+         #child { height: 10px; margin-top: 40px; }",
+    );
+    // 40px 和 -15px 合并为 25px
+    assert_eq!(boxes["outer"].y, 35.0);
+    assert_eq!(boxes["inner"].y, 35.0);
+    assert_eq!(boxes["child"].y, 35.0);
+}
+
+#[test]
+fn padding_separates_parent_and_child() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="child"></div></div></html>"#,
+        "#parent { margin-top: 10px; padding-top: 1px; } #child { height: 10px; margin-top: 30px; }",
+    );
+    assert_eq!(boxes["parent"].y, 10.0);
+    assert_eq!(boxes["child"].y, 41.0);
+}
+
+#[test]
+fn border_separates_parent_and_child() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="child"></div></div><div id="next"></div></html>"#,
+        "#parent { border-width: 1px; border-style: solid; } #child { height: 10px; margin: 30px 0; } #next { height: 10px; }",
+    );
+    assert_eq!(boxes["child"].y, 31.0);
+    assert_eq!(boxes["parent"].height, 72.0);
+    assert_eq!(boxes["next"].y, 72.0);
+}
+
+#[test]
+fn parent_and_last_child() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="child"></div></div><div id="next"></div></html>"#,
+        "#parent { margin-bottom: 10px; } #child { height: 10px; margin-bottom: 30px; } #next { height: 10px; }",
+    );
+    assert_eq!(boxes["parent"].height, 10.0);
+    assert_eq!(boxes["next"].y, 40.0);
+}
+
+#[test]
+fn explicit_height_separates_last_child() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="child"></div></div><div id="next"></div></html>"#,
+        "#parent { height: 50px; margin-bottom: 10px; } #child { height: 10px; margin-bottom: 30px; } #next { height: 10px; }",
+    );
+    assert_eq!(boxes["parent"].height, 50.0);
+    assert_eq!(boxes["next"].y, 60.0);
+}
+
+#[test]
+fn empty_block_collapses_through() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="empty"></div><div id="b"></div></html>"#,
+        "#a { height: 10px; margin-bottom: 10px; } #empty { margin: 20px 0 15px; } #b { height: 10px; margin-top: 5px; }",
+    );
+    assert_eq!(boxes["empty"].height, 0.0);
+    assert_eq!(boxes["b"].y, 30.0);
+}
+
+#[test]
+fn empty_block_with_border_does_not_collapse_through() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="empty"></div><div id="b"></div></html>"#,
+        "#a { height: 10px; margin-bottom: 10px; } #empty { margin: 20px 0 15px; border-top: 1px solid; } \
+         #b { height: 10px; margin-top: 5px; }",
+    );
+    assert_eq!(boxes["empty"].y, 30.0);
+    assert_eq!(boxes["b"].y, 46.0);
+}
+
+#[test]
+fn empty_children_collapse_with_parent() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="empty"></div></div><div id="next"></div></html>"#,
+        "#parent { margin: 10px 0; } #empty { margin: 20px 0 -5px; } #next { height: 10px; margin-top: 25px; }",
+    );
+    // 父元素和子元素的上下外边距以及后面的兄弟元素的上外边距全部合并在一起
+    assert_eq!(boxes["parent"].height, 0.0);
+    assert_eq!(boxes["next"].y, 20.0);
+}
+
+#[test]
+fn empty_children_collapse_with_parent_top() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="empty"></div></div><div id="next"></div></html>"#,
+        "#parent { height: 50px; margin-top: 10px; } #empty { margin: 20px 0 30px; } #next { height: 10px; }",
+    );
+    // 父元素的高度不为 0，子元素的外边距都和父元素的上外边距合并
+    assert_eq!(boxes["parent"].y, 30.0);
+    assert_eq!(boxes["next"].y, 80.0);
+}
+
+#[test]
+fn text_separates_parent_and_child() {
+    let boxes = layout(
+        r#"<html><div id="parent">text<div id="child"></div></div></html>"#,
+        "#parent { margin-top: 10px; } #child { height: 10px; margin-top: 30px; }",
+    );
+    assert_eq!(boxes["parent"].y, 10.0);
+    assert!(boxes["child"].y > 40.0);
+}
+
+#[test]
+fn root_margins_do_not_collapse() {
+    let boxes = layout(
+        r#"<html id="root"><div id="child"></div></html>"#,
+        "html { margin-top: 10px; } #child { height: 10px; margin: 20px 0; }",
+    );
+    assert_eq!(boxes["root"].y, 10.0);
+    assert_eq!(boxes["child"].y, 30.0);
+    assert_eq!(boxes["root"].height, 50.0);
+}
+
+#[test]
+fn stacked_left_floats() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a, #b { float: left; } #a { width: 100px; height: 50px; } #b { width: 150px; height: 30px; margin-left: 10px; }",
+    );
+    assert_eq!((boxes["a"].x, boxes["a"].y), (0.0, 0.0));
+    assert_eq!((boxes["b"].x, boxes["b"].y), (110.0, 0.0));
+}
+
+#[test]
+fn stacked_right_floats() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a, #b { float: right; width: 100px; height: 50px; }",
+    );
+    assert_eq!(boxes["a"].x, 700.0);
+    assert_eq!(boxes["b"].x, 600.0);
+}
+
+#[test]
+fn float_moves_down_until_it_fits() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div><div id="c"></div></html>"#,
+        "#a, #b, #c { float: left; width: 300px; } #a { height: 50px; } #b { height: 30px; } #c { height: 10px; }",
+    );
+    // 放不下时移动到较短的浮动盒子下面，仍然在较高的浮动盒子旁边
+    assert_eq!((boxes["c"].x, boxes["c"].y), (300.0, 30.0));
+}
+
+#[test]
+fn float_is_not_higher_than_earlier_floats() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div><div id="c"></div><div id="d"></div></html>"#,
+        "#a, #b, #c, #d { float: left; width: 300px; height: 10px; } #a { height: 50px; } #d { width: 50px; }",
+    );
+    // d 在第一行放得下，但是不能高于前面的 c
+    assert_eq!((boxes["c"].x, boxes["c"].y), (300.0, 10.0));
+    assert_eq!((boxes["d"].x, boxes["d"].y), (600.0, 10.0));
+}
+
+#[test]
+fn floats_do_not_move_following_blocks() {
+    let boxes = layout(
+        r#"<html><div id="float"></div><div id="next"></div></html>"#,
+        "#float { float: left; width: 100px; height: 50px; } #next { height: 10px; }",
+    );
+    assert_eq!(boxes["next"].y, 0.0);
+    assert_eq!(boxes["next"].width, 800.0);
+}
+
+#[test]
+fn shrink_to_fit_float_width() {
+    let boxes = layout(
+        r#"<html><div id="float"><div id="child"></div></div></html>"#,
+        "#float { float: right; padding: 0 5px; } #child { width: 120px; height: 10px; }",
+    );
+    assert_eq!(boxes["float"].width, 130.0);
+    assert_eq!(boxes["float"].x, 670.0);
+}
+
+#[test]
+fn clearance_moves_block_below_floats() {
+    let boxes = layout(
+        r#"<html><div id="float"></div><div id="cleared"></div></html>"#,
+        "#float { float: left; width: 100px; height: 50px; } #cleared { clear: both; height: 10px; margin-top: 20px; }",
+    );
+    assert_eq!(boxes["cleared"].y, 50.0);
+}
+
+#[test]
+fn no_clearance_when_margin_is_below_floats() {
+    let boxes = layout(
+        r#"<html><div id="float"></div><div id="cleared"></div></html>"#,
+        "#float { float: left; width: 100px; height: 50px; } #cleared { clear: left; height: 10px; margin-top: 70px; }",
+    );
+    assert_eq!(boxes["cleared"].y, 70.0);
+}
+
+#[test]
+fn clear_only_affects_one_side() {
+    let boxes = layout(
+        r#"<html><div id="left"></div><div id="right"></div><div id="a"></div><div id="b"></div></html>"#,
+        "#left { float: left; width: 100px; height: 50px; } #right { float: right; width: 100px; height: 80px; } \
+         #a { clear: left; height: 10px; } #b { clear: right; height: 10px; }",
+    );
+    assert_eq!(boxes["a"].y, 50.0);
+    assert_eq!(boxes["b"].y, 80.0);
+}
+
+#[test]
+fn clearance_separates_margins() {
+    let boxes = layout(
+        r#"<html><div id="float"></div><div id="cleared"></div><div id="next"></div></html>"#,
+        "#float { float: left; width: 100px; height: 50px; } #cleared { clear: both; height: 10px; margin-bottom: 20px; } \
+         #next { height: 10px; margin-top: 10px; }",
+    );
+    assert_eq!(boxes["cleared"].y, 50.0);
+    assert_eq!(boxes["next"].y, 80.0);
+}
+
+#[test]
+fn empty_clearing_block_extends_parent() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="float"></div><div id="clearfix"></div></div><div id="next"></div></html>"#,
+        "#float { float: left; width: 100px; height: 50px; } #clearfix { clear: both; } #next { height: 10px; }",
+    );
+    assert_eq!(boxes["parent"].height, 50.0);
+    assert_eq!(boxes["next"].y, 50.0);
+}
+
+#[test]
+fn cleared_float_moves_below_earlier_floats() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a, #b { float: left; width: 100px; height: 50px; } #b { clear: left; }",
+    );
+    assert_eq!((boxes["b"].x, boxes["b"].y), (0.0, 50.0));
+}
+
+#[test]
+fn parent_does_not_contain_floats() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="float"></div></div></html>"#,
+        "#float { float: left; width: 100px; height: 50px; }",
+    );
+    assert_eq!(boxes["parent"].height, 0.0);
+}
+
+#[test]
+fn formatting_contexts_contain_floats() {
+    let boxes = layout(
+        r#"<html><div id="overflow"><div class="float"></div></div><div id="flow-root"><div class="float"></div></div></html>"#,
+        ".float { float: left; width: 100px; height: 50px; } #overflow { overflow: hidden; } #flow-root { display: flow-root; }",
+    );
+    assert_eq!(boxes["overflow"].height, 50.0);
+    assert_eq!(boxes["flow-root"].y, 50.0);
+    assert_eq!(boxes["flow-root"].height, 50.0);
+}
+
+#[test]
+fn formatting_context_does_not_overlap_floats() {
+    let boxes = layout(
+        r#"<html><div id="float"></div><div id="main"></div></html>"#,
+        "#float { float: left; width: 200px; height: 50px; } #main { overflow: hidden; height: 10px; }",
+    );
+    assert_eq!(boxes["main"].x, 200.0);
+    assert_eq!(boxes["main"].width, 600.0);
+}
+
+#[test]
+fn formatting_context_margins_do_not_collapse_with_children() {
+    let boxes = layout(
+        r#"<html><div id="parent"><div id="child"></div></div></html>"#,
+        "#parent { display: flow-root; margin-top: 10px; } #child { height: 10px; margin-top: 30px; }",
+    );
+    assert_eq!(boxes["parent"].y, 10.0);
+    assert_eq!(boxes["child"].y, 40.0);
+}
+
+#[test]
+fn line_boxes_are_shortened_around_floats() {
+    let text = "line\n".repeat(10);
+    let lines = lines(
+        &format!(r#"<html><div><div id="left"></div><div id="right"></div>{}</div></html>"#, text),
+        "#left { float: left; width: 100px; height: 40px; } #right { float: right; width: 50px; height: 20px; } \
+         div { white-space: pre; line-height: 10px; }",
+    );
+    assert_eq!((lines[0].x, lines[0].width), (100.0, 650.0));
+    assert_eq!((lines[2].x, lines[2].width), (100.0, 700.0));
+    assert_eq!((lines[4].x, lines[4].width), (0.0, 800.0));
+}
+
+#[test]
+fn inline_float_starts_on_current_line() {
+    let boxes = layout(
+        r#"<html><div id="parent">text<span id="float"></span> more text</div></html>"#,
+        "#float { float: right; width: 100px; height: 20px; }",
+    );
+    assert_eq!((boxes["float"].x, boxes["float"].y), (700.0, 0.0));
+    let lines = lines(
+        r#"<html><div id="parent">text<span id="float"></span> more text</div></html>"#,
+        "#float { float: right; width: 100px; height: 20px; }",
+    );
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].width, 700.0);
+}
+
+#[test]
+fn words_move_below_floats_when_they_do_not_fit() {
+    let lines = lines(
+        r#"<html><div id="parent"><div id="float"></div>text</div></html>"#,
+        "#parent { width: 100px; line-height: 10px; } #float { float: left; width: 90px; height: 30px; }",
+    );
+    assert_eq!((lines[0].x, lines[0].y), (0.0, 30.0));
+}
+
+#[test]
+fn flex_grow_distributes_free_space() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div></div></html>"#,
+        "#flex { display: flex; width: 600px; } #a { flex: 1 1 100px; } #b { flex: 3 1 100px; }",
+    );
+    assert_eq!((boxes["a"].x, boxes["a"].width), (0.0, 200.0));
+    assert_eq!((boxes["b"].x, boxes["b"].width), (200.0, 400.0));
+}
+
+#[test]
+fn flex_shrink_is_weighted_by_base_size() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div></div></html>"#,
+        "#flex { display: flex; width: 300px; } #a { width: 300px; } #b { width: 100px; }",
+    );
+    assert_eq!(boxes["a"].width, 225.0);
+    assert_eq!(boxes["b"].width, 75.0);
+}
+
+#[test]
+fn automatic_minimum_size_prevents_shrinking_below_content() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a"><div id="content"></div></div><div id="b" class="item"></div></div></html>"#,
+        "#flex { display: flex; width: 300px; } #content { width: 250px; height: 10px; } #b { width: 200px; }",
+    );
+    assert_eq!(boxes["a"].width, 250.0);
+    assert_eq!((boxes["b"].x, boxes["b"].width), (250.0, 50.0));
+}
+
+#[test]
+fn flex_items_wrap_into_lines_separated_by_gaps() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div><div id="c" class="item"></div><div id="d" class="item"></div></div></html>"#,
+        "#flex { display: flex; flex-wrap: wrap; gap: 10px 20px; width: 300px; } .item { width: 100px; height: 20px; }",
+    );
+    assert_eq!((boxes["b"].x, boxes["b"].y), (120.0, 0.0));
+    assert_eq!((boxes["c"].x, boxes["c"].y), (0.0, 30.0));
+    assert_eq!((boxes["d"].x, boxes["d"].y), (120.0, 30.0));
+    assert_eq!(boxes["flex"].height, 50.0);
+}
+
+#[test]
+fn align_content_stretches_lines() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div></div></html>"#,
+        "#flex { display: flex; flex-wrap: wrap; width: 100px; height: 200px; } .item { width: 100px; }",
+    );
+    assert_eq!((boxes["a"].y, boxes["a"].height), (0.0, 100.0));
+    assert_eq!((boxes["b"].y, boxes["b"].height), (100.0, 100.0));
+}
+
+#[test]
+fn justify_content_distributes_items() {
+    let source = r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div><div id="c" class="item"></div></div></html>"#;
+    let items = "#flex { display: flex; width: 400px; } .item { width: 100px; }";
+    let boxes = layout(source, &format!("{} #flex {{ justify-content: space-evenly; }}", items));
+    assert_eq!((boxes["a"].x, boxes["b"].x, boxes["c"].x), (25.0, 150.0, 275.0));
+    let boxes = layout(source, &format!("{} #flex {{ justify-content: space-between; }}", items));
+    assert_eq!((boxes["a"].x, boxes["b"].x, boxes["c"].x), (0.0, 150.0, 300.0));
+    let boxes = layout(source, &format!("{} #flex {{ justify-content: flex-end; }}", items));
+    assert_eq!(boxes["a"].x, 100.0);
+}
+
+#[test]
+fn align_items_and_align_self() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div><div id="c" class="item"></div></div></html>"#,
+        "#flex { display: flex; height: 100px; } .item { width: 100px; } \
+         #b { height: 20px; align-self: center; } #c { height: 20px; align-self: flex-end; }",
+    );
+    assert_eq!((boxes["a"].y, boxes["a"].height), (0.0, 100.0));
+    assert_eq!(boxes["b"].y, 40.0);
+    assert_eq!(boxes["c"].y, 80.0);
+}
+
+#[test]
+fn auto_margins_absorb_free_space() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div></div></html>"#,
+        "#flex { display: flex; width: 400px; height: 100px; } .item { width: 100px; height: 20px; } \
+         #b { margin: auto 0 auto auto; }",
+    );
+    assert_eq!(boxes["a"].x, 0.0);
+    assert_eq!((boxes["b"].x, boxes["b"].y), (300.0, 40.0));
+}
+
+#[test]
+fn order_and_reversed_direction() {
+    let source = r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div></div></html>"#;
+    let items = "#flex { display: flex; width: 400px; } .item { width: 100px; }";
+    let boxes = layout(source, &format!("{} #b {{ order: -1; }}", items));
+    assert_eq!((boxes["a"].x, boxes["b"].x), (100.0, 0.0));
+    let boxes = layout(source, &format!("{} #flex {{ flex-direction: row-reverse; }}", items));
+    assert_eq!((boxes["a"].x, boxes["b"].x), (300.0, 200.0));
+}
+
+#[test]
+fn column_direction_grows_heights() {
+    let boxes = layout(
+        r#"<html><div id="flex"><div id="a" class="item"></div><div id="b" class="item"></div></div></html>"#,
+        "#flex { display: flex; flex-direction: column; width: 200px; height: 200px; } #a { height: 20px; } #b { flex-grow: 1; }",
+    );
+    assert_eq!((boxes["a"].width, boxes["a"].height), (200.0, 20.0));
+    assert_eq!((boxes["b"].y, boxes["b"].height), (20.0, 180.0));
+}
+
+#[test]
+fn inline_flex_containers_sit_on_the_line() {
+    let boxes = layout(
+        r#"<html><div id="parent"><span id="a"></span><span id="b"></span></div></html>"#,
+        "span { display: inline-flex; width: 50px; height: 30px; }",
+    );
+    assert_eq!((boxes["a"].x, boxes["a"].y), (0.0, 0.0));
+    assert_eq!((boxes["b"].x, boxes["b"].y), (50.0, 0.0));
+    assert!(boxes["parent"].height >= 30.0);
+}
+
+#[test]
+fn sticky_boxes_stick_to_the_nearest_scroll_container() {
+    let source = r#"<html><div id="scroller"><div><div id="spacer"></div><div id="inner"></div></div></div><div><div id="spacer2"></div><div id="outer"></div></div></html>"#;
+    let boxes = layout(
+        source,
+        "#scroller { overflow: hidden; height: 100px; padding-top: 10px; } #spacer { height: 200px; } #spacer2 { height: 50px; } \
+         #inner, #outer { position: sticky; bottom: 0px; height: 20px; }",
+    );
+    // 滚动容器的内边距盒底边在 110px，粘性盒子从 210px 移动到 90px
+    assert_eq!(boxes["inner"].y, 90.0);
+    // 滚动容器外面的粘性盒子仍然相对于视口，没有超出视口不需要移动
+    assert_eq!(boxes["outer"].y, 160.0);
+}
+
+// 每个字符宽 0.6em，字号 10px 时一个字符 6px，60px 宽的行最多放 10 个字符
+fn line_texts(source: &str, white_space: &str) -> Vec<String> {
+    let stylesheet = format!("div {{ width: 60px; font-size: 10px; white-space: {}; }}", white_space);
+    texts(source, &stylesheet).into_iter()
+        .map(|line| line.into_iter().map(|(text, _)| text).collect())
+        .collect()
+}
+
+#[test]
+fn normal_white_space_collapses_and_wraps() {
+    let source = "<html><div>aa  bb\n  cc dd   ee ff gg hh</div></html>";
+    assert_eq!(line_texts(source, "normal"), ["aa bb cc", "dd ee ff", "gg hh"]);
+}
+
+#[test]
+fn words_fit_exactly_after_spaces() {
+    // 行尾的空白后面放进新的单词时要算进宽度
+    let source = "<html><div>aaaa bbbb cccc</div></html>";
+    let lines = texts(source, "div { width: 60px; font-size: 10px; }");
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0][0].0, "aaaa bbbb");
+    assert_eq!(lines[0][0].1.width, 54.0);
+    assert_eq!(line_texts("<html><div>aa bb cc dd</div></html>", "normal"), ["aa bb cc", "dd"]);
+}
+
+#[test]
+fn nowrap_collapses_without_wrapping() {
+    let source = "<html><div>aa  bb\n  cc dd   ee ff gg hh</div></html>";
+    assert_eq!(line_texts(source, "nowrap"), ["aa bb cc dd ee ff gg hh"]);
+}
+
+#[test]
+fn pre_keeps_spaces_and_breaks_only_at_newlines() {
+    let source = "<html><div>aa  bb\n  cc dd   ee ff gg hh</div></html>";
+    assert_eq!(line_texts(source, "pre"), ["aa  bb", "  cc dd   ee ff gg hh"]);
+}
+
+#[test]
+fn pre_wrap_keeps_spaces_and_wraps() {
+    // 保留的空白在行尾悬挂，不会把单词挤到下一行
+    let source = "<html><div>aa  bb\n  cc dd   ee ff gg hh</div></html>";
+    assert_eq!(line_texts(source, "pre-wrap"), ["aa  bb", "  cc dd   ", "ee ff gg ", "hh"]);
+}
+
+#[test]
+fn collapsible_spaces_are_trimmed_at_line_edges() {
+    // 跨元素的连续空白合并成一个，行首和行尾的空白被去掉
+    let lines = texts(
+        "<html><div>aa <span> bb </span>  cc dddd </div></html>",
+        "div { width: 60px; font-size: 10px; }",
+    );
+    let texts: Vec<Vec<&str>> = lines.iter().map(|line| line.iter().map(|(text, _)| text.as_str()).collect()).collect();
+    assert_eq!(texts, [vec!["aa ", "bb ", "cc"], vec!["dddd"]]);
+    assert_eq!((lines[1][0].1.x, lines[1][0].1.width), (0.0, 24.0));
+}
+
+// 绘制文档左上角 100x50 的区域，返回所有不是白色的像素
+fn ink(source: &str, stylesheet: &str) -> Vec<(usize, usize, Color)> {
+    let mut ink = Vec::new();
+    with_layout(source, stylesheet, |layout_root| {
+        let canvas = painting::paint(layout_root, Rect { x: 0.0, y: 0.0, width: 100.0, height: 50.0 });
+        for (i, &pixel) in canvas.pixels.iter().enumerate() {
+            if (pixel.r, pixel.g, pixel.b) != (255, 255, 255) {
+                ink.push((i % canvas.width, i / canvas.width, pixel));
+            }
+        }
+    });
+    ink
+}
+
+const RED: Color = Color { r: 255, g: 0, b: 0, a: 255 };
+
+#[test]
+fn text_is_painted_in_its_color_above_the_baseline() {
+    let ink = ink("<html><div>H</div></html>", "div { color: #ff0000; font-size: 10px; }");
+    let lines = texts("<html><div>H</div></html>", "div { color: #ff0000; font-size: 10px; }");
+    let rect = lines[0][0].1;
+    // 位图字体的格子正好对齐像素，覆盖率都是 1
+    assert!(!ink.is_empty());
+    assert!(ink.iter().all(|&(_, _, color)| color == RED));
+    // 基线在片段顶部以下 ascent（8px）的位置
+    let baseline = rect.y + 8.0;
+    assert!(ink.iter().all(|&(x, y, _)| (x as f32) < rect.x + rect.width && (y as f32) >= rect.y && (y as f32) < baseline));
+    assert_eq!(ink.iter().map(|&(_, y, _)| y).max(), Some(baseline as usize - 1));
+}
+
+#[test]
+fn font_size_scales_glyphs() {
+    let small = ink("<html><div>H</div></html>", "div { color: #ff0000; font-size: 10px; }");
+    let large = ink("<html><div>H</div></html>", "div { color: #ff0000; font-size: 20px; }");
+    assert_eq!(large.len(), small.len() * 4);
+    assert_eq!(texts("<html><div>H</div></html>", "div { font-size: 20px; }")[0][0].1.width, 12.0);
+}
+
+#[test]
+fn bold_and_italic_are_synthesized() {
+    let normal = ink("<html><div>H</div></html>", "div { color: #ff0000; font-size: 10px; }");
+    let right = |ink: &[(usize, usize, Color)]| ink.iter().map(|&(x, _, _)| x).max();
+    // 粗体把字形向右复制一份，覆盖更多像素
+    let bold = ink("<html><div>H</div></html>", "div { color: #ff0000; font-size: 10px; font-weight: bold; }");
+    assert!(bold.len() > normal.len());
+    assert!(right(&bold) > right(&normal));
+    // 斜体把字形倾斜，顶部向右偏移，边缘只部分覆盖像素
+    let italic = ink("<html><div>H</div></html>", "div { color: #ff0000; font-size: 10px; font-style: italic; }");
+    assert!(right(&italic) > right(&normal));
+    assert!(italic.iter().any(|&(_, _, color)| color != RED));
+}
+
+#[test]
+fn text_color_alpha_is_blended() {
+    let ink = ink("<html><div>H</div></html>", "div { color: #ff000080; font-size: 10px; }");
+    assert!(!ink.is_empty());
+    assert!(ink.iter().all(|&(_, _, color)| color == Color { r: 255, g: 127, b: 127, a: 255 }));
+}
+
+// 只包含小写字母的字体，数字等其他字符使用嵌入的字体
+fn write_font(name: &str, advance: u16) -> String {
+    testing::write_font(name, &[('a', 'z')], advance)
+}
+
+// 按照文档顺序返回每个文字片段的宽度
+fn text_widths(source: &str, stylesheet: &str) -> Vec<f32> {
+    texts(source, stylesheet).into_iter().flatten().map(|(_, rect)| rect.width).collect()
+}
+
+#[test]
+fn font_face_is_loaded_from_a_file() {
+    let wide = write_font("loaded", 1000);
+    let stylesheet = format!("@font-face {{ font-family: Wide; src: url({:?}) format(\"truetype\"); }} \
+                              div {{ font-family: Wide; font-size: 10px; }}", wide);
+    // 每个字母 1em 宽，字体中没有的数字使用嵌入的字体，0.6em 宽
+    assert_eq!(text_widths("<html><div>abc</div></html>", &stylesheet), [30.0]);
+    assert_eq!(text_widths("<html><div>ab12</div></html>", &stylesheet), [32.0]);
+    // 第一个字体的 ascent 和 descent 决定行高
+    assert_eq!(lines("<html><div>abc</div></html>", &stylesheet)[0].height, 10.0);
+}
+
+#[test]
+fn font_faces_are_matched_by_weight_and_style() {
+    let (regular, bold, italic) = (write_font("regular", 500), write_font("bold", 1000), write_font("italic", 800));
+    let faces = format!(
+        "@font-face {{ font-family: Family; src: url({:?}); }} \
+         @font-face {{ font-family: Family; src: url({:?}); font-weight: bold; }} \
+         @font-face {{ font-family: Family; src: url({:?}); font-style: italic; }} \
+         div {{ font-family: Family; font-size: 10px; }}",
+        regular, bold, italic,
+    );
+    let width = |style: &str| text_widths("<html><div>a</div></html>", &format!("{} div {{ {} }}", faces, style))[0];
+    assert_eq!(width("font-weight: normal"), 5.0);
+    assert_eq!(width("font-weight: bold"), 10.0);
+    // 600 先找更重的，300 先找更轻的，没有时再找更重的
+    assert_eq!(width("font-weight: 600"), 10.0);
+    assert_eq!(width("font-weight: 300"), 5.0);
+    assert_eq!(width("font-style: italic"), 8.0);
+    // 先按照样式筛选再选择字重，没有粗斜体时使用斜体
+    assert_eq!(width("font-style: italic; font-weight: bold"), 8.0);
+}
+
+#[test]
+fn font_family_names_ignore_case() {
+    let wide = write_font("case", 1000);
+    let stylesheet = format!("@font-face {{ font-family: \"Wide Font\"; src: url({:?}); }} \
+                              div {{ font-family: \"WIDE FONT\"; font-size: 10px; }}", wide);
+    assert_eq!(text_widths("<html><div>a</div></html>", &stylesheet), [10.0]);
+}
+
+#[test]
+fn local_reuses_a_loaded_font() {
+    let wide = write_font("local", 1000);
+    let stylesheet = format!("@font-face {{ font-family: Wide; src: url({:?}); }} \
+                              @font-face {{ font-family: Alias; src: local(Wide); }} \
+                              div {{ font-family: Alias; font-size: 10px; }}", wide);
+    assert_eq!(text_widths("<html><div>a</div></html>", &stylesheet), [10.0]);
+}
+
+#[test]
+fn unusable_font_sources_are_skipped() {
+    let wide = write_font("fallback", 1000);
+    // 跳过不存在的文件和不支持的格式，使用第一个可以加载的来源
+    let stylesheet = format!("@font-face {{ font-family: Wide; src: url(\"missing.ttf\"), url({:?}) format(\"woff\"), url({:?}); }} \
+                              div {{ font-family: Wide; font-size: 10px; }}", wide, wide);
+    assert_eq!(text_widths("<html><div>a</div></html>", &stylesheet), [10.0]);
+    // 所有来源都无法加载时使用嵌入的字体
+    let stylesheet = "@font-face { font-family: Wide; src: url(\"missing.ttf\"); } div { font-family: Wide, serif; font-size: 10px; }";
+    assert_eq!(text_widths("<html><div>a</div></html>", stylesheet), [6.0]);
+}
+
+#[test]
+fn max_width_and_min_width_clamp_the_width() {
+    let boxes = layout(r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a { width: 500px; max-width: 300px; } #b { width: 100px; min-width: 200px; max-width: 150px; }");
+    assert_eq!(boxes["a"].width, 300.0);
+    // min-width 比 max-width 优先
+    assert_eq!(boxes["b"].width, 200.0);
+}
+
+#[test]
+fn auto_margins_are_resolved_after_clamping() {
+    let boxes = layout(r#"<html><div id="a"></div><div id="b"></div></html>"#,
+        "#a { max-width: 200px; margin: 0 auto; } #b { width: 300px; max-width: 200px; margin-left: auto; }");
+    assert_eq!((boxes["a"].x, boxes["a"].width), (300.0, 200.0));
+    assert_eq!((boxes["b"].x, boxes["b"].width), (600.0, 200.0));
+}
+
+#[test]
+fn min_height_and_max_height_clamp_the_height() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div><div id="c"><div id="d"></div></div><div id="e"></div></html>"#,
+        "#a { min-height: 50px; } #b { height: 100px; max-height: 40px; min-height: 60px; } \
+         #c { max-height: 30px; } #d { height: 100px; }",
+    );
+    assert_eq!(boxes["a"].height, 50.0);
+    assert_eq!(boxes["b"].height, 60.0);
+    // 内容的高度也被限制，溢出的内容不影响后面的盒子
+    assert_eq!(boxes["c"].height, 30.0);
+    assert_eq!(boxes["d"].height, 100.0);
+    assert_eq!(boxes["e"].y, bottom(&boxes["c"]));
+}
+
+#[test]
+fn border_box_sizing() {
+    let boxes = layout(
+        r#"<html><div id="a"><div id="b"></div></div><div id="c"></div><div id="d"></div></html>"#,
+        "#a { width: 200px; height: 100px; padding: 10px; border: 5px solid; box-sizing: border-box; } \
+         #c { width: 20px; padding: 10px; border: 5px solid; box-sizing: border-box; } \
+         #d { width: 100px; min-width: 200px; padding: 10px; box-sizing: border-box; }",
+    );
+    assert_eq!((boxes["a"].width, boxes["a"].height), (200.0, 100.0));
+    assert_eq!(boxes["b"].width, 170.0);
+    // 内容区域的宽度不能小于 0
+    assert_eq!(boxes["c"].width, 30.0);
+    // min-width 同样指边框盒的宽度
+    assert_eq!(boxes["d"].width, 200.0);
+}
+
+#[test]
+fn percentages_resolve_against_the_containing_block() {
+    let boxes = layout(
+        r#"<html><div id="p"><div id="c"></div></div></html>"#,
+        "#p { width: 400px; height: 200px; border: 1px solid; } \
+         #c { width: 50%; height: 25%; margin-left: 10%; margin-top: 5%; padding-top: 5%; padding-right: 10%; }",
+    );
+    // 宽度、外边距和内边距都相对于包含块的宽度，高度相对于包含块的高度
+    assert_eq!(boxes["c"].x, 1.0 + 40.0);
+    assert_eq!(boxes["c"].y, 1.0 + 20.0);
+    assert_eq!(boxes["c"].width, 200.0 + 40.0);
+    assert_eq!(boxes["c"].height, 50.0 + 20.0);
+}
+
+#[test]
+fn percentage_min_and_max_sizes() {
+    let boxes = layout(
+        r#"<html><div id="p"><div id="a"></div><div id="b"></div></div></html>"#,
+        "#p { width: 400px; height: 200px; } #a { max-width: 50%; min-height: 10%; } #b { width: 10%; min-width: 25%; max-height: 5%; height: 100px; }",
+    );
+    assert_eq!((boxes["a"].width, boxes["a"].height), (200.0, 20.0));
+    assert_eq!((boxes["b"].width, boxes["b"].height), (100.0, 10.0));
+}
+
+#[test]
+fn percentage_heights_need_a_definite_containing_block_height() {
+    let boxes = layout(
+        r#"<html><div id="p"><div id="c"><div id="d"></div></div></div><div id="q"><div id="e"><div id="f"></div></div></div></html>"#,
+        "#p { width: 400px; } #c { height: 50%; min-height: 10%; } #d { height: 30px; } \
+         #q { height: 200px; } #e { height: 50%; } #f { height: 50%; }",
+    );
+    // 包含块的高度为 auto 时百分比高度视为 auto，百分比的最小高度视为 0
+    assert_eq!(boxes["c"].height, 30.0);
+    assert_eq!(boxes["p"].height, 30.0);
+    // 包含块的百分比高度可以确定时，嵌套的百分比高度逐层计算
+    assert_eq!(boxes["e"].height, 100.0);
+    assert_eq!(boxes["f"].height, 50.0);
+}
+
+#[test]
+fn relative_offsets_do_not_move_other_boxes() {
+    let boxes = layout(
+        r#"<html><div id="a"><div id="c"></div></div><div id="b"></div></html>"#,
+        "#a { height: 10px; position: relative; top: 5px; left: 10px; } #b { height: 10px; }",
+    );
+    // 相对定位的盒子和它的内容一起偏移，后面的盒子仍然在原来的位置
+    assert_eq!((boxes["a"].x, boxes["a"].y), (10.0, 5.0));
+    assert_eq!((boxes["c"].x, boxes["c"].y), (10.0, 5.0));
+    assert_eq!(boxes["b"].y, 10.0);
+}
+
+#[test]
+fn relative_offsets_prefer_top_and_left() {
+    let boxes = layout(
+        r#"<html><div id="a"></div><div id="b"></div><div id="p"><div id="c"></div></div></html>"#,
+        "div { height: 10px; } #a { position: relative; bottom: 5px; right: 10px; } \
+         #b { position: relative; bottom: 5px; right: 10px; top: 1px; left: 2px; } \
+         #p { width: 200px; height: 100px; } #c { position: relative; top: 10%; left: 10%; }",
+    );
+    assert_eq!((boxes["a"].x, boxes["a"].y), (-10.0, -5.0));
+    assert_eq!((boxes["b"].x, boxes["b"].y), (2.0, 11.0));
+    // 百分比相对于包含块的宽度和高度
+    assert_eq!((boxes["c"].x, boxes["c"].y), (20.0, 30.0));
+}
+
+#[test]
+fn absolute_boxes_are_placed_in_the_padding_box_of_the_positioned_ancestor() {
+    let boxes = layout(
+        r#"<html><div id="p"><div id="a"></div><div id="b"></div></div><div id="q"><div id="c"></div></div></html>"#,
+        "#p { position: relative; margin: 20px; width: 400px; height: 300px; padding: 10px; border: 2px solid; } \
+         #a { position: absolute; top: 10px; left: 20px; width: 100px; height: 50px; } \
+         #b { position: absolute; right: 10px; bottom: 20px; width: 10%; height: 10%; } \
+         #q { margin: 30px; height: 10px; } #c { position: absolute; left: 0px; top: 0px; width: 10px; height: 10px; }",
+    );
+    // 内边距盒从 (22, 22) 开始，大小为 420 x 320
+    assert_eq!((boxes["a"].x, boxes["a"].y, boxes["a"].width, boxes["a"].height), (42.0, 32.0, 100.0, 50.0));
+    assert_eq!((boxes["b"].x, boxes["b"].y, boxes["b"].width, boxes["b"].height), (22.0 + 420.0 - 10.0 - 42.0, 22.0 + 320.0 - 20.0 - 32.0, 42.0, 32.0));
+    // 没有定位的祖先时相对于初始包含块
+    assert_eq!((boxes["c"].x, boxes["c"].y), (0.0, 0.0));
+}
+
+#[test]
+fn absolute_boxes_are_out_of_flow() {
+    let boxes = layout(
+        r#"<html><div id="p"><div id="s"></div><div id="a"></div><div id="b"></div></div></html>"#,
+        "#p { position: relative; width: 400px; } #s { height: 40px; } #a { position: absolute; width: 10px; height: 10px; } #b { height: 5px; }",
+    );
+    // 所有位置都是 auto 时使用静态位置，不占用普通流中的空间
+    assert_eq!((boxes["a"].x, boxes["a"].y), (0.0, 40.0));
+    assert_eq!(boxes["b"].y, 40.0);
+    assert_eq!(boxes["p"].height, 45.0);
+}
+
+#[test]
+fn absolute_auto_margins_and_over_constrained_insets() {
+    let source = r#"<html><div id="p"><div id="a"></div></div></html>"#;
+    let abs = |style: &str| layout(source, &format!("#p {{ position: relative; width: 400px; height: 300px; }} #a {{ position: absolute; {} }}", style))["a"];
+    // 四个位置都确定时，auto 的外边距使盒子居中
+    let a = abs("left: 0px; right: 0px; top: 0px; bottom: 0px; width: 100px; height: 50px; margin: auto;");
+    assert_eq!((a.x, a.y), (150.0, 125.0));
+    // 宽度为 auto 时填满剩余的空间
+    let a = abs("left: 10px; right: 20px; top: 5px; bottom: 15px;");
+    assert_eq!((a.x, a.y, a.width, a.height), (10.0, 5.0, 370.0, 280.0));
+    // 过度约束时忽略 right
+    let a = abs("left: 10px; right: 20px; width: 100px; margin-left: 5px; margin-right: 5px;");
+    assert_eq!((a.x, a.width), (15.0, 100.0));
+    // 剩余空间为负时水平方向 auto 的外边距不能是负的，margin-left 为 0
+    let a = abs("left: 100px; right: 100px; width: 300px; margin: 0 auto;");
+    assert_eq!((a.x, a.width), (100.0, 300.0));
+    // 宽度和 right 都是 auto 时宽度收缩适应内容
+    let boxes = layout(r#"<html><div id="p"><div id="a">abc de</div></div></html>"#,
+        "#p { position: relative; width: 400px; } #a { position: absolute; left: 50px; font-size: 10px; }");
+    assert_eq!((boxes["a"].x, boxes["a"].width), (50.0, 36.0));
+}
+
+#[test]
+fn fixed_boxes_are_placed_against_the_viewport() {
+    let boxes = layout(
+        r#"<html><div id="p"><div id="a"></div><div id="b"></div></div></html>"#,
+        "#p { position: relative; width: 400px; height: 300px; margin-top: 50px; } \
+         #a { position: fixed; right: 0px; bottom: 0px; width: 10px; height: 10px; } \
+         #b { position: fixed; left: 0px; right: 0px; top: 10%; height: 10%; }",
+    );
+    // 定位的祖先不影响固定定位的盒子
+    assert_eq!((boxes["a"].x, boxes["a"].y), (790.0, 590.0));
+    assert_eq!((boxes["b"].x, boxes["b"].y, boxes["b"].width, boxes["b"].height), (0.0, 60.0, 800.0, 60.0));
+}
@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fs, path::Path};
 
 use image::{Rgba, ImageBuffer};
 
@@ -6,13 +6,20 @@ pub mod parser;
 pub mod html;
 pub mod css;
 pub mod style;
+pub mod font;
+pub mod text;
 pub mod layout;
 pub mod painting;
+pub mod bench;
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--bench-style") {
+        bench::style();
+        return;
+    }
+
     // 获取文件字符串
     let html = fs::read_to_string("src/examples/test.html").unwrap();
-    let css = fs::read_to_string("src/examples/test.css").unwrap();
     
     // 创建一个可视区域
     let mut viewport: layout::Dimensions = Default::default();
@@ -21,9 +28,20 @@ fn main() {
 
     // 解析结构
     let root_node = html::parser::parse(html);
-    let stylesheet = css::parser::parse(css);
-    let style_root = style::style_tree(&root_node, &stylesheet);
-    let layout_root = layout::layout_tree(&style_root, viewport);
+    let loader = css::loader::FileLoader;
+    let mut stylesheets = vec![css::user_agent::stylesheet()];
+    // 用户样式表是可选的
+    let user_css = Path::new("src/examples/user.css");
+    if user_css.exists() {
+        stylesheets.push(css::loader::load_stylesheet(user_css, css::types::Origin::User, &loader).unwrap());
+    }
+    stylesheets.push(css::loader::load_stylesheet(Path::new("src/examples/test.css"), css::types::Origin::Author, &loader).unwrap());
+    let media = css::media::MediaEnvironment::new(viewport.content.width, viewport.content.height);
+    let style_root = style::style_tree(&root_node, &stylesheets, &media);
+    // 加载 @font-face 声明的字体
+    let mut fonts = font::FontCollection::new();
+    fonts.load_font_faces(&stylesheets, &media);
+    let layout_root = layout::layout_tree(&style_root, viewport, &fonts);
 
     // 绘制图形
     let canvas = painting::paint(&layout_root, viewport.content);
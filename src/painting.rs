@@ -8,12 +8,14 @@
  */
 use std::vec;
 
-use crate::{layout::{Rect, LayoutBox, BlockNode, InlineNode}, css::types::{Color, Value}};
+use crate::{font::Font, layout::{Rect, EdgeSizes, LayoutBox, LineBox, Fragment, FragmentKind, BlockNode, InlineNode, AnonymousBlock}, css::types::Color, style::types::{ComputedStyle, Float, Position}};
 
 type DisplayList = Vec<DisplayCommand>;
 
 enum DisplayCommand {
-    SolidColor(Color, Rect)
+    SolidColor(Color, Rect),
+    // 从 (x, baseline) 开始绘制一行文字
+    Text { text: String, x: f32, baseline: f32, font: Font, color: Color },
 }
 
 pub struct Canvas {
@@ -33,75 +35,131 @@ pub fn paint(layout_root: &LayoutBox, bounds: Rect) -> Canvas {
 
 fn build_display_list(layout_root: &LayoutBox) -> DisplayList {
     let mut list = Vec::new();
-    render_layout_box(&mut list, layout_root);
-    return list;
+    render_positioned(&mut list, layout_root);
+    list
 }
 
-fn render_layout_box(list: &mut DisplayList, layout_box: &LayoutBox) {
-    render_background(list, layout_box);
-    render_borders(list, layout_box);
+// 浮动盒子画在普通流的内容上面，定位元素画在浮动盒子上面，浮动盒子之间和定位元素之间按照文档顺序绘制
+fn render_positioned<'a>(list: &mut DisplayList, layout_box: &'a LayoutBox<'a>) {
+    let mut floats = Vec::new();
+    let mut positioned = Vec::new();
+    render_layout_box(list, layout_box, &mut floats, &mut positioned);
+    for child in floats.into_iter().chain(positioned) {
+        render_positioned(list, child);
+    }
+}
+
+// 绘制盒子和它在普通流中的子孙，浮动和定位的子孙放进 floats 和 positioned 之后再绘制
+fn render_layout_box<'a>(list: &mut DisplayList, layout_box: &'a LayoutBox<'a>, floats: &mut Vec<&'a LayoutBox<'a>>, positioned: &mut Vec<&'a LayoutBox<'a>>) {
+    match layout_box.box_type {
+        BlockNode(node) => render_box(list, &node.style, layout_box.dimensions.border_box(), layout_box.dimensions.border),
+        // 行内元素可能跨越多行，按照它在每一行中的片段绘制
+        AnonymousBlock => render_lines(list, &layout_box.lines),
+        InlineNode(_) => {}
+    }
 
     for child in &layout_box.children {
-        render_layout_box(list, child);
+        match child.box_type {
+            BlockNode(node) if node.style.position != Position::Static => positioned.push(child),
+            BlockNode(node) if node.style.float != Float::None => floats.push(child),
+            _ => render_layout_box(list, child, floats, positioned),
+        }
     }
 }
 
-fn render_background(list: &mut DisplayList, layout_box: &LayoutBox) {
-    get_color(layout_box, "background").map(
-        |color| list.push(DisplayCommand::SolidColor(color, layout_box.dimensions.border_box()))
-    );
+fn render_lines(list: &mut DisplayList, lines: &[LineBox]) {
+    for line in lines {
+        for fragment in &line.fragments {
+            render_fragment(list, fragment);
+        }
+    }
 }
 
-fn get_color(layout_box: &LayoutBox, name: &str) -> Option<Color> {
-    match layout_box.box_type {
-        BlockNode(style) | InlineNode(style) => match style.value(name) {
-            Some(Value::ColorValue(color)) => Some(color),
-            _ => None
+fn render_fragment(list: &mut DisplayList, fragment: &Fragment) {
+    let style = &fragment.node.style;
+    match fragment.kind {
+        FragmentKind::Text { ref text, ref font } => {
+            if let Some(color) = visible(style.color) {
+                list.push(DisplayCommand::Text {
+                    text: text.clone(),
+                    x: fragment.rect.x,
+                    // 文字片段的顶部在基线以上字体 ascent 的位置，相对定位的元素中的文字不在行的基线上
+                    baseline: fragment.rect.y + font.ascent(),
+                    font: font.clone(),
+                    color,
+                });
+            }
+        }
+        FragmentKind::Box { left, right } => {
+            // 左右边框只画在包含元素左侧和右侧的片段中
+            let border = EdgeSizes {
+                left: if left { style.border_width.left } else { 0.0 },
+                right: if right { style.border_width.right } else { 0.0 },
+                top: style.border_width.top,
+                bottom: style.border_width.bottom,
+            };
+            render_box(list, style, fragment.rect, border);
         }
-        _ => None
     }
 }
 
-fn render_borders(list: &mut DisplayList, layout_box: &LayoutBox) {
-    let color = match get_color(layout_box, "border-color") {
-        Some(color) => color,
-        _ => return
-    };
+// 绘制边框盒的背景和边框
+fn render_box(list: &mut DisplayList, style: &ComputedStyle, border_box: Rect, border: EdgeSizes) {
+    render_background(list, style, border_box);
+    render_borders(list, style, border_box, border);
+}
+
+fn render_background(list: &mut DisplayList, style: &ComputedStyle, border_box: Rect) {
+    if let Some(color) = visible(style.background_color) {
+        list.push(DisplayCommand::SolidColor(color, border_box));
+    }
+}
 
-    let d = &layout_box.dimensions;
-    let border_box = d.border_box();
+// 透明的颜色不需要绘制
+fn visible(color: Color) -> Option<Color> {
+    Some(color).filter(|color| color.a > 0)
+}
 
+fn render_borders(list: &mut DisplayList, style: &ComputedStyle, border_box: Rect, border: EdgeSizes) {
     // left border
-    list.push(DisplayCommand::SolidColor(color, Rect { 
-        x: border_box.x, 
-        y: border_box.y, 
-        width: d.border.left, 
-        height: border_box.height,
-    }));
+    if let Some(color) = visible(style.border_color.left) {
+        list.push(DisplayCommand::SolidColor(color, Rect { 
+            x: border_box.x, 
+            y: border_box.y, 
+            width: border.left, 
+            height: border_box.height,
+        }));
+    }
 
     // right border
-    list.push(DisplayCommand::SolidColor(color, Rect { 
-        x: border_box.x + border_box.width - d.border.right, 
-        y: border_box.y, 
-        width: d.border.right, 
-        height: border_box.height,
-    }));
+    if let Some(color) = visible(style.border_color.right) {
+        list.push(DisplayCommand::SolidColor(color, Rect { 
+            x: border_box.x + border_box.width - border.right, 
+            y: border_box.y, 
+            width: border.right, 
+            height: border_box.height,
+        }));
+    }
 
     // top border
-    list.push(DisplayCommand::SolidColor(color, Rect { 
-        x: border_box.x, 
-        y: border_box.y, 
-        width: border_box.width, 
-        height: d.border.top,
-    }));
+    if let Some(color) = visible(style.border_color.top) {
+        list.push(DisplayCommand::SolidColor(color, Rect { 
+            x: border_box.x, 
+            y: border_box.y, 
+            width: border_box.width, 
+            height: border.top,
+        }));
+    }
 
     // bottom border
-    list.push(DisplayCommand::SolidColor(color, Rect { 
-        x: border_box.x, 
-        y: border_box.y + border_box.height - d.border.bottom, 
-        width: border_box.width, 
-        height: d.border.bottom,
-    }));
+    if let Some(color) = visible(style.border_color.bottom) {
+        list.push(DisplayCommand::SolidColor(color, Rect { 
+            x: border_box.x, 
+            y: border_box.y + border_box.height - border.bottom, 
+            width: border_box.width, 
+            height: border.bottom,
+        }));
+    }
 }
 
 impl Canvas {
@@ -115,7 +173,7 @@ impl Canvas {
         }
     }
 
-    // 绘制矩形
+    // 绘制矩形或文字
     fn paint_item(&mut self, item: &DisplayCommand) {
         match item {
             &DisplayCommand::SolidColor(color, rect) => {
@@ -131,16 +189,20 @@ impl Canvas {
                     }
                 }
             }
+            DisplayCommand::Text { text, x, baseline, font, color } => {
+                font.rasterize(text, *x, *baseline, |x, y, coverage| self.blend(x, y, *color, coverage));
+            }
         }
     }
-}
-
-trait Clamp {
-    fn clamp(self, lower: Self, upper: Self) -> Self;
-}
 
-impl Clamp for f32 {
-    fn clamp(self, lower: Self, upper: Self) -> Self {
-        self.max(lower).min(upper)
+    // 按照覆盖率和颜色的透明度把颜色混合到像素上，画布以外的像素被忽略
+    fn blend(&mut self, x: i32, y: i32, color: Color, coverage: f32) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let alpha = coverage * color.a as f32 / 255.0;
+        let pixel = &mut self.pixels[y as usize * self.width + x as usize];
+        let mix = |src: u8, dst: u8| (src as f32 * alpha + dst as f32 * (1.0 - alpha)).round() as u8;
+        *pixel = Color { r: mix(color.r, pixel.r), g: mix(color.g, pixel.g), b: mix(color.b, pixel.b), a: mix(255, pixel.a) };
     }
-}
\ No newline at end of file
+}
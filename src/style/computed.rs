@@ -0,0 +1,245 @@
+// 计算值阶段：处理继承、初始值以及 inherit/initial/unset 关键字，替换 var()，并把相对单位转换为绝对单位
+use std::collections::HashMap;
+
+use crate::css::{properties, types::{Value, Unit, UnparsedValue, Calc, CalcType}};
+
+use super::{PropertyMap, variables};
+
+// font-size: medium 对应的像素值
+pub const MEDIUM_FONT_SIZE: f32 = 16.0;
+
+// 计算时需要用到的上下文
+struct Context<'a> {
+    parent: Option<&'a PropertyMap>,
+    // 父元素的字体大小，font-size 的 em 和百分比相对于它计算
+    parent_font_size: f32,
+    // 当前元素的字体大小，其它属性的 em 相对于它计算
+    font_size: f32,
+    // 根元素的字体大小，rem 相对于它计算
+    root_font_size: f32,
+}
+
+// 根据层叠后的值和父元素的计算值，得到元素的计算值
+// root_font_size 为 None 时表示当前元素就是根元素
+pub fn compute(specified: PropertyMap, parent: Option<&PropertyMap>, root_font_size: Option<f32>) -> PropertyMap {
+    // 先确定自定义属性，再替换其它属性中的 var()，替换失败的属性视为 unset
+    let variables = variables::compute(&specified, parent);
+    let specified: PropertyMap = specified.into_iter()
+        .filter(|(name, _)| !properties::is_custom(name))
+        .map(|(name, value)| match value {
+            Value::Unparsed(ref unparsed) => {
+                let value = variables::substitute_value(&name, unparsed, &variables)
+                    .unwrap_or_else(|| Value::Keyword("unset".to_string()));
+                (name, value)
+            }
+            _ => (name, value)
+        })
+        .collect();
+
+    let parent_font_size = parent.map_or(MEDIUM_FONT_SIZE, font_size_of);
+    let mut ctx = Context {
+        parent,
+        parent_font_size,
+        font_size: parent_font_size,
+        root_font_size: root_font_size.unwrap_or(MEDIUM_FONT_SIZE),
+    };
+    let mut computed = HashMap::new();
+
+    // 其它属性依赖 font-size 和 color，所以先计算它们
+    for name in ["font-size", "color"] {
+        let value = compute_property(name, specified.get(name), &ctx, &computed);
+        computed.insert(name.to_string(), value);
+        ctx.font_size = font_size_of(&computed);
+    }
+    for name in properties::names() {
+        if computed.contains_key(name) {
+            continue;
+        }
+        let value = compute_property(name, specified.get(name), &ctx, &computed);
+        computed.insert(name.to_string(), value);
+    }
+
+    // 自定义属性的计算值是替换过 var() 的文本
+    computed.extend(variables.into_iter().map(|(name, css)| {
+        (name, Value::Unparsed(UnparsedValue { css, shorthand: None }))
+    }));
+    computed
+}
+
+// 文本节点没有自己的样式，只继承父元素可继承的属性（自定义属性总是继承）
+pub fn inherit(parent: &PropertyMap) -> PropertyMap {
+    parent.iter()
+        .filter(|(name, _)| properties::is_custom(name) || properties::lookup(name).is_some_and(|p| p.inherited))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+pub fn font_size_of(values: &PropertyMap) -> f32 {
+    match values.get("font-size") {
+        Some(&Value::Length(size, Unit::Px)) => size,
+        _ => MEDIUM_FONT_SIZE
+    }
+}
+
+fn compute_property(name: &str, specified: Option<&Value>, ctx: &Context, computed: &PropertyMap) -> Value {
+    let property = properties::lookup(name).expect("unsupported property");
+    let inherited_value = || ctx.parent.and_then(|parent| parent.get(name)).cloned();
+
+    let keyword = match specified {
+        Some(Value::Keyword(k)) => Some(&**k),
+        _ => None
+    };
+    let inherit = match (specified, keyword) {
+        (_, Some("inherit")) => true,
+        (_, Some("initial")) => false,
+        (None, _) | (_, Some("unset")) => property.inherited,
+        _ => return compute_value(name, specified.unwrap(), ctx, computed)
+    };
+
+    // 继承来的值已经是父元素的计算值，不需要再次计算
+    match inherit.then(inherited_value).flatten() {
+        Some(value) => value,
+        None => compute_value(name, &property.initial, ctx, computed)
+    }
+}
+
+fn compute_value(name: &str, value: &Value, ctx: &Context, computed: &PropertyMap) -> Value {
+    match name {
+        "font-size" => compute_font_size(value, ctx),
+        "font-weight" => compute_font_weight(value, ctx),
+        "line-height" => match *value {
+            Value::Length(p, Unit::Percent) => Value::Length(ctx.font_size * p / 100.0, Unit::Px),
+            Value::Calc(ref calc) => compute_calc(calc, ctx, Some(ctx.font_size)),
+            _ => absolute_length(value, ctx)
+        },
+        "border-top-width" | "border-right-width" | "border-bottom-width" | "border-left-width" => {
+            // border-style 为 none 或 hidden 时边框宽度为 0（属性列表中 border-style 排在 border-width 之前）
+            match computed.get(&name.replace("-width", "-style")) {
+                Some(Value::Keyword(s)) if s == "none" || s == "hidden" => Value::Length(0.0, Unit::Px),
+                _ => compute_border_width(value, ctx)
+            }
+        }
+        // color 自身的 currentcolor 等同于 inherit
+        "color" => match value {
+            Value::Keyword(k) if k == "currentcolor" => {
+                let parent = ctx.parent.and_then(|parent| parent.get("color"));
+                parent.cloned().unwrap_or_else(|| properties::lookup(name).unwrap().initial.clone())
+            }
+            _ => value.clone()
+        },
+        _ if name.ends_with("color") => match value {
+            Value::Keyword(k) if k == "currentcolor" => computed.get("color").cloned().unwrap_or_else(|| value.clone()),
+            _ => value.clone()
+        },
+        _ => absolute_length(value, ctx)
+    }
+}
+
+// 将 em、rem、pt 转换为 px，百分比需要等到布局阶段才能确定
+fn absolute_length(value: &Value, ctx: &Context) -> Value {
+    match *value {
+        Value::Length(f, Unit::Em) => Value::Length(f * ctx.font_size, Unit::Px),
+        Value::Length(f, Unit::Rem) => Value::Length(f * ctx.root_font_size, Unit::Px),
+        Value::Length(f, Unit::Pt) => Value::Length(f * 4.0 / 3.0, Unit::Px),
+        Value::List(ref values, separator) => {
+            Value::List(values.iter().map(|v| absolute_length(v, ctx)).collect(), separator)
+        }
+        Value::Calc(ref calc) => compute_calc(calc, ctx, None),
+        _ => value.clone()
+    }
+}
+
+// 表达式中的相对单位转换为 px，能够确定结果的表达式直接计算出来，包含百分比的表达式留到布局阶段
+// percent_basis 不为 None 时百分比相对于它计算，例如 font-size 的百分比相对于父元素的字体大小
+fn compute_calc(calc: &Calc, ctx: &Context, percent_basis: Option<f32>) -> Value {
+    let calc = calc.map_lengths(&|v, unit| match absolute_length(&Value::Length(v, unit.clone()), ctx) {
+        Value::Length(v, unit) => Calc::Length(v, unit),
+        _ => unreachable!()
+    });
+    match (calc.evaluate(percent_basis), calc.check_type()) {
+        (Some(v), Ok(CalcType::Number)) => Value::Number(v),
+        (Some(v), _) => Value::Length(v, Unit::Px),
+        (None, _) => Value::Calc(calc)
+    }
+}
+
+fn compute_font_size(value: &Value, ctx: &Context) -> Value {
+    let parent = ctx.parent_font_size;
+    let size = match *value {
+        Value::Keyword(ref k) => match &**k {
+            "xx-small" => MEDIUM_FONT_SIZE * 3.0 / 5.0,
+            "x-small" => MEDIUM_FONT_SIZE * 3.0 / 4.0,
+            "small" => MEDIUM_FONT_SIZE * 8.0 / 9.0,
+            "medium" => MEDIUM_FONT_SIZE,
+            "large" => MEDIUM_FONT_SIZE * 6.0 / 5.0,
+            "x-large" => MEDIUM_FONT_SIZE * 3.0 / 2.0,
+            "xx-large" => MEDIUM_FONT_SIZE * 2.0,
+            "smaller" => parent / 1.2,
+            "larger" => parent * 1.2,
+            _ => parent
+        },
+        // font-size 的 em 和百分比相对于父元素的字体大小
+        Value::Length(f, Unit::Em) => f * parent,
+        Value::Length(f, Unit::Percent) => f * parent / 100.0,
+        Value::Calc(ref calc) => return compute_calc(calc, ctx, Some(parent)),
+        _ => return absolute_length(value, ctx)
+    };
+    Value::Length(size, Unit::Px)
+}
+
+fn compute_font_weight(value: &Value, ctx: &Context) -> Value {
+    let parent = match ctx.parent.and_then(|p| p.get("font-weight")) {
+        Some(&Value::Number(weight)) => weight,
+        _ => 400.0
+    };
+    let weight = match *value {
+        Value::Keyword(ref k) => match &**k {
+            "bold" => 700.0,
+            // bolder 和 lighter 相对于父元素的字重
+            "bolder" if parent < 350.0 => 400.0,
+            "bolder" if parent < 550.0 => 700.0,
+            "bolder" => 900.0,
+            "lighter" if parent < 550.0 => 100.0,
+            "lighter" if parent < 750.0 => 400.0,
+            "lighter" => 700.0,
+            _ => 400.0
+        },
+        Value::Number(weight) => weight,
+        _ => 400.0
+    };
+    Value::Number(weight)
+}
+
+fn compute_border_width(value: &Value, ctx: &Context) -> Value {
+    match value {
+        Value::Keyword(k) => Value::Length(match &**k {
+            "thin" => 1.0,
+            "thick" => 5.0,
+            _ => 3.0
+        }, Unit::Px),
+        _ => absolute_length(value, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{css::{self, media::MediaEnvironment, types::{Color, Value}}, html, style::{style_tree, types::{Display, LengthPercentageAuto}}};
+
+    // 属性名称、关键字和全局关键字都不区分大小写
+    #[test]
+    fn keywords_and_property_names_ignore_case() {
+        let root = html::parser::parse("<div><p></p></div>".to_string());
+        let stylesheets = vec![css::parser::parse(
+            "div { Color: #ff0000; } p { DISPLAY: BLOCK; WIDTH: 10PX; color: INHERIT; Margin-Left: Auto; --Gap: 1px; }".to_string(),
+        )];
+        let styled = style_tree(&root, &stylesheets, &MediaEnvironment::new(800.0, 600.0));
+        let p = &styled.children[0];
+        assert_eq!(p.style.display, Display::Block);
+        assert_eq!(p.style.width, LengthPercentageAuto::Px(10.0));
+        assert_eq!(p.style.margin.left, LengthPercentageAuto::Auto);
+        assert_eq!(p.computed_values.get("color"), Some(&Value::ColorValue(Color { r: 255, g: 0, b: 0, a: 255 })));
+        // 自定义属性的名称区分大小写
+        assert!(p.computed_values.contains_key("--Gap"));
+        assert!(!p.computed_values.contains_key("--gap"));
+    }
+}
@@ -0,0 +1,361 @@
+// 增量计算样式：保存上一次计算的结果，DOM 或样式表变化后只重新计算受影响的元素以及依赖它们的后代元素
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    css::{media::MediaEnvironment, types::{Rule, Selector, Stylesheet}},
+    html::types::{AttrMap, ElementData, Node, NodeId, NodeType},
+};
+
+use super::{active_rules, computed, matches, specified_values, types::ComputedStyle, ActiveRules, PropertyMap, StyledNode};
+
+// 上一次计算样式的结果，按照节点的唯一标识保存
+#[derive(Debug, Default)]
+pub struct StyleCache {
+    entries: HashMap<NodeId, CachedStyle>,
+    // 计算时使用的媒体环境，环境变化后所有元素都需要重新计算
+    env: Option<MediaEnvironment>,
+    // 新插入的样式表中的选择器，匹配它们的元素需要重新层叠
+    inserted: Vec<Selector>,
+}
+
+#[derive(Debug)]
+struct CachedStyle {
+    // 计算样式时元素的 ID 和类名，和当前的值比较可以知道修改了哪些
+    id: Option<String>,
+    classes: BTreeSet<String>,
+    // 层叠后的值和计算值
+    specified: PropertyMap,
+    computed_values: PropertyMap,
+}
+
+// 一次增量计算中各个阶段处理的元素数量
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RestyleStats {
+    // 重新匹配规则并层叠的元素
+    pub cascaded: usize,
+    // 重新计算计算值的元素（层叠结果或者继承的值发生了变化）
+    pub recomputed: usize,
+    // 直接使用上一次结果的元素
+    pub reused: usize,
+}
+
+impl StyleCache {
+    // 样式表插入到列表中之后调用，下一次计算时只有匹配新规则的元素需要重新层叠
+    pub fn stylesheet_inserted(&mut self, stylesheet: &Stylesheet) {
+        self.inserted.extend(stylesheet.style_rules().iter().flat_map(|rule| rule.selectors.iter().cloned()));
+    }
+
+    // 样式表被删除或者修改后调用，下一次计算时所有元素都重新计算
+    pub fn invalidate_all(&mut self) {
+        self.entries.clear();
+        self.inserted.clear();
+    }
+}
+
+// 使用 cache 中上一次的结果计算样式，并用这次的结果更新 cache，cache 为空时等同于完整计算
+// 修改元素的属性需要通过 Node::set_attribute 等方法，这样元素会被标记为需要重新计算
+pub fn restyle<'a>(root: &'a Node, stylesheets: &[Stylesheet], env: &MediaEnvironment, cache: &mut StyleCache) -> (StyledNode<'a>, RestyleStats) {
+    if cache.env.as_ref() != Some(env) {
+        cache.invalidate_all();
+        cache.env = Some(env.clone());
+    }
+    let active = active_rules(stylesheets, env);
+
+    let mut restyler = Restyler {
+        stylesheets: &active,
+        dependencies: Dependencies::new(stylesheets),
+        inserted: &cache.inserted,
+        previous: std::mem::take(&mut cache.entries),
+        entries: HashMap::new(),
+        root_font_size_changed: false,
+        stats: RestyleStats::default(),
+    };
+    let root = restyler.restyle_node(root, None, None, false);
+
+    // 已经删除的节点不会被访问到，它们的结果随着 previous 一起丢弃
+    cache.entries = restyler.entries;
+    let stats = restyler.stats;
+    cache.inserted.clear();
+    (root, stats)
+}
+
+// 从 ID 和类名到使用了它们的规则，修改 ID 或类名时只有这些规则可能改变匹配结果
+struct Dependencies<'a> {
+    ids: HashMap<&'a str, Vec<&'a Rule>>,
+    classes: HashMap<&'a str, Vec<&'a Rule>>,
+}
+
+impl<'a> Dependencies<'a> {
+    fn new(stylesheets: &'a [Stylesheet]) -> Dependencies<'a> {
+        let mut dependencies = Dependencies { ids: HashMap::new(), classes: HashMap::new() };
+        for rule in stylesheets.iter().flat_map(|stylesheet| stylesheet.style_rules()) {
+            for selector in &rule.selectors {
+                let Selector::Simple(ref simple) = *selector;
+                if let Some(ref id) = simple.id {
+                    dependencies.ids.entry(id).or_default().push(rule);
+                }
+                for class in &simple.class {
+                    dependencies.classes.entry(class).or_default().push(rule);
+                }
+            }
+        }
+        dependencies
+    }
+
+    // 元素的 ID 或类名修改后，是否有规则在修改前或修改后匹配它，有的话需要重新层叠
+    fn affected(&self, previous: &CachedStyle, elem: &ElementData) -> bool {
+        let classes: BTreeSet<String> = elem.classes().into_iter().map(str::to_string).collect();
+        let changed_ids = [previous.id.as_ref(), elem.id()].into_iter().flatten()
+            .filter(|_| previous.id.as_ref() != elem.id());
+        let changed_classes = previous.classes.symmetric_difference(&classes);
+
+        let rules = changed_ids.filter_map(|id| self.ids.get(&**id))
+            .chain(changed_classes.filter_map(|class| self.classes.get(&**class)))
+            .flatten();
+        let before = previous_element(elem, previous);
+        rules.flat_map(|rule| &rule.selectors).any(|selector| matches(elem, selector) || matches(&before, selector))
+    }
+}
+
+// 用保存的 ID 和类名还原修改之前的元素，用来判断修改之前是否匹配
+fn previous_element(elem: &ElementData, previous: &CachedStyle) -> ElementData {
+    let mut attributes = AttrMap::new();
+    if let Some(ref id) = previous.id {
+        attributes.insert("id".to_string(), id.clone());
+    }
+    let classes: Vec<&str> = previous.classes.iter().map(|class| &**class).collect();
+    attributes.insert("class".to_string(), classes.join(" "));
+    ElementData::new(elem.tag_name.clone(), attributes)
+}
+
+struct Restyler<'a, 'b> {
+    stylesheets: &'b [ActiveRules<'a>],
+    dependencies: Dependencies<'a>,
+    inserted: &'b [Selector],
+    previous: HashMap<NodeId, CachedStyle>,
+    entries: HashMap<NodeId, CachedStyle>,
+    // 根元素的字体大小变化后，所有使用 rem 的元素都需要重新计算
+    root_font_size_changed: bool,
+    stats: RestyleStats,
+}
+
+impl<'a, 'b> Restyler<'a, 'b> {
+    // parent_changed 表示父元素的计算值是否发生了变化，变化后需要重新计算继承的值
+    fn restyle_node<'n>(&mut self, node: &'n Node, parent: Option<&PropertyMap>, root_font_size: Option<f32>, parent_changed: bool) -> StyledNode<'n> {
+        let dirty = node.take_style_dirty();
+        let previous_font_size = self.previous.get(&node.id).map(|previous| computed::font_size_of(&previous.computed_values));
+        let (computed_values, changed) = match node.node_type {
+            NodeType::Element(ref elem) => self.restyle_element(node.id, elem, dirty, parent, root_font_size, parent_changed),
+            NodeType::Text(_) => (parent.map(computed::inherit).unwrap_or_default(), parent_changed)
+        };
+
+        if root_font_size.is_none() {
+            self.root_font_size_changed = previous_font_size != Some(computed::font_size_of(&computed_values));
+        }
+        let root_font_size = root_font_size.unwrap_or_else(|| computed::font_size_of(&computed_values));
+        let children = node.children().iter()
+            .map(|child| self.restyle_node(child, Some(&computed_values), Some(root_font_size), changed))
+            .collect();
+        let style = ComputedStyle::from_values(&computed_values);
+        StyledNode { node, computed_values, style, children }
+    }
+
+    // 返回元素的计算值，以及计算值是否和上一次不同
+    fn restyle_element(
+        &mut self,
+        id: NodeId,
+        elem: &ElementData,
+        dirty: bool,
+        parent: Option<&PropertyMap>,
+        root_font_size: Option<f32>,
+        parent_changed: bool,
+    ) -> (PropertyMap, bool) {
+        let previous = self.previous.remove(&id);
+        let needs_cascade = match previous {
+            None => true,
+            Some(ref previous) => {
+                (dirty && self.dependencies.affected(previous, elem))
+                    || self.inserted.iter().any(|selector| matches(elem, selector))
+            }
+        };
+
+        let specified = match previous {
+            Some(ref previous) if !needs_cascade => previous.specified.clone(),
+            _ => {
+                self.stats.cascaded += 1;
+                specified_values(elem, self.stylesheets)
+            }
+        };
+
+        // 层叠结果和继承的值都没有变化时，计算值也不会变化
+        let inputs_changed = parent_changed || self.root_font_size_changed;
+        let (computed_values, changed) = match previous {
+            Some(previous) if !inputs_changed && previous.specified == specified => {
+                self.stats.reused += 1;
+                (previous.computed_values, false)
+            }
+            previous => {
+                self.stats.recomputed += 1;
+                let computed_values = computed::compute(specified.clone(), parent, root_font_size);
+                let changed = previous.is_none_or(|previous| previous.computed_values != computed_values);
+                (computed_values, changed)
+            }
+        };
+
+        self.entries.insert(id, CachedStyle {
+            id: elem.id().cloned(),
+            classes: elem.classes().into_iter().map(str::to_string).collect(),
+            specified,
+            computed_values: computed_values.clone(),
+        });
+        (computed_values, changed)
+    }
+}
+
+// 每次修改之后增量计算的结果都应该和完整计算的结果相同
+#[cfg(test)]
+mod tests {
+    use crate::{css, html::{self, types::{Node, NodeId, NodeType}}, style::{style_tree, StyledNode}};
+    use crate::css::{media::MediaEnvironment, types::Stylesheet};
+
+    use super::{restyle, RestyleStats, StyleCache};
+
+    const SOURCE: &str = "<html><div id=\"a\" class=\"box\"><div id=\"b\" class=\"item\"><p id=\"c\">text</p></div><div id=\"d\" class=\"item\"></div></div></html>";
+
+    const STYLESHEET: &str = "html { font-size: 20px; } .box { color: #ff0000; } .wide { font-size: 2em; padding-left: 1rem; } \
+                              .item { margin-top: 1em; } .active { color: #0000ff; } #b { display: block; } #e { padding-top: 3px; }";
+
+    fn parse(source: &str, stylesheet: &str) -> (Node, Vec<Stylesheet>) {
+        (html::parser::parse(source.to_string()), vec![css::parser::parse(stylesheet.to_string())])
+    }
+
+    fn env() -> MediaEnvironment {
+        MediaEnvironment::new(800.0, 600.0)
+    }
+
+    // 按照 id 属性查找元素的节点标识
+    fn node_id(node: &Node, id: &str) -> NodeId {
+        fn find(node: &Node, id: &str) -> Option<NodeId> {
+            match node.node_type {
+                NodeType::Element(ref elem) if elem.id().is_some_and(|value| value == id) => Some(node.id),
+                _ => node.children().iter().find_map(|child| find(child, id))
+            }
+        }
+        find(node, id).expect("no element with the id")
+    }
+
+    fn assert_same(restyled: &StyledNode, fresh: &StyledNode) {
+        assert_eq!(restyled.node.id, fresh.node.id);
+        assert_eq!(restyled.computed_values, fresh.computed_values, "node {:?}", restyled.node.node_type);
+        assert_eq!(restyled.children.len(), fresh.children.len());
+        for (restyled, fresh) in restyled.children.iter().zip(&fresh.children) {
+            assert_same(restyled, fresh);
+        }
+    }
+
+    // 先计算一次填充 cache，修改后再增量计算，和完整计算的结果比较
+    fn check<F>(source: &str, stylesheet: &str, modify: F) -> RestyleStats where F: FnOnce(&mut Node, &mut Vec<Stylesheet>, &mut StyleCache) {
+        let (mut root, mut stylesheets) = parse(source, stylesheet);
+        let mut cache = StyleCache::default();
+        restyle(&root, &stylesheets, &env(), &mut cache);
+
+        modify(&mut root, &mut stylesheets, &mut cache);
+        let (restyled, stats) = restyle(&root, &stylesheets, &env(), &mut cache);
+        assert_same(&restyled, &style_tree(&root, &stylesheets, &env()));
+        stats
+    }
+
+    #[test]
+    fn unchanged_tree_reuses_everything() {
+        let stats = check(SOURCE, STYLESHEET, |_, _, _| {});
+        assert_eq!(stats.cascaded, 0);
+        assert_eq!(stats.recomputed, 0);
+    }
+
+    #[test]
+    fn class_change() {
+        let stats = check(SOURCE, STYLESHEET, |root, _, _| {
+            let id = node_id(root, "d");
+            root.find_mut(id).unwrap().set_attribute("class", "item active");
+        });
+        assert_eq!(stats.cascaded, 1);
+    }
+
+    #[test]
+    fn class_removed() {
+        check(SOURCE, STYLESHEET, |root, _, _| {
+            let id = node_id(root, "a");
+            root.find_mut(id).unwrap().remove_attribute("class");
+        });
+    }
+
+    #[test]
+    fn id_change() {
+        check(SOURCE, STYLESHEET, |root, _, _| {
+            let id = node_id(root, "d");
+            root.find_mut(id).unwrap().set_attribute("id", "e");
+        });
+    }
+
+    // 没有选择器依赖的属性修改后不需要重新层叠
+    #[test]
+    fn attribute_change() {
+        let stats = check(SOURCE, STYLESHEET, |root, _, _| {
+            let id = node_id(root, "b");
+            root.find_mut(id).unwrap().set_attribute("title", "hello");
+        });
+        assert_eq!(stats.cascaded, 0);
+        assert_eq!(stats.recomputed, 0);
+    }
+
+    #[test]
+    fn node_insertion() {
+        let stats = check(SOURCE, STYLESHEET, |root, _, _| {
+            let attributes = [("class".to_string(), "item active".to_string())].into_iter().collect();
+            let child = html::types::elem("div".to_string(), attributes, vec![html::types::text("new".to_string())]);
+            let id = node_id(root, "a");
+            root.find_mut(id).unwrap().insert_child(1, child);
+        });
+        assert_eq!(stats.cascaded, 1);
+    }
+
+    #[test]
+    fn node_removal() {
+        check(SOURCE, STYLESHEET, |root, _, _| {
+            let id = node_id(root, "a");
+            root.find_mut(id).unwrap().remove_child(0);
+        });
+    }
+
+    #[test]
+    fn stylesheet_insertion() {
+        let stats = check(SOURCE, STYLESHEET, |_, stylesheets, cache| {
+            let stylesheet = css::parser::parse(".item { padding-bottom: 2em; } #c { color: #00ff00; }".to_string());
+            cache.stylesheet_inserted(&stylesheet);
+            stylesheets.push(stylesheet);
+        });
+        assert_eq!(stats.cascaded, 3);
+    }
+
+    // 选择器只有简单选择器，后代元素通过继承的值和 em、rem 依赖祖先元素：
+    // 祖先的类名变化后，后代即使没有重新层叠也要重新计算，没有变化的兄弟子树直接复用
+    #[test]
+    fn descendants_of_changed_element() {
+        let stats = check(SOURCE, STYLESHEET, |root, _, _| {
+            let id = node_id(root, "b");
+            root.find_mut(id).unwrap().set_attribute("class", "item wide");
+        });
+        assert_eq!(stats.cascaded, 1);
+        assert!(stats.recomputed >= 2);
+        assert!(stats.reused >= 2);
+    }
+
+    // 根元素的字体大小变化后，所有使用 rem 的元素都要重新计算
+    #[test]
+    fn root_font_size_change() {
+        let source = "<html><div id=\"a\"><div class=\"wide\"></div></div></html>";
+        check(source, STYLESHEET, |root, _, _| {
+            root.set_attribute("class", "wide");
+        });
+    }
+}
@@ -0,0 +1,316 @@
+// 调试用的查询接口：查看节点的全部解析值（和 getComputedStyle 相同），以及某个属性的值是由哪条规则决定的
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    css::{media::MediaEnvironment, serializer::{self, Mode}, types::{Declaration, Origin, Rule, Selector, Specificity, Stylesheet, Unit, Value}},
+    html::types::{NodeId, NodeType},
+    layout::{relative_offset, BlockNode, InlineNode, LayoutBox, Rect},
+};
+
+use super::{active_rules, candidates, cascaded_value, matches, types::{BoxSizing, Position}, StyledNode};
+
+// 属性的层叠过程：匹配元素的全部规则，以及最终生效的声明
+#[derive(Debug)]
+pub struct Explanation<'a> {
+    pub property: String,
+    // 按照样式表和规则出现的顺序排列
+    pub matched_rules: Vec<MatchedRuleInfo<'a>>,
+    // 生效的声明，没有时属性取继承的值或者初始值
+    pub winner: Option<&'a Declaration>,
+    pub computed_value: Option<Value>,
+}
+
+#[derive(Debug)]
+pub struct MatchedRuleInfo<'a> {
+    pub rule: &'a Rule,
+    // 规则中第一个匹配元素的选择器，它决定了规则的权重
+    pub selector: &'a Selector,
+    pub specificity: Specificity,
+    pub origin: Origin,
+    // 规则中这个属性的声明（简写属性已经展开为长属性）
+    pub declarations: Vec<&'a Declaration>,
+}
+
+// 查找盒子时记录的包含块：普通流中的盒子相对于最近的块容器的内容区，
+// 绝对定位的盒子相对于最近的定位祖先的内边距盒，固定定位的盒子相对于视口
+#[derive(Debug, Clone, Copy)]
+struct ContainingBlocks {
+    block: Rect,
+    positioned: Rect,
+    viewport: Rect,
+}
+
+// 节点的全部解析值，按属性名称排序，viewport 为布局时使用的视口，找不到节点或者节点没有生成盒子（display: none）时返回 None
+// 大多数属性的解析值就是计算值，和布局有关的属性使用布局之后的像素值：
+// 块级盒子的 width 和 height、外边距和内边距、定位元素的 top、right、bottom、left，例如 width: 50% 换算为实际的宽度
+pub fn computed_style(layout_root: &LayoutBox, viewport: Rect, node_id: NodeId) -> Option<BTreeMap<String, Value>> {
+    let blocks = ContainingBlocks { block: viewport, positioned: viewport, viewport };
+    let (layout_box, containing_block) = find_box(layout_root, node_id, blocks)?;
+    let node = match layout_box.box_type {
+        BlockNode(node) | InlineNode(node) => node,
+        _ => unreachable!()
+    };
+    let mut values: BTreeMap<String, Value> = node.computed_values.iter().map(|(name, value)| (name.clone(), value.clone())).collect();
+    if let NodeType::Element(_) = node.node.node_type {
+        resolve_used_values(&mut values, layout_box, node, containing_block);
+    }
+    Some(values)
+}
+
+// 返回节点生成的盒子和它的包含块
+fn find_box<'a, 'b>(layout_box: &'b LayoutBox<'a>, id: NodeId, blocks: ContainingBlocks) -> Option<(&'b LayoutBox<'a>, Rect)> {
+    let node = match layout_box.box_type {
+        BlockNode(node) | InlineNode(node) => Some(node),
+        _ => None
+    };
+    if let Some(node) = node.filter(|node| node.node.id == id) {
+        let containing_block = match node.style.position {
+            Position::Absolute => blocks.positioned,
+            Position::Fixed => blocks.viewport,
+            _ => blocks.block,
+        };
+        return Some((layout_box, containing_block));
+    }
+
+    let mut blocks = blocks;
+    if let BlockNode(node) = layout_box.box_type {
+        blocks.block = layout_box.dimensions.content;
+        if node.style.position != Position::Static {
+            blocks.positioned = layout_box.dimensions.padding_box();
+        }
+    }
+    layout_box.children.iter().find_map(|child| find_box(child, id, blocks))
+}
+
+fn resolve_used_values(values: &mut BTreeMap<String, Value>, layout_box: &LayoutBox, node: &StyledNode, containing_block: Rect) {
+    let style = &node.style;
+    let mut set = |name: &str, px: f32| {
+        values.insert(name.to_string(), Value::Length(px, Unit::Px));
+    };
+
+    let d = layout_box.dimensions;
+    match layout_box.box_type {
+        BlockNode(_) => {
+            // width 和 height 是 box-sizing 指定的盒子的尺寸
+            let size = match style.box_sizing {
+                BoxSizing::ContentBox => d.content,
+                BoxSizing::BorderBox => d.border_box(),
+            };
+            set("width", size.width);
+            set("height", size.height);
+            for (side, margin, padding) in [
+                ("top", d.margin.top, d.padding.top),
+                ("right", d.margin.right, d.padding.right),
+                ("bottom", d.margin.bottom, d.padding.bottom),
+                ("left", d.margin.left, d.padding.left),
+            ] {
+                set(&format!("margin-{}", side), margin);
+                set(&format!("padding-{}", side), padding);
+            }
+        }
+        // 行内盒子的 width 和 height 不起作用，保持计算值，外边距和内边距的百分比相对于包含块的宽度
+        _ => {
+            let basis = containing_block.width;
+            for (side, margin, padding) in [
+                ("top", &style.margin.top, &style.padding.top),
+                ("right", &style.margin.right, &style.padding.right),
+                ("bottom", &style.margin.bottom, &style.padding.bottom),
+                ("left", &style.margin.left, &style.padding.left),
+            ] {
+                set(&format!("margin-{}", side), margin.resolve(Some(basis)).to_px());
+                set(&format!("padding-{}", side), padding.resolve(basis));
+            }
+        }
+    }
+
+    // 非定位元素的 top、right、bottom、left 不起作用，粘性定位的偏移取决于滚动的位置，都保持计算值
+    let (top, right, bottom, left) = match style.position {
+        Position::Static | Position::Sticky => return,
+        Position::Relative => {
+            let (dx, dy) = relative_offset(style, containing_block.width, Some(containing_block.height));
+            (dy, -dx, -dy, dx)
+        }
+        // 外边距盒到包含块各边的距离
+        Position::Absolute | Position::Fixed => {
+            let margin_box = d.margin_box();
+            (
+                margin_box.y - containing_block.y,
+                containing_block.x + containing_block.width - margin_box.x - margin_box.width,
+                containing_block.y + containing_block.height - margin_box.y - margin_box.height,
+                margin_box.x - containing_block.x,
+            )
+        }
+    };
+    set("top", top);
+    set("right", right);
+    set("bottom", bottom);
+    set("left", left);
+}
+
+// 解释元素的某个属性为什么是现在的值，stylesheets 和 env 需要和计算 styled_root 时使用的相同
+// 找不到节点或者节点不是元素时返回 None
+pub fn explain<'a>(
+    styled_root: &StyledNode,
+    stylesheets: &'a [Stylesheet],
+    env: &MediaEnvironment,
+    node_id: NodeId,
+    property: &str,
+) -> Option<Explanation<'a>> {
+    let node = find(styled_root, node_id)?;
+    let NodeType::Element(ref elem) = node.node.node_type else { return None };
+
+    let active = active_rules(stylesheets, env);
+    let matched_rules = active.iter().flat_map(|(origin, selector_map)| {
+        selector_map.matching_rules(elem).into_iter().map(move |(specificity, rule)| MatchedRuleInfo {
+            rule,
+            selector: rule.selectors.iter().find(|selector| matches(elem, selector)).unwrap(),
+            specificity,
+            origin: *origin,
+            declarations: rule.declarations.iter().filter(|d| d.name == property).collect(),
+        })
+    }).collect();
+    let winner = candidates(elem, &active).get(property).and_then(|candidates| cascaded_value(candidates));
+
+    Some(Explanation {
+        property: property.to_string(),
+        matched_rules,
+        winner,
+        computed_value: node.computed_values.get(property).cloned(),
+    })
+}
+
+fn find<'a, 'b>(node: &'b StyledNode<'a>, id: NodeId) -> Option<&'b StyledNode<'a>> {
+    if node.node.id == id {
+        return Some(node);
+    }
+    node.children.iter().find_map(|child| find(child, id))
+}
+
+// 例如：
+// margin-left: 8px
+//   body (0, 0, 1) UserAgent
+//     > margin-left: 8px
+impl<'a> fmt::Display for Explanation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let computed = self.computed_value.as_ref().map_or("(none)".to_string(), |value| serializer::serialize_value(value, Mode::Pretty));
+        writeln!(f, "{}: {}", self.property, computed)?;
+        for matched in &self.matched_rules {
+            writeln!(f, "  {} {:?} {:?}", serializer::serialize_selector(matched.selector), matched.specificity, matched.origin)?;
+            for declaration in &matched.declarations {
+                // 生效的声明用 > 标记
+                let marker = if self.winner.is_some_and(|winner| std::ptr::eq(winner, *declaration)) { ">" } else { " " };
+                let important = if declaration.important { " !important" } else { "" };
+                writeln!(f, "    {} {}: {}{}", marker, declaration.name, serializer::serialize_value(&declaration.value, Mode::Pretty), important)?;
+            }
+        }
+        if self.winner.is_none() {
+            writeln!(f, "  no declaration applies, the value is inherited or initial")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        css::{self, media::MediaEnvironment, types::{Color, Origin, Unit, Value}},
+        font::FontCollection,
+        html::{self, types::{Node, NodeId, NodeType}},
+        layout::{self, Dimensions},
+        style::style_tree,
+    };
+
+    use super::{computed_style, explain};
+
+    const SOURCE: &str = "<html><div id=\"outer\"><div id=\"a\"></div><div id=\"rel\"></div><div id=\"abs\"></div>\
+                          <span id=\"s\">x</span><p id=\"hidden\"></p></div></html>";
+
+    // 按照 id 属性查找元素的节点标识
+    fn node_id(node: &Node, id: &str) -> NodeId {
+        fn find(node: &Node, id: &str) -> Option<NodeId> {
+            match node.node_type {
+                NodeType::Element(ref elem) if elem.id().is_some_and(|value| value == id) => Some(node.id),
+                _ => node.children().iter().find_map(|child| find(child, id))
+            }
+        }
+        find(node, id).expect("no element with the id")
+    }
+
+    fn px(v: f32) -> Option<Value> {
+        Some(Value::Length(v, Unit::Px))
+    }
+
+    #[test]
+    fn computed_style_resolves_layout_values() {
+        let root = html::parser::parse(SOURCE.to_string());
+        let stylesheets = vec![css::user_agent::stylesheet(), css::parser::parse("\
+            #outer { width: 400px; padding: 10px; position: relative; } \
+            #a { width: 50%; height: 20px; margin: 0 auto; padding: 5%; } \
+            #rel { position: relative; left: 10%; top: 5px; height: 10px; } \
+            #abs { position: absolute; right: 0; bottom: 10px; width: 25%; height: 50px; padding: 5px; box-sizing: border-box; } \
+            span { margin-left: 10%; } #hidden { display: none; }".to_string())];
+        let styled = style_tree(&root, &stylesheets, &MediaEnvironment::new(800.0, 600.0));
+        let mut viewport: Dimensions = Default::default();
+        viewport.content.width = 800.0;
+        viewport.content.height = 600.0;
+        let layout_root = layout::layout_tree(&styled, viewport, &FontCollection::new());
+        let style = |id: &str| computed_style(&layout_root, viewport.content, node_id(&root, id));
+
+        // 百分比相对于包含块计算，auto 的外边距为实际的外边距
+        let a = style("a").unwrap();
+        assert_eq!((a.get("width").cloned(), a.get("height").cloned()), (px(200.0), px(20.0)));
+        assert_eq!((a.get("margin-left").cloned(), a.get("margin-right").cloned()), (px(80.0), px(80.0)));
+        assert_eq!((a.get("padding-top").cloned(), a.get("padding-left").cloned()), (px(20.0), px(20.0)));
+        assert_eq!(a.get("top"), Some(&Value::Keyword("auto".to_string())));
+        // 计算值没有变化的属性原样返回
+        assert_eq!(a.get("display"), Some(&Value::Keyword("block".to_string())));
+
+        // 相对定位的 right 和 bottom 是 left 和 top 的相反数
+        let rel = style("rel").unwrap();
+        assert_eq!((rel.get("width").cloned(), rel.get("left").cloned(), rel.get("right").cloned()), (px(400.0), px(40.0), px(-40.0)));
+        assert_eq!((rel.get("top").cloned(), rel.get("bottom").cloned()), (px(5.0), px(-5.0)));
+
+        // 绝对定位的盒子相对于定位祖先的内边距盒，border-box 的 width 包括内边距
+        let abs = style("abs").unwrap();
+        assert_eq!((abs.get("width").cloned(), abs.get("height").cloned()), (px(105.0), px(50.0)));
+        assert_eq!((abs.get("left").cloned(), abs.get("right").cloned(), abs.get("bottom").cloned()), (px(315.0), px(0.0), px(10.0)));
+
+        // 行内盒子的 width 保持 auto，外边距的百分比相对于包含块的宽度
+        let span = style("s").unwrap();
+        assert_eq!(span.get("width"), Some(&Value::Keyword("auto".to_string())));
+        assert_eq!(span.get("margin-left").cloned(), px(40.0));
+
+        assert!(style("hidden").is_none());
+    }
+
+    #[test]
+    fn explain_lists_matched_rules_and_the_winner() {
+        let root = html::parser::parse("<div id=\"a\" class=\"x\"></div>".to_string());
+        let stylesheets = vec![
+            css::user_agent::stylesheet(),
+            css::parser::parse("div { color: #000000; } #a, div { color: #ff0000; } .x { margin-top: 1px; } p { color: #0000ff; }".to_string()),
+        ];
+        let env = MediaEnvironment::new(800.0, 600.0);
+        let styled = style_tree(&root, &stylesheets, &env);
+        let explanation = explain(&styled, &stylesheets, &env, root.id, "color").unwrap();
+
+        let matched: Vec<_> = explanation.matched_rules.iter()
+            .map(|matched| (css::serializer::serialize_selector(matched.selector), matched.specificity, matched.origin, matched.declarations.len()))
+            .collect();
+        assert_eq!(matched, [
+            ("div".to_string(), (0, 0, 1), Origin::UserAgent, 0),
+            ("div".to_string(), (0, 0, 1), Origin::Author, 1),
+            ("#a".to_string(), (1, 0, 0), Origin::Author, 1),
+            (".x".to_string(), (0, 1, 0), Origin::Author, 0),
+        ]);
+        let winner = explanation.winner.unwrap();
+        assert!(std::ptr::eq(winner, explanation.matched_rules[2].declarations[0]));
+        assert_eq!(explanation.computed_value, Some(Value::ColorValue(Color { r: 255, g: 0, b: 0, a: 255 })));
+        assert!(explanation.to_string().contains("  #a (1, 0, 0) Author\n    > color: #ff0000\n"));
+
+        // 没有声明时说明值来自继承或者初始值
+        let explanation = explain(&styled, &stylesheets, &env, root.id, "font-size").unwrap();
+        assert!(explanation.winner.is_none());
+        assert!(explanation.to_string().ends_with("no declaration applies, the value is inherited or initial\n"));
+    }
+}
@@ -0,0 +1,262 @@
+/**
+ * 该模块负责组合‘节点树’和‘样式列表’，生成的数据描述了每个节点分别应用了哪些属性，结构如下：
+ * {
+ *    node: 当前节点，
+ *    computed_values：当前节点应用了哪些样式（已经处理过继承和相对单位的计算值），
+ *    style：强类型的计算样式，布局和绘制阶段读取它，
+ *    children：子节点
+ * }
+ */
+use std::collections::{HashMap, BTreeSet};
+
+use rayon::prelude::*;
+
+pub mod computed;
+pub mod incremental;
+pub mod inspect;
+pub mod selector_map;
+pub mod sharing;
+pub mod types;
+pub mod variables;
+
+use crate::{css::{properties, media::MediaEnvironment, types::{Value, SimpleSelector, Selector, Rule, Specificity, Stylesheet, Origin, Declaration}}, html::types::{Node, ElementData, NodeType}};
+
+use self::{selector_map::SelectorMap, sharing::{SharingCache, SharingStats}, types::ComputedStyle};
+
+// 一个元素应用的样式
+pub type PropertyMap = HashMap<String, Value>;
+
+// 一个元素可以有多个 MatchedRule，Specificity 用来判断 css 的优先级
+type MatchedRule<'a> = (Specificity, &'a Rule);
+
+// 一个样式表的来源，以及其中在当前媒体环境下生效的规则
+type ActiveRules<'a> = (Origin, SelectorMap<'a>);
+
+#[derive(Debug)]
+pub struct StyledNode<'a> {
+    pub node: &'a Node,
+    pub computed_values: PropertyMap,
+    pub style: ComputedStyle,
+    pub children: Vec<StyledNode<'a>>,
+}
+
+// 遍历节点树的方式：依次处理每个节点，或者用多个线程同时处理兄弟节点，两种方式的结果完全相同
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Traversal {
+    Sequential,
+    Parallel,
+}
+
+// stylesheets 按照出现的顺序参与层叠，每个样式表的来源（浏览器默认、用户、作者）决定了它的优先级
+// @media 规则中的样式只有在 env 满足媒体查询时才生效
+pub fn style_tree<'a>(root: &'a Node, stylesheets: &[Stylesheet], env: &MediaEnvironment) -> StyledNode<'a> {
+    style_tree_with_stats(root, stylesheets, env, Traversal::Parallel).0
+}
+
+// 同 style_tree，可以指定遍历方式，并返回样式共享缓存的命中情况
+pub fn style_tree_with_stats<'a>(
+    root: &'a Node,
+    stylesheets: &[Stylesheet],
+    env: &MediaEnvironment,
+    traversal: Traversal,
+) -> (StyledNode<'a>, SharingStats) {
+    warn_unknown_properties(stylesheets);
+    let active = active_rules(stylesheets, env);
+    let ctx = Context { stylesheets: &active, traversal };
+
+    // 根节点没有可以共享样式的兄弟节点
+    let mut stats = SharingStats::default();
+    if let NodeType::Element(_) = root.node_type {
+        stats.misses += 1;
+    }
+    let (root, subtree_stats) = style_subtree(root, own_values(root, &ctx, None, None), &ctx, None);
+    stats += subtree_stats;
+    (root, stats)
+}
+
+// 每个样式表在当前媒体环境下生效的规则
+fn active_rules<'a>(stylesheets: &'a [Stylesheet], env: &MediaEnvironment) -> Vec<ActiveRules<'a>> {
+    stylesheets.iter()
+        .map(|stylesheet| (stylesheet.origin, SelectorMap::new(&stylesheet.effective_rules(env))))
+        .collect()
+}
+
+// 遍历节点树时不变的参数
+struct Context<'a, 'b> {
+    stylesheets: &'b [ActiveRules<'a>],
+    traversal: Traversal,
+}
+
+// 引擎不支持的属性不参与层叠，每个属性只警告一次
+fn warn_unknown_properties(stylesheets: &[Stylesheet]) {
+    let unknown: BTreeSet<&str> = stylesheets.iter()
+        .flat_map(|stylesheet| stylesheet.style_rules())
+        .flat_map(|rule| &rule.declarations)
+        .map(|declaration| &*declaration.name)
+        .filter(|name| properties::lookup(name).is_none() && !properties::is_custom(name))
+        .collect();
+    for name in unknown {
+        eprintln!("warning: unsupported property `{}` is ignored", name);
+    }
+}
+
+// 节点自身的计算值，parent 为父元素的计算值，root_font_size 为根元素的字体大小（rem 的基准），根元素时两者都为 None
+fn own_values(node: &Node, ctx: &Context, parent: Option<&PropertyMap>, root_font_size: Option<f32>) -> PropertyMap {
+    match node.node_type {
+        NodeType::Element(ref elem) => computed::compute(specified_values(elem, ctx.stylesheets), parent, root_font_size),
+        NodeType::Text(_) => parent.map(computed::inherit).unwrap_or_default()
+    }
+}
+
+// 已经知道节点自身的计算值，继续计算它的子树
+fn style_subtree<'a>(
+    node: &'a Node,
+    computed_values: PropertyMap,
+    ctx: &Context,
+    root_font_size: Option<f32>,
+) -> (StyledNode<'a>, SharingStats) {
+    let root_font_size = root_font_size.unwrap_or_else(|| computed::font_size_of(&computed_values));
+    let (children, stats) = style_children(node, &computed_values, ctx, root_font_size);
+    let style = ComputedStyle::from_values(&computed_values);
+    (StyledNode { node, computed_values, style, children }, stats)
+}
+
+// 计算全部子节点的样式：先按照顺序确定哪些兄弟元素可以共享样式，
+// 再计算其余子节点自身的样式，最后计算每个子节点的子树，后两步在并行遍历时由多个线程同时进行
+fn style_children<'a>(
+    node: &'a Node,
+    parent: &PropertyMap,
+    ctx: &Context,
+    root_font_size: f32,
+) -> (Vec<StyledNode<'a>>, SharingStats) {
+    let mut stats = SharingStats::default();
+    let mut cache = SharingCache::default();
+    let shared: Vec<Option<usize>> = node.children().iter().enumerate().map(|(index, child)| match child.node_type {
+        NodeType::Element(ref elem) => {
+            let found = cache.lookup(elem, &mut stats);
+            if found.is_none() {
+                cache.insert(elem, index);
+            }
+            found
+        }
+        NodeType::Text(_) => None
+    }).collect();
+
+    // 只有一个子节点时没有可以并行的工作
+    let parallel = ctx.traversal == Traversal::Parallel && node.children().len() > 1;
+    let compute = |(child, shared): (&Node, &Option<usize>)| {
+        shared.is_none().then(|| own_values(child, ctx, Some(parent), Some(root_font_size)))
+    };
+    let mut values: Vec<Option<PropertyMap>> = if parallel {
+        node.children().par_iter().zip(&shared).map(compute).collect()
+    } else {
+        node.children().iter().zip(&shared).map(compute).collect()
+    };
+    // 共享的样式总是来自前面没有共享样式的兄弟元素
+    for (index, shared) in shared.iter().enumerate() {
+        if let Some(from) = *shared {
+            values[index] = values[from].clone();
+        }
+    }
+
+    let subtree = |(child, values): (&'a Node, Option<PropertyMap>)| {
+        style_subtree(child, values.unwrap(), ctx, Some(root_font_size))
+    };
+    let children: Vec<(StyledNode, SharingStats)> = if parallel {
+        node.children().par_iter().zip(values).map(subtree).collect()
+    } else {
+        node.children().iter().zip(values).map(subtree).collect()
+    };
+
+    let children = children.into_iter().map(|(child, child_stats)| {
+        stats += child_stats;
+        child
+    }).collect();
+    (children, stats)
+}
+
+// 获取元素的样式列表
+fn specified_values(elem: &ElementData, stylesheets: &[ActiveRules]) -> PropertyMap {
+    candidates(elem, stylesheets).into_iter()
+        .map(|(name, candidates)| {
+            let value = cascaded_value(&candidates).map_or_else(|| Value::Keyword("unset".to_string()), |d| d.value.clone());
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+// 按属性分组的候选声明，每组内部按照优先级从低到高排列
+fn candidates<'a>(elem: &ElementData, stylesheets: &[ActiveRules<'a>]) -> HashMap<&'a str, Vec<(Origin, &'a Declaration)>> {
+    let mut declarations: Vec<(u8, Specificity, Origin, &Declaration)> = Vec::new();
+    for (origin, selector_map) in stylesheets {
+        for (specificity, rule) in selector_map.matching_rules(elem) {
+            for declaration in rule.declarations.iter().filter(|d| properties::lookup(&d.name).is_some() || properties::is_custom(&d.name)) {
+                let level = cascade_level(*origin, declaration.important);
+                declarations.push((level, specificity, *origin, declaration));
+            }
+        }
+    }
+
+    // 先按照来源和 !important，再按照 css 选择器的权重渲染，优先级低的先渲染
+    // 排序是稳定的，优先级相同时后出现的声明覆盖先出现的
+    declarations.sort_by_key(|&(level, specificity, _, _)| (level, specificity));
+
+    let mut candidates: HashMap<&str, Vec<(Origin, &Declaration)>> = HashMap::new();
+    for (_, _, origin, declaration) in declarations {
+        candidates.entry(&declaration.name).or_default().push((origin, declaration));
+    }
+    candidates
+}
+
+// 取优先级最高的声明，revert 会回退到更低来源中优先级最高的声明，都没有时返回 None（视为 unset）
+fn cascaded_value<'a>(candidates: &[(Origin, &'a Declaration)]) -> Option<&'a Declaration> {
+    let mut limit: Option<Origin> = None;
+    for &(origin, declaration) in candidates.iter().rev() {
+        if limit.is_some_and(|limit| origin >= limit) {
+            continue;
+        }
+        match declaration.value {
+            Value::Keyword(ref k) if k == "revert" => limit = Some(origin),
+            _ => return Some(declaration)
+        }
+    }
+    None
+}
+
+// 层叠的优先级，数值越大优先级越高：普通声明按照 浏览器默认 < 用户 < 作者，!important 声明的顺序则相反
+fn cascade_level(origin: Origin, important: bool) -> u8 {
+    match (important, origin) {
+        (false, Origin::UserAgent) => 0,
+        (false, Origin::User) => 1,
+        (false, Origin::Author) => 2,
+        (true, Origin::Author) => 3,
+        (true, Origin::User) => 4,
+        (true, Origin::UserAgent) => 5,
+    }
+}
+
+fn matches(elem: &ElementData, selector: &Selector) -> bool {
+    match *selector {
+        Selector::Simple(ref simple_selector) => matches_simple_selector(elem, simple_selector)
+    }
+}
+
+fn matches_simple_selector(elem: &ElementData, selector: &SimpleSelector) -> bool {
+    // 如果有标签，并且和当前匹配元素的标签对不上，则返回
+    if selector.tag_name.iter().any(|name| elem.tag_name != *name) {
+        return false;
+    }
+
+    // 如果有 ID，并且和当前匹配元素的 ID 对不上，则返回
+    if selector.id.iter().any(|id| elem.id() != Some(id)) {
+        return false;
+    }
+
+    // 如果某个类选择器不在当前匹配的元素中，则返回
+    let elem_classes = elem.classes();
+    if selector.class.iter().any(|class| !elem_classes.contains(&**class)) {
+        return false;
+    }
+
+    true
+}
@@ -0,0 +1,122 @@
+// 按照选择器中的 ID、类名、标签名把规则分组，匹配元素时只检查可能匹配的分组，而不是全部规则
+use std::collections::HashMap;
+
+use crate::{css::types::{Rule, Selector}, html::types::ElementData};
+
+use super::{matches, MatchedRule};
+
+// (规则在样式表中的顺序, 选择器在规则中的顺序, 规则)
+type Entry<'a> = (usize, usize, &'a Rule);
+
+#[derive(Debug, Default)]
+pub struct SelectorMap<'a> {
+    ids: HashMap<&'a str, Vec<Entry<'a>>>,
+    classes: HashMap<&'a str, Vec<Entry<'a>>>,
+    tags: HashMap<&'a str, Vec<Entry<'a>>>,
+    // 没有 ID、类名和标签名的选择器，例如 *
+    universal: Vec<Entry<'a>>,
+}
+
+impl<'a> SelectorMap<'a> {
+    // 每个选择器只放进一个分组，优先级为 ID > 第一个类名 > 标签名 > 通用
+    pub fn new(rules: &[&'a Rule]) -> SelectorMap<'a> {
+        let mut map = SelectorMap::default();
+        for (rule_index, &rule) in rules.iter().enumerate() {
+            for (selector_index, selector) in rule.selectors.iter().enumerate() {
+                let entry = (rule_index, selector_index, rule);
+                let Selector::Simple(ref simple) = *selector;
+                if let Some(ref id) = simple.id {
+                    map.ids.entry(id).or_default().push(entry);
+                } else if let Some(class) = simple.class.first() {
+                    map.classes.entry(class).or_default().push(entry);
+                } else if let Some(ref tag_name) = simple.tag_name {
+                    map.tags.entry(tag_name).or_default().push(entry);
+                } else {
+                    map.universal.push(entry);
+                }
+            }
+        }
+        map
+    }
+
+    // 结果按照规则在样式表中的顺序排列，每条规则使用第一个匹配的选择器的权重，和逐条检查全部规则的结果相同
+    pub fn matching_rules(&self, elem: &ElementData) -> Vec<MatchedRule<'a>> {
+        let empty = Vec::new();
+        let candidates = elem.id().and_then(|id| self.ids.get(&**id)).unwrap_or(&empty).iter()
+            .chain(elem.classes().into_iter().flat_map(|class| self.classes.get(class).unwrap_or(&empty)))
+            .chain(self.tags.get(&*elem.tag_name).unwrap_or(&empty))
+            .chain(&self.universal);
+
+        // 同一条规则的多个选择器可能在不同的分组中匹配，只保留顺序最靠前的选择器
+        let mut matched: HashMap<usize, (usize, &'a Rule)> = HashMap::new();
+        for &(rule_index, selector_index, rule) in candidates {
+            let selector = &rule.selectors[selector_index];
+            if matched.get(&rule_index).is_some_and(|&(index, _)| index < selector_index) || !matches(elem, selector) {
+                continue;
+            }
+            matched.insert(rule_index, (selector_index, rule));
+        }
+
+        let mut result: Vec<(usize, MatchedRule<'a>)> = matched.into_iter()
+            .map(|(rule_index, (selector_index, rule))| (rule_index, (rule.selectors[selector_index].specificity(), rule)))
+            .collect();
+        result.sort_by_key(|&(rule_index, _)| rule_index);
+        result.into_iter().map(|(_, matched_rule)| matched_rule).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{css::{parser, types::Rule}, html::types::{AttrMap, ElementData}};
+
+    use super::{super::{matches, MatchedRule}, SelectorMap};
+
+    // 分组之前的做法：按照顺序检查每条规则，使用第一个匹配的选择器
+    fn linear<'a>(rules: &[&'a Rule], elem: &ElementData) -> Vec<MatchedRule<'a>> {
+        rules.iter()
+            .filter_map(|&rule| rule.selectors.iter().find(|selector| matches(elem, selector)).map(|selector| (selector.specificity(), rule)))
+            .collect()
+    }
+
+    fn element(tag_name: &str, id: Option<&str>, class: Option<&str>) -> ElementData {
+        let mut attributes = AttrMap::new();
+        if let Some(id) = id {
+            attributes.insert("id".to_string(), id.to_string());
+        }
+        if let Some(class) = class {
+            attributes.insert("class".to_string(), class.to_string());
+        }
+        ElementData::new(tag_name.to_string(), attributes)
+    }
+
+    #[test]
+    fn same_as_linear_scan() {
+        let stylesheet = parser::parse("\
+            * { color: #000; } div { width: 1px; } p { width: 2px; } #main { width: 3px; } .a { width: 4px; } .b { width: 5px; } \
+            div.a { width: 6px; } .a.b { width: 7px; } .b.a { width: 8px; } div#main.a.b { width: 9px; } p#main { width: 10px; } \
+            p, .a, #main { width: 11px; } *, div.b { width: 12px; } #other, span { width: 13px; } div { height: 1px; }".to_string());
+        let rules = stylesheet.style_rules();
+        let map = SelectorMap::new(&rules);
+
+        let elements = [
+            element("div", None, None),
+            element("p", None, None),
+            element("span", None, None),
+            element("div", Some("main"), None),
+            element("p", Some("main"), Some("a")),
+            element("div", None, Some("a")),
+            element("div", None, Some("b a")),
+            element("div", Some("main"), Some("a b")),
+            element("section", Some("other"), Some("c")),
+        ];
+        for elem in &elements {
+            let expected = linear(&rules, elem);
+            let actual = map.matching_rules(elem);
+            assert_eq!(actual.len(), expected.len(), "{:?}", elem);
+            for ((actual_specificity, actual_rule), (expected_specificity, expected_rule)) in actual.iter().zip(&expected) {
+                assert_eq!(actual_specificity, expected_specificity, "{:?}", elem);
+                assert!(std::ptr::eq(*actual_rule, *expected_rule), "{:?}", elem);
+            }
+        }
+    }
+}
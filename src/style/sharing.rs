@@ -0,0 +1,135 @@
+// 样式共享缓存：兄弟元素的父元素相同，如果标签名、类名和其它属性也相同（并且都没有 ID），
+// 它们匹配的规则就完全相同，可以直接复用之前计算好的样式，不需要重新层叠和计算
+use std::{collections::VecDeque, ops::AddAssign};
+
+use crate::html::types::ElementData;
+
+// 每组兄弟元素最多缓存的样式数量
+const CACHE_SIZE: usize = 8;
+
+// 缓存的命中和未命中次数，用来评估缓存的效果
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SharingStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl AddAssign for SharingStats {
+    fn add_assign(&mut self, other: SharingStats) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+    }
+}
+
+// 一组兄弟元素共用的缓存，记录已经计算过样式的兄弟元素的位置，最近插入的排在最前面
+// 缓存中只保存位置而不是样式，这样可以先确定哪些元素共享样式，再并行计算其余元素的样式
+#[derive(Default)]
+pub struct SharingCache<'a> {
+    entries: VecDeque<(&'a ElementData, usize)>,
+}
+
+impl<'a> SharingCache<'a> {
+    // 查找可以和 elem 共享样式的兄弟元素，找到时返回它的位置
+    pub fn lookup(&self, elem: &ElementData, stats: &mut SharingStats) -> Option<usize> {
+        let found = can_share(elem)
+            .then(|| self.entries.iter().find(|(candidate, _)| equivalent(candidate, elem)))
+            .flatten();
+        match found {
+            Some(&(_, index)) => {
+                stats.hits += 1;
+                Some(index)
+            }
+            None => {
+                stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, elem: &'a ElementData, index: usize) {
+        if !can_share(elem) {
+            return;
+        }
+        if self.entries.len() == CACHE_SIZE {
+            self.entries.pop_back();
+        }
+        self.entries.push_front((elem, index));
+    }
+}
+
+// 有 ID 的元素可能匹配只属于它自己的规则，不参与共享
+fn can_share(elem: &ElementData) -> bool {
+    elem.id().is_none()
+}
+
+// 类名的顺序和重复不影响匹配，其它属性需要完全相同
+fn equivalent(a: &ElementData, b: &ElementData) -> bool {
+    a.tag_name == b.tag_name && a.classes() == b.classes() && other_attributes(a) == other_attributes(b)
+}
+
+fn other_attributes(elem: &ElementData) -> Vec<(&String, &String)> {
+    let mut attributes: Vec<_> = elem.attributes().iter().filter(|(name, _)| *name != "class").collect();
+    attributes.sort();
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{css::{self, media::MediaEnvironment}, html::{self, types::{AttrMap, ElementData}}, style::{style_tree_with_stats, Traversal}};
+
+    use super::{SharingCache, SharingStats};
+
+    fn element(tag_name: &str, attributes: &[(&str, &str)]) -> ElementData {
+        let attributes: AttrMap = attributes.iter().map(|&(name, value)| (name.to_string(), value.to_string())).collect();
+        ElementData::new(tag_name.to_string(), attributes)
+    }
+
+    // 把 first 放进缓存之后查找 second
+    fn shared(first: &ElementData, second: &ElementData) -> (Option<usize>, SharingStats) {
+        let mut cache = SharingCache::default();
+        let mut stats = SharingStats::default();
+        cache.insert(first, 0);
+        (cache.lookup(second, &mut stats), stats)
+    }
+
+    #[test]
+    fn identical_siblings_are_shared() {
+        let a = element("p", &[("class", "x y"), ("title", "t")]);
+        let b = element("p", &[("class", "y x"), ("title", "t")]);
+        assert_eq!(shared(&a, &b), (Some(0), SharingStats { hits: 1, misses: 0 }));
+    }
+
+    #[test]
+    fn different_siblings_are_not_shared() {
+        let a = element("p", &[("class", "x")]);
+        for b in [
+            element("p", &[("class", "y")]),
+            element("p", &[("class", "x y")]),
+            element("div", &[("class", "x")]),
+            element("p", &[("class", "x"), ("id", "b")]),
+            element("p", &[("class", "x"), ("title", "t")]),
+            element("p", &[("class", "x"), ("style", "color: #f00")]),
+        ] {
+            assert_eq!(shared(&a, &b), (None, SharingStats { hits: 0, misses: 1 }), "{:?}", b);
+        }
+        // 有 ID 的元素不放进缓存
+        let with_id = element("p", &[("class", "x"), ("id", "a")]);
+        assert_eq!(shared(&with_id, &with_id).0, None);
+    }
+
+    #[test]
+    fn hits_and_misses_are_counted() {
+        let root = html::parser::parse(
+            "<div><p class=\"a\">1</p><p class=\"a\">2</p><p class=\"b\">3</p><p class=\"a\">4</p><p id=\"x\" class=\"a\">5</p></div>".to_string(),
+        );
+        let stylesheets = vec![css::parser::parse(".a { color: #ff0000; } #x { color: #0000ff; }".to_string())];
+        let env = MediaEnvironment::new(800.0, 600.0);
+        for traversal in [Traversal::Sequential, Traversal::Parallel] {
+            let (styled, stats) = style_tree_with_stats(&root, &stylesheets, &env, traversal);
+            // 根元素、第一个 .a、.b 和有 ID 的元素没有命中
+            assert_eq!(stats, SharingStats { hits: 2, misses: 4 });
+            assert_eq!(styled.children[1].computed_values, styled.children[0].computed_values);
+            assert_ne!(styled.children[4].computed_values, styled.children[0].computed_values);
+        }
+    }
+}
@@ -0,0 +1,705 @@
+// 强类型的计算样式，布局和绘制阶段直接读取字段，不再通过字符串查找属性
+use crate::css::types::{Calc, CalcType, Color, Separator, Unit, Value};
+
+use super::{computed::MEDIUM_FONT_SIZE, PropertyMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Display {
+    Inline,
+    Block,
+    // 块盒子，内容建立新的块格式化上下文
+    FlowRoot,
+    // 块级和行内级的弹性容器
+    Flex,
+    InlineFlex,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    Static,
+    Relative,
+    Absolute,
+    Fixed,
+    Sticky,
+}
+
+// 浮动盒子靠在包含块的哪一侧
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Float {
+    None,
+    Left,
+    Right,
+}
+
+// 盒子需要放在哪一侧的浮动盒子下面
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clear {
+    None,
+    Left,
+    Right,
+    Both,
+}
+
+// 内容溢出盒子时如何处理，visible 和 clip 以外的值使盒子建立新的块格式化上下文
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Visible,
+    Hidden,
+    Clip,
+    Scroll,
+    Auto,
+}
+
+// 弹性容器的主轴方向
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlexDirection {
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+// 弹性项目是否可以换行，wrap-reverse 时行从交叉轴的末端开始排列
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlexWrap {
+    Nowrap,
+    Wrap,
+    WrapReverse,
+}
+
+// 弹性项目在主轴上的初始尺寸，Size(Auto) 表示使用主轴方向的 width 或 height
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlexBasis {
+    Content,
+    Size(LengthPercentageAuto),
+}
+
+// align-items 和 align-self 的值，normal 在弹性布局中等同于 stretch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlignItems {
+    Stretch,
+    FlexStart,
+    FlexEnd,
+    Start,
+    End,
+    Center,
+    Baseline,
+}
+
+// justify-content 和 align-content 的值，normal 在弹性布局中 justify-content 等同于 flex-start，align-content 等同于 stretch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentDistribution {
+    Normal,
+    Stretch,
+    FlexStart,
+    FlexEnd,
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+// 包含百分比的 calc() 要等到布局时才能计算
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentage {
+    Px(f32),
+    Percent(f32),
+    Calc(Box<Calc>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LengthPercentageAuto {
+    Auto,
+    Px(f32),
+    Percent(f32),
+    Calc(Box<Calc>),
+}
+
+// width、height 等属性指定的是内容区的尺寸还是边框盒的尺寸
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoxSizing {
+    ContentBox,
+    BorderBox,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BorderStyle {
+    None,
+    Hidden,
+    Dotted,
+    Dashed,
+    Solid,
+    Double,
+    Groove,
+    Ridge,
+    Inset,
+    Outset,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineHeight {
+    Normal,
+    Number(f32),
+    Px(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+    Collapse,
+}
+
+// 空白符是否合并，以及是否可以在空白处换行
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhiteSpace {
+    // 合并空白和换行符，可以自动换行
+    Normal,
+    // 合并空白和换行符，不自动换行
+    Nowrap,
+    // 保留空白和换行符，不自动换行
+    Pre,
+    // 保留空白和换行符，可以自动换行
+    PreWrap,
+}
+
+// 行内方向，决定了双向文本中段落和嵌入的基础方向
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+// 元素如何参与双向文本的重新排序
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnicodeBidi {
+    Normal,
+    // 开始一个新的嵌入级别
+    Embed,
+    // 内容和外面的文字互不影响
+    Isolate,
+    // 内容中的字符全部按照 direction 的方向排列
+    BidiOverride,
+    IsolateOverride,
+    // 方向由内容中第一个强方向字符决定
+    Plaintext,
+}
+
+// 上、右、下、左四条边各自的值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sides<T> {
+    pub top: T,
+    pub right: T,
+    pub bottom: T,
+    pub left: T,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedStyle {
+    pub display: Display,
+    pub position: Position,
+    // 定位元素的 top、right、bottom、left
+    pub inset: Sides<LengthPercentageAuto>,
+    pub float: Float,
+    pub clear: Clear,
+    pub overflow: Overflow,
+    pub width: LengthPercentageAuto,
+    pub height: LengthPercentageAuto,
+    // auto 在弹性项目中根据内容计算，其他盒子中为 0
+    pub min_width: LengthPercentageAuto,
+    pub min_height: LengthPercentageAuto,
+    // None 表示没有限制
+    pub max_width: Option<LengthPercentage>,
+    pub max_height: Option<LengthPercentage>,
+    pub box_sizing: BoxSizing,
+    pub flex_direction: FlexDirection,
+    pub flex_wrap: FlexWrap,
+    pub flex_grow: f32,
+    pub flex_shrink: f32,
+    pub flex_basis: FlexBasis,
+    pub order: i32,
+    pub justify_content: ContentDistribution,
+    pub align_items: AlignItems,
+    // None 表示 auto，使用父元素的 align-items
+    pub align_self: Option<AlignItems>,
+    pub align_content: ContentDistribution,
+    pub row_gap: LengthPercentage,
+    pub column_gap: LengthPercentage,
+    pub margin: Sides<LengthPercentageAuto>,
+    pub padding: Sides<LengthPercentage>,
+    pub border_width: Sides<f32>,
+    pub border_style: Sides<BorderStyle>,
+    pub border_color: Sides<Color>,
+    pub background_color: Color,
+    pub color: Color,
+    pub font_family: Vec<String>,
+    pub font_size: f32,
+    pub font_style: FontStyle,
+    pub font_weight: f32,
+    pub line_height: LineHeight,
+    pub visibility: Visibility,
+    pub white_space: WhiteSpace,
+    pub direction: Direction,
+    pub unicode_bidi: UnicodeBidi,
+}
+
+pub const TRANSPARENT: Color = Color { r: 0, g: 0, b: 0, a: 0 };
+
+const BLACK: Color = Color { r: 0, g: 0, b: 0, a: 255 };
+
+impl Default for ComputedStyle {
+    // 所有属性都取初始值
+    fn default() -> ComputedStyle {
+        ComputedStyle {
+            display: Display::Inline,
+            position: Position::Static,
+            inset: Sides::all(LengthPercentageAuto::Auto),
+            float: Float::None,
+            clear: Clear::None,
+            overflow: Overflow::Visible,
+            width: LengthPercentageAuto::Auto,
+            height: LengthPercentageAuto::Auto,
+            min_width: LengthPercentageAuto::Auto,
+            min_height: LengthPercentageAuto::Auto,
+            max_width: None,
+            max_height: None,
+            box_sizing: BoxSizing::ContentBox,
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Nowrap,
+            flex_grow: 0.0,
+            flex_shrink: 1.0,
+            flex_basis: FlexBasis::Size(LengthPercentageAuto::Auto),
+            order: 0,
+            justify_content: ContentDistribution::Normal,
+            align_items: AlignItems::Stretch,
+            align_self: None,
+            align_content: ContentDistribution::Normal,
+            row_gap: LengthPercentage::Px(0.0),
+            column_gap: LengthPercentage::Px(0.0),
+            margin: Sides::all(LengthPercentageAuto::Px(0.0)),
+            padding: Sides::all(LengthPercentage::Px(0.0)),
+            border_width: Sides::all(0.0),
+            border_style: Sides::all(BorderStyle::None),
+            border_color: Sides::all(BLACK),
+            background_color: TRANSPARENT,
+            color: BLACK,
+            font_family: vec!["serif".to_string()],
+            font_size: MEDIUM_FONT_SIZE,
+            font_style: FontStyle::Normal,
+            font_weight: 400.0,
+            line_height: LineHeight::Normal,
+            visibility: Visibility::Visible,
+            white_space: WhiteSpace::Normal,
+            direction: Direction::Ltr,
+            unicode_bidi: UnicodeBidi::Normal,
+        }
+    }
+}
+
+impl<T: Clone> Sides<T> {
+    pub fn all(value: T) -> Sides<T> {
+        Sides { top: value.clone(), right: value.clone(), bottom: value.clone(), left: value }
+    }
+}
+
+impl ComputedStyle {
+    // 从计算值中读取每个属性，缺失或者类型不对的属性使用初始值
+    pub fn from_values(values: &PropertyMap) -> ComputedStyle {
+        let initial = ComputedStyle::default();
+        let get = |name: &str| values.get(name);
+
+        ComputedStyle {
+            display: get("display").and_then(display).unwrap_or(initial.display),
+            position: get("position").and_then(position).unwrap_or(initial.position),
+            inset: sides(values, |side| side.to_string(), length_percentage_auto, initial.inset),
+            float: get("float").and_then(float).unwrap_or(initial.float),
+            clear: get("clear").and_then(clear).unwrap_or(initial.clear),
+            overflow: get("overflow").and_then(overflow).unwrap_or(initial.overflow),
+            width: get("width").and_then(length_percentage_auto).unwrap_or(initial.width),
+            height: get("height").and_then(length_percentage_auto).unwrap_or(initial.height),
+            min_width: get("min-width").and_then(length_percentage_auto).unwrap_or(initial.min_width),
+            min_height: get("min-height").and_then(length_percentage_auto).unwrap_or(initial.min_height),
+            max_width: get("max-width").and_then(max_size).unwrap_or(initial.max_width),
+            max_height: get("max-height").and_then(max_size).unwrap_or(initial.max_height),
+            box_sizing: get("box-sizing").and_then(box_sizing).unwrap_or(initial.box_sizing),
+            flex_direction: get("flex-direction").and_then(flex_direction).unwrap_or(initial.flex_direction),
+            flex_wrap: get("flex-wrap").and_then(flex_wrap).unwrap_or(initial.flex_wrap),
+            flex_grow: get("flex-grow").and_then(number).unwrap_or(initial.flex_grow),
+            flex_shrink: get("flex-shrink").and_then(number).unwrap_or(initial.flex_shrink),
+            flex_basis: get("flex-basis").and_then(flex_basis).unwrap_or(initial.flex_basis),
+            order: get("order").and_then(number).map_or(initial.order, |order| order as i32),
+            justify_content: get("justify-content").and_then(content_distribution).unwrap_or(initial.justify_content),
+            align_items: get("align-items").and_then(align_items).unwrap_or(initial.align_items),
+            align_self: get("align-self").and_then(align_self).unwrap_or(initial.align_self),
+            align_content: get("align-content").and_then(content_distribution).unwrap_or(initial.align_content),
+            row_gap: get("row-gap").and_then(gap).unwrap_or(initial.row_gap),
+            column_gap: get("column-gap").and_then(gap).unwrap_or(initial.column_gap),
+            margin: sides(values, |side| format!("margin-{}", side), length_percentage_auto, initial.margin),
+            padding: sides(values, |side| format!("padding-{}", side), length_percentage, initial.padding),
+            border_width: sides(values, |side| format!("border-{}-width", side), px, initial.border_width),
+            border_style: sides(values, |side| format!("border-{}-style", side), border_style, initial.border_style),
+            border_color: sides(values, |side| format!("border-{}-color", side), color, initial.border_color),
+            background_color: get("background-color").and_then(color).unwrap_or(initial.background_color),
+            color: get("color").and_then(color).unwrap_or(initial.color),
+            font_family: get("font-family").and_then(font_family).unwrap_or(initial.font_family),
+            font_size: get("font-size").and_then(px).unwrap_or(initial.font_size),
+            font_style: get("font-style").and_then(font_style).unwrap_or(initial.font_style),
+            font_weight: get("font-weight").and_then(number).unwrap_or(initial.font_weight),
+            line_height: get("line-height").and_then(line_height).unwrap_or(initial.line_height),
+            visibility: get("visibility").and_then(visibility).unwrap_or(initial.visibility),
+            white_space: get("white-space").and_then(white_space).unwrap_or(initial.white_space),
+            direction: get("direction").and_then(direction).unwrap_or(initial.direction),
+            unicode_bidi: get("unicode-bidi").and_then(unicode_bidi).unwrap_or(initial.unicode_bidi),
+        }
+    }
+}
+
+// 读取四条边对应的属性，例如：margin-top、margin-right、margin-bottom、margin-left
+fn sides<T, F>(values: &PropertyMap, name: F, parse: fn(&Value) -> Option<T>, initial: Sides<T>) -> Sides<T>
+    where F: Fn(&str) -> String {
+    let side = |side: &str, initial: T| values.get(&name(side)).and_then(parse).unwrap_or(initial);
+    Sides {
+        top: side("top", initial.top),
+        right: side("right", initial.right),
+        bottom: side("bottom", initial.bottom),
+        left: side("left", initial.left),
+    }
+}
+
+impl LengthPercentage {
+    // 百分比需要知道包含块的尺寸，不知道时按 0 处理
+    pub fn to_px(&self) -> f32 {
+        match self {
+            LengthPercentage::Px(f) => *f,
+            LengthPercentage::Percent(_) => 0.0,
+            LengthPercentage::Calc(calc) => calc.evaluate(None).unwrap_or(0.0),
+        }
+    }
+
+    // 百分比和 calc() 相对于 basis 计算
+    pub fn resolve(&self, basis: f32) -> f32 {
+        match self {
+            LengthPercentage::Px(f) => *f,
+            LengthPercentage::Percent(p) => basis * p / 100.0,
+            LengthPercentage::Calc(calc) => calc.evaluate(Some(basis)).unwrap_or(0.0),
+        }
+    }
+
+    // basis 为 None 时包含百分比的值无法计算，返回 None
+    pub fn try_resolve(&self, basis: Option<f32>) -> Option<f32> {
+        match self {
+            LengthPercentage::Px(f) => Some(*f),
+            LengthPercentage::Percent(p) => basis.map(|basis| basis * p / 100.0),
+            LengthPercentage::Calc(calc) => calc.evaluate(basis),
+        }
+    }
+}
+
+impl LengthPercentageAuto {
+    // auto 和百分比需要在布局时单独处理，这里按 0 处理
+    pub fn to_px(&self) -> f32 {
+        match self {
+            LengthPercentageAuto::Px(f) => *f,
+            LengthPercentageAuto::Auto | LengthPercentageAuto::Percent(_) => 0.0,
+            LengthPercentageAuto::Calc(calc) => calc.evaluate(None).unwrap_or(0.0),
+        }
+    }
+
+    // 把百分比和 calc() 相对于 basis 计算成 px，basis 为 None 时包含百分比的值视为 auto
+    pub fn resolve(&self, basis: Option<f32>) -> LengthPercentageAuto {
+        let px = match self {
+            LengthPercentageAuto::Auto => None,
+            LengthPercentageAuto::Px(f) => Some(*f),
+            LengthPercentageAuto::Percent(p) => basis.map(|basis| basis * p / 100.0),
+            LengthPercentageAuto::Calc(calc) => calc.evaluate(basis),
+        };
+        px.map_or(LengthPercentageAuto::Auto, LengthPercentageAuto::Px)
+    }
+}
+
+fn keyword(value: &Value) -> Option<&str> {
+    match value {
+        Value::Keyword(k) => Some(k),
+        _ => None
+    }
+}
+
+fn px(value: &Value) -> Option<f32> {
+    match *value {
+        Value::Length(f, Unit::Px) => Some(f),
+        Value::Number(0.0) => Some(0.0),
+        _ => None
+    }
+}
+
+fn number(value: &Value) -> Option<f32> {
+    match *value {
+        Value::Number(f) => Some(f),
+        _ => None
+    }
+}
+
+fn length_percentage(value: &Value) -> Option<LengthPercentage> {
+    match *value {
+        Value::Length(f, Unit::Percent) => Some(LengthPercentage::Percent(f)),
+        Value::Calc(ref calc) if calc.check_type() == Ok(CalcType::Length) => Some(LengthPercentage::Calc(Box::new(calc.clone()))),
+        _ => px(value).map(LengthPercentage::Px)
+    }
+}
+
+fn length_percentage_auto(value: &Value) -> Option<LengthPercentageAuto> {
+    match *value {
+        Value::Keyword(ref k) if k == "auto" => Some(LengthPercentageAuto::Auto),
+        _ => length_percentage(value).map(|v| match v {
+            LengthPercentage::Px(f) => LengthPercentageAuto::Px(f),
+            LengthPercentage::Percent(f) => LengthPercentageAuto::Percent(f),
+            LengthPercentage::Calc(calc) => LengthPercentageAuto::Calc(calc),
+        })
+    }
+}
+
+// max-width、max-height 的值，none 表示没有限制
+fn max_size(value: &Value) -> Option<Option<LengthPercentage>> {
+    match *value {
+        Value::Keyword(ref k) if k == "none" => Some(None),
+        _ => length_percentage(value).map(Some)
+    }
+}
+
+fn color(value: &Value) -> Option<Color> {
+    match value {
+        Value::ColorValue(c) => Some(*c),
+        Value::Keyword(k) if k == "transparent" => Some(TRANSPARENT),
+        _ => None
+    }
+}
+
+fn display(value: &Value) -> Option<Display> {
+    match keyword(value)? {
+        "block" => Some(Display::Block),
+        "inline" => Some(Display::Inline),
+        "flow-root" => Some(Display::FlowRoot),
+        "flex" => Some(Display::Flex),
+        "inline-flex" => Some(Display::InlineFlex),
+        "none" => Some(Display::None),
+        _ => None
+    }
+}
+
+fn position(value: &Value) -> Option<Position> {
+    match keyword(value)? {
+        "static" => Some(Position::Static),
+        "relative" => Some(Position::Relative),
+        "absolute" => Some(Position::Absolute),
+        "fixed" => Some(Position::Fixed),
+        "sticky" => Some(Position::Sticky),
+        _ => None
+    }
+}
+
+fn float(value: &Value) -> Option<Float> {
+    match keyword(value)? {
+        "none" => Some(Float::None),
+        "left" => Some(Float::Left),
+        "right" => Some(Float::Right),
+        _ => None
+    }
+}
+
+fn clear(value: &Value) -> Option<Clear> {
+    match keyword(value)? {
+        "none" => Some(Clear::None),
+        "left" => Some(Clear::Left),
+        "right" => Some(Clear::Right),
+        "both" => Some(Clear::Both),
+        _ => None
+    }
+}
+
+fn overflow(value: &Value) -> Option<Overflow> {
+    match keyword(value)? {
+        "visible" => Some(Overflow::Visible),
+        "hidden" => Some(Overflow::Hidden),
+        "clip" => Some(Overflow::Clip),
+        "scroll" => Some(Overflow::Scroll),
+        "auto" => Some(Overflow::Auto),
+        _ => None
+    }
+}
+
+fn flex_direction(value: &Value) -> Option<FlexDirection> {
+    match keyword(value)? {
+        "row" => Some(FlexDirection::Row),
+        "row-reverse" => Some(FlexDirection::RowReverse),
+        "column" => Some(FlexDirection::Column),
+        "column-reverse" => Some(FlexDirection::ColumnReverse),
+        _ => None
+    }
+}
+
+fn flex_wrap(value: &Value) -> Option<FlexWrap> {
+    match keyword(value)? {
+        "nowrap" => Some(FlexWrap::Nowrap),
+        "wrap" => Some(FlexWrap::Wrap),
+        "wrap-reverse" => Some(FlexWrap::WrapReverse),
+        _ => None
+    }
+}
+
+fn flex_basis(value: &Value) -> Option<FlexBasis> {
+    match *value {
+        Value::Keyword(ref k) if k == "content" => Some(FlexBasis::Content),
+        _ => length_percentage_auto(value).map(FlexBasis::Size)
+    }
+}
+
+fn align_items(value: &Value) -> Option<AlignItems> {
+    match keyword(value)? {
+        "normal" | "stretch" => Some(AlignItems::Stretch),
+        "flex-start" => Some(AlignItems::FlexStart),
+        "flex-end" => Some(AlignItems::FlexEnd),
+        "start" => Some(AlignItems::Start),
+        "end" => Some(AlignItems::End),
+        "center" => Some(AlignItems::Center),
+        "baseline" => Some(AlignItems::Baseline),
+        _ => None
+    }
+}
+
+// align-self 的值，auto 为 Some(None)
+fn align_self(value: &Value) -> Option<Option<AlignItems>> {
+    match *value {
+        Value::Keyword(ref k) if k == "auto" => Some(None),
+        _ => align_items(value).map(Some)
+    }
+}
+
+fn content_distribution(value: &Value) -> Option<ContentDistribution> {
+    match keyword(value)? {
+        "normal" => Some(ContentDistribution::Normal),
+        "stretch" => Some(ContentDistribution::Stretch),
+        "flex-start" => Some(ContentDistribution::FlexStart),
+        "flex-end" => Some(ContentDistribution::FlexEnd),
+        "start" => Some(ContentDistribution::Start),
+        "end" => Some(ContentDistribution::End),
+        "center" => Some(ContentDistribution::Center),
+        "space-between" => Some(ContentDistribution::SpaceBetween),
+        "space-around" => Some(ContentDistribution::SpaceAround),
+        "space-evenly" => Some(ContentDistribution::SpaceEvenly),
+        _ => None
+    }
+}
+
+// row-gap、column-gap 的 normal 在弹性布局中为 0
+fn gap(value: &Value) -> Option<LengthPercentage> {
+    match *value {
+        Value::Keyword(ref k) if k == "normal" => Some(LengthPercentage::Px(0.0)),
+        _ => length_percentage(value)
+    }
+}
+
+fn box_sizing(value: &Value) -> Option<BoxSizing> {
+    match keyword(value)? {
+        "content-box" => Some(BoxSizing::ContentBox),
+        "border-box" => Some(BoxSizing::BorderBox),
+        _ => None
+    }
+}
+
+fn border_style(value: &Value) -> Option<BorderStyle> {
+    match keyword(value)? {
+        "none" => Some(BorderStyle::None),
+        "hidden" => Some(BorderStyle::Hidden),
+        "dotted" => Some(BorderStyle::Dotted),
+        "dashed" => Some(BorderStyle::Dashed),
+        "solid" => Some(BorderStyle::Solid),
+        "double" => Some(BorderStyle::Double),
+        "groove" => Some(BorderStyle::Groove),
+        "ridge" => Some(BorderStyle::Ridge),
+        "inset" => Some(BorderStyle::Inset),
+        "outset" => Some(BorderStyle::Outset),
+        _ => None
+    }
+}
+
+fn font_style(value: &Value) -> Option<FontStyle> {
+    match keyword(value)? {
+        "normal" => Some(FontStyle::Normal),
+        "italic" => Some(FontStyle::Italic),
+        "oblique" => Some(FontStyle::Oblique),
+        _ => None
+    }
+}
+
+fn line_height(value: &Value) -> Option<LineHeight> {
+    match *value {
+        Value::Keyword(ref k) if k == "normal" => Some(LineHeight::Normal),
+        Value::Number(f) => Some(LineHeight::Number(f)),
+        _ => px(value).map(LineHeight::Px)
+    }
+}
+
+fn visibility(value: &Value) -> Option<Visibility> {
+    match keyword(value)? {
+        "visible" => Some(Visibility::Visible),
+        "hidden" => Some(Visibility::Hidden),
+        "collapse" => Some(Visibility::Collapse),
+        _ => None
+    }
+}
+
+fn white_space(value: &Value) -> Option<WhiteSpace> {
+    match keyword(value)? {
+        "normal" => Some(WhiteSpace::Normal),
+        "nowrap" => Some(WhiteSpace::Nowrap),
+        "pre" => Some(WhiteSpace::Pre),
+        "pre-wrap" => Some(WhiteSpace::PreWrap),
+        _ => None
+    }
+}
+
+fn direction(value: &Value) -> Option<Direction> {
+    match keyword(value)? {
+        "ltr" => Some(Direction::Ltr),
+        "rtl" => Some(Direction::Rtl),
+        _ => None
+    }
+}
+
+fn unicode_bidi(value: &Value) -> Option<UnicodeBidi> {
+    match keyword(value)? {
+        "normal" => Some(UnicodeBidi::Normal),
+        "embed" => Some(UnicodeBidi::Embed),
+        "isolate" => Some(UnicodeBidi::Isolate),
+        "bidi-override" => Some(UnicodeBidi::BidiOverride),
+        "isolate-override" => Some(UnicodeBidi::IsolateOverride),
+        "plaintext" => Some(UnicodeBidi::Plaintext),
+        _ => None
+    }
+}
+
+// 字体名称列表，例如：`"Helvetica Neue", Arial, sans-serif`
+fn font_family(value: &Value) -> Option<Vec<String>> {
+    let family_name = |value: &Value| match value {
+        Value::Keyword(k) | Value::Str(k) => Some(k.clone()),
+        // 没有引号的多个单词组成一个名称，例如：Times New Roman
+        Value::List(words, Separator::Space) => {
+            words.iter().map(keyword).collect::<Option<Vec<_>>>().map(|words| words.join(" "))
+        }
+        _ => None
+    };
+    match value {
+        Value::List(families, Separator::Comma) => families.iter().map(family_name).collect(),
+        _ => family_name(value).map(|family| vec![family])
+    }
+}
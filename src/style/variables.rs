@@ -0,0 +1,145 @@
+// 自定义属性（--*）的继承和 var() 替换
+use std::collections::{HashMap, HashSet};
+
+use crate::css::{parser, properties, shorthand, types::{UnparsedValue, Value}};
+
+use super::PropertyMap;
+
+// 自定义属性名称到它的值（替换过 var() 的原始文本）
+pub type Variables = HashMap<String, String>;
+
+// 计算元素的自定义属性：从父元素继承，再用自己声明的值覆盖
+// 形成循环引用的自定义属性在计算值时无效，和没有声明一样
+pub fn compute(specified: &PropertyMap, parent: Option<&PropertyMap>) -> Variables {
+    let mut raw: Variables = parent.into_iter()
+        .flat_map(|parent| parent.iter())
+        .chain(specified.iter())
+        .filter_map(|(name, value)| match value {
+            Value::Unparsed(UnparsedValue { css, .. }) if properties::is_custom(name) => Some((name.clone(), css.clone())),
+            _ => None
+        })
+        .collect();
+
+    // 自定义属性的值总是保留为原始文本，全局关键字也要从文本中识别
+    // 声明为 initial 的值等同于没有声明（guaranteed-invalid），自定义属性总是继承，inherit、unset 和 revert 都使用父元素的值
+    for (name, value) in specified.iter().filter(|(name, _)| properties::is_custom(name)) {
+        let Value::Unparsed(UnparsedValue { css, .. }) = value else { continue };
+        match &*css.trim().to_ascii_lowercase() {
+            "initial" => { raw.remove(name); }
+            "inherit" | "unset" | "revert" => match parent.and_then(|p| p.get(name)) {
+                Some(Value::Unparsed(UnparsedValue { css, .. })) => { raw.insert(name.clone(), css.clone()); }
+                _ => { raw.remove(name); }
+            },
+            _ => {}
+        }
+    }
+
+    let mut resolver = Resolver { raw: &raw, resolved: HashMap::new(), stack: Vec::new(), cyclic: HashSet::new() };
+    raw.keys()
+        .filter_map(|name| resolver.resolve(name).map(|value| (name.clone(), value)))
+        .collect()
+}
+
+// 替换普通属性中的 var() 并重新解析，替换失败或者结果不合法时返回 None（计算值时无效）
+pub fn substitute_value(name: &str, value: &UnparsedValue, variables: &Variables) -> Option<Value> {
+    let css = substitute(&value.css, &mut |variable| variables.get(variable).cloned())?;
+    let parsed = parser::parse_value(css)?;
+    let value = match value.shorthand {
+        // 由简写属性展开的长属性，需要先展开简写属性再取出自己的值
+        Some(ref shorthand) => shorthand::expand(shorthand, parsed)?
+            .into_iter()
+            .find(|declaration| declaration.name == name)
+            .map(|declaration| declaration.value)?,
+        None => parsed
+    };
+    properties::is_valid(name, &value).then_some(value)
+}
+
+// 解析自定义属性之间的引用，并检测循环引用
+struct Resolver<'a> {
+    raw: &'a Variables,
+    resolved: HashMap<String, Option<String>>,
+    // 正在解析的自定义属性，用来检测循环
+    stack: Vec<String>,
+    cyclic: HashSet<String>,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve(&mut self, name: &str) -> Option<String> {
+        if let Some(value) = self.resolved.get(name) {
+            return value.clone();
+        }
+        // 出现在解析栈上说明形成了循环，循环中的所有属性都无效
+        if let Some(start) = self.stack.iter().position(|n| n == name) {
+            self.cyclic.extend(self.stack[start..].iter().cloned());
+            return None;
+        }
+        let css = self.raw.get(name)?.clone();
+
+        self.stack.push(name.to_string());
+        let value = substitute(&css, &mut |variable| self.resolve(variable));
+        self.stack.pop();
+
+        let value = value.filter(|_| !self.cyclic.contains(name));
+        self.resolved.insert(name.to_string(), value.clone());
+        value
+    }
+}
+
+// 把 css 中的 var(--name, fallback) 替换为 lookup 返回的值
+// 引用的自定义属性无效并且没有后备值时返回 None
+fn substitute(css: &str, lookup: &mut dyn FnMut(&str) -> Option<String>) -> Option<String> {
+    let mut result = String::new();
+    let mut rest = css;
+    while let Some(start) = find_var(rest) {
+        result.push_str(&rest[..start]);
+        let args_start = start + "var(".len();
+        let args_end = args_start + matching_paren(&rest[args_start..])?;
+        let args = &rest[args_start..args_end];
+
+        let (name, fallback) = match args.find(',') {
+            Some(comma) => (args[..comma].trim(), Some(args[comma + 1..].trim())),
+            None => (args.trim(), None)
+        };
+        if !properties::is_custom(name) {
+            return None;
+        }
+        let value = match lookup(name) {
+            Some(value) => value,
+            None => substitute(fallback?, lookup)?
+        };
+        result.push_str(&value);
+        rest = &rest[args_end + 1..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+// 查找字符串之外的第一个 var(
+fn find_var(css: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in css.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, 'v' | 'V') if css[i..].get(..4).is_some_and(|s| s.eq_ignore_ascii_case("var(")) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+// 找到与前面的左括号匹配的右括号的位置
+fn matching_paren(css: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in css.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,609 @@
+// Unicode 双向算法（UAX #9）：计算段落中每个字符的嵌入级别，以及把一行中的内容按照级别排列为显示顺序
+use std::cmp::Ordering;
+
+use self::BidiClass::*;
+
+// 嵌入级别，奇数为从右到左
+pub type Level = u8;
+
+// 显式嵌入的最大深度
+const MAX_DEPTH: Level = 125;
+// 括号配对时最多同时打开的括号数
+const MAX_BRACKETS: usize = 63;
+
+// 字符的双向类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BidiClass {
+    // 强类型：从左到右、从右到左、阿拉伯字母
+    L, R, AL,
+    // 弱类型：欧洲数字、数字中的正负号、数字前后的货币符号等、阿拉伯数字、数字中的分隔符、组合字符、格式字符
+    EN, ES, ET, AN, CS, NSM, BN,
+    // 中性：段落分隔符、制表符、空白、其他符号
+    B, S, WS, ON,
+    // 显式格式字符
+    LRE, LRO, RLE, RLO, PDF, LRI, RLI, FSI, PDI,
+}
+
+// 常用文字的双向类型，按照编码排序，表中没有的字符为 L
+const CLASSES: &[(u32, u32, BidiClass)] = &[
+    (0x0000, 0x0008, BN), (0x0009, 0x0009, S), (0x000A, 0x000A, B), (0x000B, 0x000B, S), (0x000C, 0x000C, WS),
+    (0x000D, 0x000D, B), (0x000E, 0x001B, BN), (0x001C, 0x001E, B), (0x001F, 0x001F, S), (0x0020, 0x0020, WS),
+    (0x0021, 0x0022, ON), (0x0023, 0x0025, ET), (0x0026, 0x002A, ON), (0x002B, 0x002B, ES), (0x002C, 0x002C, CS),
+    (0x002D, 0x002D, ES), (0x002E, 0x002F, CS), (0x0030, 0x0039, EN), (0x003A, 0x003A, CS), (0x003B, 0x0040, ON),
+    (0x005B, 0x0060, ON), (0x007B, 0x007E, ON), (0x007F, 0x0084, BN), (0x0085, 0x0085, B), (0x0086, 0x009F, BN),
+    (0x00A0, 0x00A0, CS), (0x00A1, 0x00A1, ON), (0x00A2, 0x00A5, ET), (0x00A6, 0x00A9, ON), (0x00AB, 0x00AC, ON),
+    (0x00AD, 0x00AD, BN), (0x00AE, 0x00AF, ON), (0x00B0, 0x00B1, ET), (0x00B2, 0x00B3, EN), (0x00B4, 0x00B4, ON),
+    (0x00B6, 0x00B8, ON), (0x00B9, 0x00B9, EN), (0x00BB, 0x00BF, ON), (0x00D7, 0x00D7, ON), (0x00F7, 0x00F7, ON),
+    (0x0300, 0x036F, NSM), (0x0483, 0x0489, NSM), (0x058A, 0x058A, ON),
+    // 希伯来字母
+    (0x0591, 0x05BD, NSM), (0x05BE, 0x05BE, R), (0x05BF, 0x05BF, NSM), (0x05C0, 0x05C0, R), (0x05C1, 0x05C2, NSM),
+    (0x05C3, 0x05C3, R), (0x05C4, 0x05C5, NSM), (0x05C6, 0x05C6, R), (0x05C7, 0x05C7, NSM), (0x05C8, 0x05FF, R),
+    // 阿拉伯字母
+    (0x0600, 0x0605, AN), (0x0606, 0x0607, ON), (0x0608, 0x0608, AL), (0x0609, 0x060A, ET), (0x060B, 0x060B, AL),
+    (0x060C, 0x060C, CS), (0x060D, 0x060D, AL), (0x060E, 0x060F, ON), (0x0610, 0x061A, NSM), (0x061B, 0x064A, AL),
+    (0x064B, 0x065F, NSM), (0x0660, 0x0669, AN), (0x066A, 0x066A, ET), (0x066B, 0x066C, AN), (0x066D, 0x066F, AL),
+    (0x0670, 0x0670, NSM), (0x0671, 0x06D5, AL), (0x06D6, 0x06DC, NSM), (0x06DD, 0x06DD, AN), (0x06DE, 0x06DE, ON),
+    (0x06DF, 0x06E4, NSM), (0x06E5, 0x06E6, AL), (0x06E7, 0x06E8, NSM), (0x06E9, 0x06E9, ON), (0x06EA, 0x06ED, NSM),
+    (0x06EE, 0x06EF, AL), (0x06F0, 0x06F9, EN), (0x06FA, 0x07BF, AL), (0x07C0, 0x085F, R), (0x0860, 0x08FF, AL),
+    // 标点和格式字符
+    (0x2000, 0x200A, WS), (0x200B, 0x200D, BN), (0x200F, 0x200F, R), (0x2010, 0x2027, ON), (0x2028, 0x2028, WS),
+    (0x2029, 0x2029, B), (0x202A, 0x202A, LRE), (0x202B, 0x202B, RLE), (0x202C, 0x202C, PDF), (0x202D, 0x202D, LRO),
+    (0x202E, 0x202E, RLO), (0x202F, 0x202F, CS), (0x2030, 0x2034, ET), (0x2035, 0x2043, ON), (0x2044, 0x2044, CS),
+    (0x2045, 0x205E, ON), (0x205F, 0x205F, WS), (0x2060, 0x2065, BN), (0x2066, 0x2066, LRI), (0x2067, 0x2067, RLI),
+    (0x2068, 0x2068, FSI), (0x2069, 0x2069, PDI), (0x206A, 0x206F, BN), (0x2070, 0x2070, EN), (0x2074, 0x2079, EN),
+    (0x207A, 0x207B, ES), (0x207C, 0x207E, ON), (0x2080, 0x2089, EN), (0x208A, 0x208B, ES), (0x208C, 0x208E, ON),
+    (0x20A0, 0x20CF, ET),
+    // 符号
+    (0x2190, 0x2211, ON), (0x2212, 0x2212, ES), (0x2213, 0x2213, ET), (0x2214, 0x2335, ON), (0x2460, 0x2487, ON),
+    (0x2488, 0x249B, EN), (0x2500, 0x27FF, ON), (0x2900, 0x2BFF, ON), (0x2E00, 0x2E7F, ON),
+    // 中日韩标点
+    (0x3000, 0x3000, WS), (0x3001, 0x3004, ON), (0x3008, 0x3020, ON), (0x3030, 0x3030, ON), (0x303D, 0x303F, ON),
+    (0x309B, 0x309C, ON), (0x30A0, 0x30A0, ON), (0x30FB, 0x30FB, ON),
+    // 表现形式
+    (0xFB1D, 0xFB1D, R), (0xFB1E, 0xFB1E, NSM), (0xFB1F, 0xFB28, R), (0xFB29, 0xFB29, ES), (0xFB2A, 0xFB4F, R),
+    (0xFB50, 0xFD3D, AL), (0xFD3E, 0xFD3F, ON), (0xFD40, 0xFDFF, AL), (0xFE00, 0xFE0F, NSM), (0xFE10, 0xFE19, ON),
+    (0xFE20, 0xFE2F, NSM), (0xFE30, 0xFE4F, ON), (0xFE50, 0xFE50, CS), (0xFE51, 0xFE51, ON), (0xFE52, 0xFE52, CS),
+    (0xFE54, 0xFE54, ON), (0xFE55, 0xFE55, CS), (0xFE56, 0xFE5E, ON), (0xFE5F, 0xFE5F, ET), (0xFE60, 0xFE61, ON),
+    (0xFE62, 0xFE63, ES), (0xFE64, 0xFE66, ON), (0xFE68, 0xFE68, ON), (0xFE69, 0xFE6A, ET), (0xFE6B, 0xFE6B, ON),
+    (0xFE70, 0xFEFE, AL), (0xFEFF, 0xFEFF, BN),
+    // 全角字符
+    (0xFF01, 0xFF02, ON), (0xFF03, 0xFF05, ET), (0xFF06, 0xFF0A, ON), (0xFF0B, 0xFF0B, ES), (0xFF0C, 0xFF0C, CS),
+    (0xFF0D, 0xFF0D, ES), (0xFF0E, 0xFF0F, CS), (0xFF10, 0xFF19, EN), (0xFF1A, 0xFF1A, CS), (0xFF1B, 0xFF20, ON),
+    (0xFF3B, 0xFF40, ON), (0xFF5B, 0xFF65, ON), (0xFFE0, 0xFFE1, ET), (0xFFE2, 0xFFE4, ON), (0xFFE5, 0xFFE6, ET),
+    (0xFFE8, 0xFFEE, ON),
+    // 其他从右到左的文字
+    (0x10800, 0x10FFF, R), (0x1E800, 0x1EFFF, R),
+];
+
+// 成对的括号，用于括号配对规则和镜像
+const BRACKETS: &[(char, char)] = &[
+    ('(', ')'), ('[', ']'), ('{', '}'), ('\u{2045}', '\u{2046}'), ('\u{207D}', '\u{207E}'), ('\u{208D}', '\u{208E}'),
+    ('\u{2329}', '\u{232A}'), ('\u{3008}', '\u{3009}'), ('\u{300A}', '\u{300B}'), ('\u{300C}', '\u{300D}'),
+    ('\u{300E}', '\u{300F}'), ('\u{3010}', '\u{3011}'), ('\u{3014}', '\u{3015}'), ('\u{3016}', '\u{3017}'),
+    ('\u{3018}', '\u{3019}'), ('\u{301A}', '\u{301B}'), ('\u{FF08}', '\u{FF09}'), ('\u{FF3B}', '\u{FF3D}'),
+    ('\u{FF5B}', '\u{FF5D}'),
+];
+
+// 从右到左显示时需要镜像的其他字符
+const MIRRORED: &[(char, char)] = &[('<', '>'), ('\u{AB}', '\u{BB}'), ('\u{2039}', '\u{203A}'), ('\u{2264}', '\u{2265}')];
+
+pub fn bidi_class(c: char) -> BidiClass {
+    let code = c as u32;
+    let found = CLASSES.binary_search_by(|&(start, end, _)| {
+        if end < code {
+            Ordering::Less
+        } else if start > code {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    });
+    match found {
+        Ok(index) => CLASSES[index].2,
+        Err(_) => L,
+    }
+}
+
+pub fn is_rtl(level: Level) -> bool {
+    level % 2 == 1
+}
+
+// 从右到左显示时的镜像字符，例如 ( 显示为 )
+pub fn mirror(c: char) -> char {
+    BRACKETS.iter().chain(MIRRORED).find_map(|&(open, close)| {
+        if c == open {
+            Some(close)
+        } else if c == close {
+            Some(open)
+        } else {
+            None
+        }
+    }).unwrap_or(c)
+}
+
+// 计算每个字符的嵌入级别，text 中可以包含多个以段落分隔符分开的段落
+// base 为段落的基础级别，为 None 时由每个段落中第一个强类型字符决定（P2、P3）
+pub fn levels(text: &[char], base: Option<Level>) -> Vec<Level> {
+    let classes: Vec<BidiClass> = text.iter().map(|&c| bidi_class(c)).collect();
+    let mut levels = Vec::with_capacity(text.len());
+    let mut start = 0;
+    while start < text.len() {
+        let end = classes[start..].iter().position(|&class| class == B).map_or(text.len(), |i| start + i + 1);
+        let level = base.unwrap_or_else(|| first_strong(&classes[start..end]).unwrap_or(0));
+        levels.extend(Paragraph::new(&text[start..end], &classes[start..end], level).resolve());
+        start = end;
+    }
+    levels
+}
+
+// 段落的基础级别由第一个强类型字符决定，没有强类型字符时为 0
+pub fn paragraph_level(text: &[char]) -> Level {
+    let classes: Vec<BidiClass> = text.iter().map(|&c| bidi_class(c)).collect();
+    first_strong(&classes).unwrap_or(0)
+}
+
+// 按照 L2 规则把一行中的内容排列为显示顺序，返回每个显示位置上的内容在 levels 中的下标
+pub fn visual_order(levels: &[Level]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let (Some(&highest), Some(lowest_odd)) = (levels.iter().max(), levels.iter().copied().filter(|&l| is_rtl(l)).min()) else {
+        return order;
+    };
+    // 从最高的级别开始，依次反转不低于这个级别的每一段内容
+    for level in (lowest_odd..=highest).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+    }
+    order
+}
+
+// 第一个不在隔离内容中的强类型字符决定的方向，没有时返回 None
+fn first_strong(classes: &[BidiClass]) -> Option<Level> {
+    let mut isolates = 0;
+    for &class in classes {
+        match class {
+            L if isolates == 0 => return Some(0),
+            R | AL if isolates == 0 => return Some(1),
+            LRI | RLI | FSI => isolates += 1,
+            PDI if isolates > 0 => isolates -= 1,
+            B => break,
+            _ => {}
+        }
+    }
+    None
+}
+
+// 比 level 高的下一个奇数或者偶数级别
+fn next_level(level: Level, rtl: bool) -> Level {
+    match (rtl, is_rtl(level)) {
+        (true, true) | (false, false) => level + 2,
+        _ => level + 1,
+    }
+}
+
+// 级别对应的强类型
+fn direction(level: Level) -> BidiClass {
+    if is_rtl(level) { R } else { L }
+}
+
+// 中性类型规则中数字视为从右到左
+fn strong_direction(class: BidiClass) -> Option<BidiClass> {
+    match class {
+        L => Some(L),
+        R | AL | EN | AN => Some(R),
+        _ => None,
+    }
+}
+
+fn is_isolate_initiator(class: BidiClass) -> bool {
+    matches!(class, LRI | RLI | FSI)
+}
+
+// X9 规则删除的字符，它们不参与之后的规则
+fn is_removed(class: BidiClass) -> bool {
+    matches!(class, RLE | LRE | RLO | LRO | PDF | BN)
+}
+
+fn is_neutral(class: BidiClass) -> bool {
+    matches!(class, B | S | WS | ON | LRI | RLI | FSI | PDI)
+}
+
+// 一个段落，以段落分隔符结束
+struct Paragraph<'t> {
+    text: &'t [char],
+    // 原始的双向类型
+    original: &'t [BidiClass],
+    // 经过覆盖和各条规则修改后的类型
+    classes: Vec<BidiClass>,
+    levels: Vec<Level>,
+    level: Level,
+    // 隔离开始字符对应的 PDI 的位置
+    matching_pdi: Vec<Option<usize>>,
+}
+
+#[derive(Clone, Copy)]
+struct Status {
+    level: Level,
+    overridden: Option<BidiClass>,
+    isolate: bool,
+}
+
+impl<'t> Paragraph<'t> {
+    fn new(text: &'t [char], original: &'t [BidiClass], level: Level) -> Paragraph<'t> {
+        let mut matching_pdi = vec![None; text.len()];
+        let mut open = Vec::new();
+        for (i, &class) in original.iter().enumerate() {
+            if is_isolate_initiator(class) {
+                open.push(i);
+            } else if class == PDI {
+                if let Some(start) = open.pop() {
+                    matching_pdi[start] = Some(i);
+                }
+            }
+        }
+        Paragraph { text, original, classes: original.to_vec(), levels: vec![level; text.len()], level, matching_pdi }
+    }
+
+    fn resolve(mut self) -> Vec<Level> {
+        self.explicit_levels();
+        for sequence in self.isolating_run_sequences() {
+            self.resolve_sequence(&sequence);
+        }
+        self.reset_separators();
+        self.levels
+    }
+
+    // X1-X8：根据显式格式字符计算嵌入级别，覆盖的内容修改为强类型
+    fn explicit_levels(&mut self) {
+        let mut stack = vec![Status { level: self.level, overridden: None, isolate: false }];
+        let (mut overflow_isolates, mut overflow_embeddings, mut valid_isolates) = (0, 0, 0);
+        for i in 0..self.text.len() {
+            let top = *stack.last().unwrap();
+            match self.original[i] {
+                RLE | LRE | RLO | LRO => {
+                    self.levels[i] = top.level;
+                    let class = self.original[i];
+                    let level = next_level(top.level, matches!(class, RLE | RLO));
+                    if level <= MAX_DEPTH && overflow_isolates == 0 && overflow_embeddings == 0 {
+                        let overridden = match class {
+                            RLO => Some(R),
+                            LRO => Some(L),
+                            _ => None,
+                        };
+                        stack.push(Status { level, overridden, isolate: false });
+                    } else if overflow_isolates == 0 {
+                        overflow_embeddings += 1;
+                    }
+                }
+                RLI | LRI | FSI => {
+                    self.levels[i] = top.level;
+                    if let Some(class) = top.overridden {
+                        self.classes[i] = class;
+                    }
+                    let rtl = match self.original[i] {
+                        RLI => true,
+                        LRI => false,
+                        _ => first_strong(&self.original[i + 1..self.matching_pdi[i].unwrap_or(self.text.len())]) == Some(1),
+                    };
+                    let level = next_level(top.level, rtl);
+                    if level <= MAX_DEPTH && overflow_isolates == 0 && overflow_embeddings == 0 {
+                        valid_isolates += 1;
+                        stack.push(Status { level, overridden: None, isolate: true });
+                    } else {
+                        overflow_isolates += 1;
+                    }
+                }
+                PDI => {
+                    if overflow_isolates > 0 {
+                        overflow_isolates -= 1;
+                    } else if valid_isolates > 0 {
+                        overflow_embeddings = 0;
+                        while !stack.last().unwrap().isolate {
+                            stack.pop();
+                        }
+                        stack.pop();
+                        valid_isolates -= 1;
+                    }
+                    let top = *stack.last().unwrap();
+                    self.levels[i] = top.level;
+                    if let Some(class) = top.overridden {
+                        self.classes[i] = class;
+                    }
+                }
+                // PDF 使用结束嵌入之后的级别
+                PDF => {
+                    if overflow_isolates > 0 {
+                    } else if overflow_embeddings > 0 {
+                        overflow_embeddings -= 1;
+                    } else if !top.isolate && stack.len() >= 2 {
+                        stack.pop();
+                    }
+                    self.levels[i] = stack.last().unwrap().level;
+                }
+                B => self.levels[i] = self.level,
+                BN => self.levels[i] = top.level,
+                _ => {
+                    self.levels[i] = top.level;
+                    if let Some(class) = top.overridden {
+                        self.classes[i] = class;
+                    }
+                }
+            }
+        }
+    }
+
+    // X10：把级别相同的连续字符分成一段，隔离开始字符所在的段和对应的 PDI 所在的段连接为一个序列
+    fn isolating_run_sequences(&self) -> Vec<Vec<usize>> {
+        let mut runs: Vec<Vec<usize>> = Vec::new();
+        for i in (0..self.text.len()).filter(|&i| !is_removed(self.original[i])) {
+            match runs.last_mut() {
+                Some(run) if self.levels[*run.last().unwrap()] == self.levels[i] => run.push(i),
+                _ => runs.push(vec![i]),
+            }
+        }
+
+        let matched: Vec<usize> = self.matching_pdi.iter().flatten().copied().collect();
+        let mut sequences = Vec::new();
+        for run in &runs {
+            // 以对应某个隔离开始字符的 PDI 开头的段已经连接到前面的序列
+            if matched.contains(&run[0]) {
+                continue;
+            }
+            let mut sequence = run.clone();
+            while let Some(pdi) = self.matching_pdi[*sequence.last().unwrap()] {
+                match runs.iter().find(|run| run[0] == pdi) {
+                    Some(next) => sequence.extend(next),
+                    None => break,
+                }
+            }
+            sequences.push(sequence);
+        }
+        sequences
+    }
+
+    // W1-W7、N0-N2、I1-I2：处理一个序列中的弱类型和中性类型，然后确定每个字符的级别
+    fn resolve_sequence(&mut self, sequence: &[usize]) {
+        let level = self.levels[sequence[0]];
+        let (sos, eos) = self.boundaries(sequence);
+        let mut types: Vec<BidiClass> = sequence.iter().map(|&i| self.classes[i]).collect();
+
+        // W1：组合字符取前一个字符的类型
+        let mut previous = sos;
+        for class in types.iter_mut() {
+            if *class == NSM {
+                *class = if matches!(previous, LRI | RLI | FSI | PDI) { ON } else { previous };
+            }
+            previous = *class;
+        }
+        // W2：阿拉伯字母之后的欧洲数字视为阿拉伯数字；W3：阿拉伯字母视为 R
+        let mut strong = sos;
+        for class in types.iter_mut() {
+            match *class {
+                L | R | AL => strong = *class,
+                EN if strong == AL => *class = AN,
+                _ => {}
+            }
+        }
+        for class in types.iter_mut().filter(|class| **class == AL) {
+            *class = R;
+        }
+        // W4：两个数字之间的单个分隔符
+        for i in 1..types.len().saturating_sub(1) {
+            let (before, after) = (types[i - 1], types[i + 1]);
+            match types[i] {
+                ES if before == EN && after == EN => types[i] = EN,
+                CS if before == after && matches!(before, EN | AN) => types[i] = before,
+                _ => {}
+            }
+        }
+        // W5：欧洲数字前后的货币符号等
+        let mut i = 0;
+        while i < types.len() {
+            if types[i] != ET {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < types.len() && types[i] == ET {
+                i += 1;
+            }
+            if (start > 0 && types[start - 1] == EN) || types.get(i) == Some(&EN) {
+                types[start..i].fill(EN);
+            }
+        }
+        // W6：其余的分隔符视为中性；W7：从左到右的文字之后的欧洲数字视为 L
+        for class in types.iter_mut().filter(|class| matches!(class, ES | ET | CS)) {
+            *class = ON;
+        }
+        let mut strong = sos;
+        for class in types.iter_mut() {
+            match *class {
+                L | R => strong = *class,
+                EN if strong == L => *class = L,
+                _ => {}
+            }
+        }
+
+        self.resolve_brackets(sequence, &mut types, sos, level);
+
+        // N1：两侧方向相同的中性字符取这个方向；N2：其余的中性字符取嵌入方向
+        let mut i = 0;
+        while i < types.len() {
+            if !is_neutral(types[i]) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < types.len() && is_neutral(types[i]) {
+                i += 1;
+            }
+            let before = if start == 0 { Some(sos) } else { strong_direction(types[start - 1]) };
+            let after = if i == types.len() { Some(eos) } else { strong_direction(types[i]) };
+            let resolved = match (before, after) {
+                (Some(before), Some(after)) if before == after => before,
+                _ => direction(level),
+            };
+            types[start..i].fill(resolved);
+        }
+
+        // I1、I2
+        for (&index, &class) in sequence.iter().zip(&types) {
+            let level = &mut self.levels[index];
+            match (is_rtl(*level), class) {
+                (false, R) => *level += 1,
+                (false, AN | EN) => *level += 2,
+                (true, L | EN | AN) => *level += 1,
+                _ => {}
+            }
+        }
+    }
+
+    // 序列开始和结束处的方向，由序列的级别和相邻字符的级别中较高的一个决定
+    fn boundaries(&self, sequence: &[usize]) -> (BidiClass, BidiClass) {
+        let first = sequence[0];
+        let last = *sequence.last().unwrap();
+        let level = self.levels[first];
+        let before = (0..first).rev().find(|&i| !is_removed(self.original[i])).map_or(self.level, |i| self.levels[i]);
+        let after = if is_isolate_initiator(self.original[last]) {
+            self.level
+        } else {
+            (last + 1..self.text.len()).find(|&i| !is_removed(self.original[i])).map_or(self.level, |i| self.levels[i])
+        };
+        (direction(level.max(before)), direction(self.levels[last].max(after)))
+    }
+
+    // N0：成对的括号取括号中的内容的方向
+    fn resolve_brackets(&self, sequence: &[usize], types: &mut [BidiClass], sos: BidiClass, level: Level) {
+        let mut pairs = Vec::new();
+        let mut open: Vec<(char, usize)> = Vec::new();
+        for (position, &index) in sequence.iter().enumerate() {
+            if types[position] != ON {
+                continue;
+            }
+            let c = self.text[index];
+            if let Some(&(_, close)) = BRACKETS.iter().find(|&&(o, _)| o == c) {
+                if open.len() == MAX_BRACKETS {
+                    break;
+                }
+                open.push((close, position));
+            } else if let Some(depth) = open.iter().rposition(|&(close, _)| close == c) {
+                pairs.push((open[depth].1, position));
+                open.truncate(depth);
+            }
+        }
+        pairs.sort();
+
+        let embedding = direction(level);
+        for (start, end) in pairs {
+            let inside: Vec<BidiClass> = types[start + 1..end].iter().filter_map(|&class| strong_direction(class)).collect();
+            let resolved = if inside.contains(&embedding) {
+                embedding
+            } else if let Some(&opposite) = inside.first() {
+                // 括号中只有相反方向的内容时，括号前面的内容也是这个方向才取相反方向
+                let context = types[..start].iter().rev().find_map(|&class| strong_direction(class)).unwrap_or(sos);
+                if context == opposite { opposite } else { embedding }
+            } else {
+                continue;
+            };
+            for position in [start, end] {
+                types[position] = resolved;
+                // 括号之后的组合字符和括号相同
+                for next in position + 1..types.len() {
+                    if self.original[sequence[next]] != NSM {
+                        break;
+                    }
+                    types[next] = resolved;
+                }
+            }
+        }
+    }
+
+    // L1：段落分隔符、制表符以及它们前面的空白使用段落的级别
+    fn reset_separators(&mut self) {
+        let mut reset = false;
+        for i in (0..self.text.len()).rev() {
+            match self.original[i] {
+                B | S => {
+                    reset = true;
+                    self.levels[i] = self.level;
+                }
+                WS | LRI | RLI | FSI | PDI | RLE | LRE | RLO | LRO | PDF | BN if reset => self.levels[i] = self.level,
+                _ => reset = false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{levels, paragraph_level, visual_order, Level};
+
+    // 返回每个字符的级别和按照显示顺序排列的文字
+    fn reorder(text: &str) -> (Vec<Level>, String) {
+        let chars: Vec<char> = text.chars().collect();
+        let levels = levels(&chars, None);
+        let visual = visual_order(&levels).into_iter().map(|i| chars[i]).collect();
+        (levels, visual)
+    }
+
+    #[test]
+    fn mixed_directions() {
+        // 从左到右的段落中，希伯来字母反转，两侧方向不同的空白取段落的方向
+        assert_eq!(reorder("abc אבג def"), (vec![0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0], "abc גבא def".to_string()));
+        // 从右到左的段落中，英文和数字保持从左到右的顺序
+        let (levels, visual) = reorder("אבג abc 123 דה");
+        assert_eq!(paragraph_level(&"אבג abc 123 דה".chars().collect::<Vec<_>>()), 1);
+        assert_eq!(levels, [1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1]);
+        assert_eq!(visual, "הד abc 123 גבא");
+    }
+
+    #[test]
+    fn numbers_and_brackets() {
+        // 数字中的分隔符随数字一起从左到右排列
+        assert_eq!(reorder("ש 1.5 ק").1, "ק 1.5 ש");
+        // 阿拉伯字母之后的欧洲数字和阿拉伯数字都保持从左到右的顺序
+        assert_eq!(reorder("سلام 123 ١٢٣").1, "١٢٣ 123 مالس");
+        // 括号取括号中的内容和嵌入方向，和段落一起反转
+        assert_eq!(reorder("א (b) ג"), (vec![1, 1, 1, 2, 1, 1, 1], "ג )b( א".to_string()));
+    }
+
+    #[test]
+    fn explicit_embeddings_and_overrides() {
+        // RLO 把其中的内容视为从右到左
+        assert_eq!(reorder("ab\u{202E}cd\u{202C}ef"), (vec![0, 0, 0, 1, 1, 0, 0, 0], "ab\u{202E}dc\u{202C}ef".to_string()));
+        // RLE 只提高嵌入级别，从左到右的文字仍然从左到右
+        assert_eq!(reorder("ab\u{202B}cd\u{202C}ef"), (vec![0, 0, 0, 2, 2, 0, 0, 0], "ab\u{202B}cd\u{202C}ef".to_string()));
+        // 从右到左的段落中 LRE 嵌入的内容和 PDF 之后的内容
+        let (levels, visual) = reorder("אב\u{202A}cd\u{202C}ef");
+        assert_eq!(levels, [1, 1, 1, 2, 2, 1, 2, 2]);
+        assert_eq!(visual, "ef\u{202C}cd\u{202A}בא");
+    }
+
+    #[test]
+    fn isolates() {
+        // 隔离的内容不参与决定段落的方向
+        assert_eq!(paragraph_level(&"\u{2067}אב\u{2069}cd".chars().collect::<Vec<_>>()), 0);
+        assert_eq!(reorder("\u{2067}אב\u{2069}cd"), (vec![0, 1, 1, 0, 0, 0], "\u{2067}בא\u{2069}cd".to_string()));
+        // 隔离开始和结束字符使用外面的级别，其中的内容整体反转
+        let (levels, visual) = reorder("ab \u{2067}cd אב\u{2069} ef");
+        assert_eq!(levels, [0, 0, 0, 0, 2, 2, 1, 1, 1, 0, 0, 0, 0]);
+        assert_eq!(visual, "ab \u{2067}בא cd\u{2069} ef");
+    }
+
+    #[test]
+    fn each_paragraph_has_its_own_direction() {
+        let chars: Vec<char> = "ab\nאב cd".chars().collect();
+        assert_eq!(levels(&chars, None), [0, 0, 0, 1, 1, 1, 2, 2]);
+        // 指定基础级别时所有段落都使用它
+        assert_eq!(levels(&chars, Some(0)), [0, 0, 0, 1, 1, 0, 0, 0]);
+    }
+}
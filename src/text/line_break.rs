@@ -0,0 +1,208 @@
+// 换行机会（UAX #14）：根据相邻两个字符的换行类型判断它们之间是否可以换行
+// 空白由行内布局单独处理，空白之后总是可以换行，所以这里只实现不依赖空白的成对规则
+use std::cmp::Ordering;
+
+use self::LineBreakClass::*;
+
+// 字符的换行类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineBreakClass {
+    // 强制换行、回车、换行、下一行、空格、零宽空格
+    BK, CR, LF, NL, SP, ZW,
+    // 组合字符、不能在前后换行的字符、不换行空格
+    CM, WJ, GL,
+    // 开始标点、结束标点、右括号、引号、感叹号、数字中的分隔符、不能出现在行首的字符、斜线
+    OP, CL, CP, QU, EX, IS, NS, SY,
+    // 数字前缀、数字后缀、数字、字母、希伯来字母、表意文字、省略号
+    PR, PO, NU, AL, HL, ID, IN,
+    // 连字符、之后可以换行、之前可以换行、前后都可以换行（破折号）
+    HY, BA, BB, B2,
+}
+
+// 常用文字的换行类型，按照编码排序，表中没有的字符为 AL
+const CLASSES: &[(u32, u32, LineBreakClass)] = &[
+    (0x0009, 0x0009, BA), (0x000A, 0x000A, LF), (0x000B, 0x000C, BK), (0x000D, 0x000D, CR), (0x0020, 0x0020, SP),
+    (0x0021, 0x0021, EX), (0x0022, 0x0022, QU), (0x0024, 0x0024, PR), (0x0025, 0x0025, PO), (0x0027, 0x0027, QU),
+    (0x0028, 0x0028, OP), (0x0029, 0x0029, CP), (0x002B, 0x002B, PR), (0x002C, 0x002C, IS), (0x002D, 0x002D, HY),
+    (0x002E, 0x002E, IS), (0x002F, 0x002F, SY), (0x0030, 0x0039, NU), (0x003A, 0x003B, IS), (0x003F, 0x003F, EX),
+    (0x005B, 0x005B, OP), (0x005C, 0x005C, PR), (0x005D, 0x005D, CP), (0x007B, 0x007B, OP), (0x007C, 0x007C, BA),
+    (0x007D, 0x007D, CL), (0x0085, 0x0085, NL), (0x00A0, 0x00A0, GL), (0x00A1, 0x00A1, OP), (0x00A2, 0x00A2, PO),
+    (0x00A3, 0x00A5, PR), (0x00AB, 0x00AB, QU), (0x00AD, 0x00AD, BA), (0x00B0, 0x00B0, PO), (0x00B1, 0x00B1, PR),
+    (0x00B4, 0x00B4, BB), (0x00BB, 0x00BB, QU), (0x00BF, 0x00BF, OP),
+    (0x0300, 0x036F, CM), (0x0483, 0x0489, CM),
+    // 希伯来字母
+    (0x0591, 0x05BD, CM), (0x05BE, 0x05BE, BA), (0x05BF, 0x05BF, CM), (0x05C1, 0x05C2, CM), (0x05C4, 0x05C5, CM),
+    (0x05C7, 0x05C7, CM), (0x05D0, 0x05EA, HL), (0x05EF, 0x05F2, HL),
+    // 阿拉伯字母
+    (0x0609, 0x060A, PO), (0x060C, 0x060D, IS), (0x0610, 0x061A, CM), (0x061B, 0x061B, EX), (0x061D, 0x061F, EX),
+    (0x064B, 0x065F, CM), (0x0660, 0x0669, NU), (0x066A, 0x066A, PO), (0x066B, 0x066C, NU), (0x0670, 0x0670, CM),
+    (0x06D4, 0x06D4, EX), (0x06D6, 0x06DC, CM), (0x06DF, 0x06E4, CM), (0x06E7, 0x06E8, CM), (0x06EA, 0x06ED, CM),
+    (0x06F0, 0x06F9, NU),
+    // 韩文字母
+    (0x1100, 0x115F, ID),
+    // 标点和格式字符
+    (0x2000, 0x2006, BA), (0x2007, 0x2007, GL), (0x2008, 0x200A, BA), (0x200B, 0x200B, ZW), (0x200C, 0x200D, CM),
+    (0x2010, 0x2010, BA), (0x2011, 0x2011, GL), (0x2012, 0x2013, BA), (0x2014, 0x2014, B2), (0x2018, 0x2019, QU),
+    (0x201A, 0x201A, OP), (0x201B, 0x201D, QU), (0x201E, 0x201E, OP), (0x201F, 0x201F, QU), (0x2024, 0x2026, IN),
+    (0x2027, 0x2027, BA), (0x2028, 0x2029, BK), (0x202F, 0x202F, GL), (0x2030, 0x2037, PO), (0x2039, 0x203A, QU),
+    (0x203C, 0x203D, NS), (0x2044, 0x2044, IS), (0x2045, 0x2045, OP), (0x2046, 0x2046, CL), (0x2047, 0x2049, NS),
+    (0x2060, 0x2060, WJ), (0x20A0, 0x20CF, PR),
+    // 中日韩文字和标点
+    (0x2E80, 0x2FFF, ID), (0x3000, 0x3000, BA), (0x3001, 0x3002, CL), (0x3003, 0x3004, ID), (0x3005, 0x3005, NS),
+    (0x3006, 0x3007, ID), (0x3008, 0x3008, OP), (0x3009, 0x3009, CL), (0x300A, 0x300A, OP), (0x300B, 0x300B, CL),
+    (0x300C, 0x300C, OP), (0x300D, 0x300D, CL), (0x300E, 0x300E, OP), (0x300F, 0x300F, CL), (0x3010, 0x3010, OP),
+    (0x3011, 0x3011, CL), (0x3012, 0x3013, ID), (0x3014, 0x3014, OP), (0x3015, 0x3015, CL), (0x3016, 0x3016, OP),
+    (0x3017, 0x3017, CL), (0x3018, 0x3018, OP), (0x3019, 0x3019, CL), (0x301A, 0x301A, OP), (0x301B, 0x301B, CL),
+    (0x301C, 0x301C, NS), (0x301D, 0x301D, OP), (0x301E, 0x301F, CL), (0x3020, 0x303A, ID), (0x303B, 0x303C, NS),
+    (0x303D, 0x303F, ID),
+    // 平假名和片假名，小写的假名不能出现在行首
+    (0x3041, 0x3041, NS), (0x3042, 0x3042, ID), (0x3043, 0x3043, NS), (0x3044, 0x3044, ID), (0x3045, 0x3045, NS),
+    (0x3046, 0x3046, ID), (0x3047, 0x3047, NS), (0x3048, 0x3048, ID), (0x3049, 0x3049, NS), (0x304A, 0x3062, ID),
+    (0x3063, 0x3063, NS), (0x3064, 0x3082, ID), (0x3083, 0x3083, NS), (0x3084, 0x3084, ID), (0x3085, 0x3085, NS),
+    (0x3086, 0x3086, ID), (0x3087, 0x3087, NS), (0x3088, 0x308D, ID), (0x308E, 0x308E, NS), (0x308F, 0x3094, ID),
+    (0x3095, 0x3096, NS), (0x3099, 0x309A, CM), (0x309B, 0x309E, NS), (0x309F, 0x309F, ID), (0x30A0, 0x30A1, NS),
+    (0x30A2, 0x30A2, ID), (0x30A3, 0x30A3, NS), (0x30A4, 0x30A4, ID), (0x30A5, 0x30A5, NS), (0x30A6, 0x30A6, ID),
+    (0x30A7, 0x30A7, NS), (0x30A8, 0x30A8, ID), (0x30A9, 0x30A9, NS), (0x30AA, 0x30C2, ID), (0x30C3, 0x30C3, NS),
+    (0x30C4, 0x30E2, ID), (0x30E3, 0x30E3, NS), (0x30E4, 0x30E4, ID), (0x30E5, 0x30E5, NS), (0x30E6, 0x30E6, ID),
+    (0x30E7, 0x30E7, NS), (0x30E8, 0x30ED, ID), (0x30EE, 0x30EE, NS), (0x30EF, 0x30F4, ID), (0x30F5, 0x30F6, NS),
+    (0x30F7, 0x30FA, ID), (0x30FB, 0x30FE, NS), (0x30FF, 0x30FF, ID), (0x3100, 0x31EF, ID), (0x31F0, 0x31FF, NS),
+    (0x3200, 0x4DBF, ID), (0x4E00, 0x9FFF, ID), (0xA000, 0xA4CF, ID), (0xAC00, 0xD7A3, ID), (0xF900, 0xFAFF, ID),
+    // 竖排标点和全角字符
+    (0xFE10, 0xFE10, IS), (0xFE11, 0xFE12, CL), (0xFE13, 0xFE14, NS), (0xFE15, 0xFE16, EX), (0xFE17, 0xFE17, OP),
+    (0xFE18, 0xFE18, CL), (0xFE19, 0xFE19, IN), (0xFE30, 0xFE4F, ID), (0xFEFF, 0xFEFF, WJ), (0xFF01, 0xFF01, EX),
+    (0xFF02, 0xFF03, ID), (0xFF04, 0xFF04, PR), (0xFF05, 0xFF05, PO), (0xFF06, 0xFF07, ID), (0xFF08, 0xFF08, OP),
+    (0xFF09, 0xFF09, CL), (0xFF0A, 0xFF0B, ID), (0xFF0C, 0xFF0C, CL), (0xFF0D, 0xFF0D, ID), (0xFF0E, 0xFF0E, CL),
+    (0xFF0F, 0xFF19, ID), (0xFF1A, 0xFF1B, NS), (0xFF1C, 0xFF1E, ID), (0xFF1F, 0xFF1F, EX), (0xFF20, 0xFF3A, ID),
+    (0xFF3B, 0xFF3B, OP), (0xFF3C, 0xFF3C, ID), (0xFF3D, 0xFF3D, CL), (0xFF3E, 0xFF5A, ID), (0xFF5B, 0xFF5B, OP),
+    (0xFF5C, 0xFF5C, ID), (0xFF5D, 0xFF5D, CL), (0xFF5E, 0xFF5E, ID), (0xFF5F, 0xFF5F, OP), (0xFF60, 0xFF61, CL),
+    (0xFF62, 0xFF62, OP), (0xFF63, 0xFF64, CL), (0xFF65, 0xFF65, NS), (0xFF66, 0xFF9F, ID), (0xFFE0, 0xFFE0, PO),
+    (0xFFE1, 0xFFE1, PR), (0xFFE2, 0xFFE4, ID), (0xFFE5, 0xFFE6, PR),
+    // 表情符号和扩展的表意文字
+    (0x1F000, 0x1FAFF, ID), (0x20000, 0x3FFFD, ID),
+];
+
+pub fn line_break_class(c: char) -> LineBreakClass {
+    let code = c as u32;
+    let found = CLASSES.binary_search_by(|&(start, end, _)| {
+        if end < code {
+            Ordering::Less
+        } else if start > code {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    });
+    match found {
+        Ok(index) => CLASSES[index].2,
+        Err(_) => AL,
+    }
+}
+
+// 两个相邻的字符之间是否可以换行，before 是组合字符之前的基本字符的类型（LB9）
+pub fn break_between(before: LineBreakClass, after: LineBreakClass) -> bool {
+    match (before, after) {
+        // LB4-LB6：强制换行之后必须换行，之前不能换行
+        (BK | CR | LF | NL, _) => true,
+        (_, BK | CR | LF | NL) => false,
+        // LB8：零宽空格之后可以换行
+        (ZW, _) => true,
+        // LB9：组合字符和前面的字符作为一个整体
+        (_, CM | ZW) => false,
+        // LB11、LB12、LB12a：不换行空格和连接符的前后不能换行
+        (WJ | GL, _) | (_, WJ) => false,
+        (_, GL) => matches!(before, BA | HY),
+        // LB13：结束标点之前不能换行
+        (_, CL | CP | EX | IS | SY) => false,
+        // LB14：开始标点之后不能换行
+        (OP, _) => false,
+        // LB15-LB17
+        (QU, OP) | (CL | CP, NS) | (B2, B2) => false,
+        // LB19：引号的前后不能换行
+        (QU, _) | (_, QU) => false,
+        // LB21：连字符等字符之前、BB 之后不能换行
+        (_, BA | HY | NS) | (BB, _) => false,
+        // LB21b、LB22
+        (SY, HL) | (_, IN) => false,
+        // LB23-LB25：数字和字母、数字和前后缀之间不能换行
+        (AL | HL, NU) | (NU, AL | HL) => false,
+        (PR, ID) | (ID, PO) => false,
+        (PR | PO, AL | HL) | (AL | HL, PR | PO) => false,
+        (CL | CP | NU, PO | PR) | (PO | PR, OP | NU) | (HY | IS | NU | SY, NU) => false,
+        // LB28-LB30：字母之间、字母和括号之间不能换行
+        (AL | HL, AL | HL) | (IS, AL | HL) => false,
+        (AL | HL | NU, OP) | (CP, AL | HL | NU) => false,
+        // LB31：其他位置都可以换行，例如两个表意文字之间
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{break_between, line_break_class, LineBreakClass};
+
+    // 和行内布局一样判断每两个字符之间能否换行，返回可以换行的位置（之后的字符的下标）
+    fn breaks(text: &str) -> Vec<usize> {
+        let mut breaks = Vec::new();
+        let mut previous: Option<LineBreakClass> = None;
+        for (i, c) in text.chars().enumerate() {
+            let class = match line_break_class(c) {
+                LineBreakClass::CM if previous.is_some() => continue,
+                LineBreakClass::CM => LineBreakClass::AL,
+                class => class,
+            };
+            if previous.is_some_and(|previous| break_between(previous, class)) {
+                breaks.push(i);
+            }
+            previous = Some(class);
+        }
+        breaks
+    }
+
+    #[test]
+    fn ideographs() {
+        // 表意文字、假名和韩文音节之间都可以换行
+        assert_eq!(breaks("中文字"), [1, 2]);
+        assert_eq!(breaks("한국어"), [1, 2]);
+        assert_eq!(breaks("日本語と中文"), [1, 2, 3, 4, 5]);
+        // 小写的假名和长音符号不能出现在行首
+        assert_eq!(breaks("きゃく"), [2]);
+        assert_eq!(breaks("カード"), [2]);
+    }
+
+    #[test]
+    fn punctuation() {
+        // 结束标点不能出现在行首，开始标点不能出现在行尾
+        assert_eq!(breaks("中文。字"), [1, 3]);
+        assert_eq!(breaks("中「文」字"), [1, 4]);
+        assert_eq!(breaks("中（文）字"), [1, 4]);
+        // 字母、数字和它们前后的符号之间不能换行，连字符之后可以
+        assert_eq!(breaks("Hello,world!"), Vec::<usize>::new());
+        assert_eq!(breaks("$10.5%"), Vec::<usize>::new());
+        assert_eq!(breaks("well-known"), [5]);
+        assert_eq!(breaks("a—b"), [1, 2]);
+        // 斜线之前不能换行，之后可以，数字之间的斜线除外
+        assert_eq!(breaks("a/b"), [2]);
+        assert_eq!(breaks("1/2"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn combining_marks_stay_with_their_base() {
+        // 组合字符之后使用前面的基本字符的类型
+        assert_eq!(breaks("中\u{0301}文"), [2]);
+        assert_eq!(breaks("e\u{0301}\u{0301}x"), Vec::<usize>::new());
+        assert_eq!(breaks("中\u{0301}。"), Vec::<usize>::new());
+        // 行首的组合字符视为字母
+        assert_eq!(breaks("\u{0301}中"), [1]);
+    }
+
+    #[test]
+    fn spaces_and_joiners() {
+        // 零宽空格之后可以换行，不换行空格和连接符的前后不能换行
+        assert_eq!(breaks("ab\u{200B}cd"), [3]);
+        assert_eq!(breaks("中\u{00A0}文"), Vec::<usize>::new());
+        assert_eq!(breaks("中\u{2060}文"), Vec::<usize>::new());
+        // 强制换行之后必须换行，之前不能换行
+        assert!(break_between(LineBreakClass::LF, LineBreakClass::AL));
+        assert!(!break_between(LineBreakClass::ID, LineBreakClass::LF));
+    }
+}
@@ -0,0 +1,4 @@
+// 文本处理：双向文本的嵌入级别和显示顺序（UAX #9）、换行机会（UAX #14），以及阿拉伯字母连写和连字等字形变换
+pub mod bidi;
+pub mod line_break;
+pub mod shaping;
@@ -0,0 +1,218 @@
+// 字形变换：阿拉伯字母按照前后的字母选择连写形式，常见的字母组合替换为连字，从右到左的文字排列为显示顺序
+// 没有读取字体中 OpenType 的 GSUB 表，变换结果使用 Unicode 中的表现形式字符，字体中没有对应的字形时保留原来的字符
+use crate::font::Font;
+
+use super::bidi::{self, BidiClass};
+
+// 字母和后面的字母之间是否连写
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Joining {
+    // 不和前后的字母连写，例如 hamza
+    None,
+    // 只和前面的字母连写，例如 alef
+    Right,
+    // 和前后的字母都可以连写
+    Dual,
+    // 不改变形状，但是让前后的字母连写，例如 tatweel 和零宽连接符
+    Causing,
+    // 组合字符，不影响连写
+    Transparent,
+}
+
+// 连写形式在表现形式中相对于独立形式的偏移：独立、词尾、词首、词中
+const ISOLATED: u32 = 0;
+const FINAL: u32 = 1;
+const INITIAL: u32 = 2;
+const MEDIAL: u32 = 3;
+
+// U+0621 到 U+064A 的字母在 Arabic Presentation Forms-B 中的独立形式，0 表示没有表现形式
+const BASIC_FORMS: [(u32, Joining); 42] = [
+    (0xFE80, Joining::None), (0xFE81, Joining::Right), (0xFE83, Joining::Right), (0xFE85, Joining::Right),
+    (0xFE87, Joining::Right), (0xFE89, Joining::Dual), (0xFE8D, Joining::Right), (0xFE8F, Joining::Dual),
+    (0xFE93, Joining::Right), (0xFE95, Joining::Dual), (0xFE99, Joining::Dual), (0xFE9D, Joining::Dual),
+    (0xFEA1, Joining::Dual), (0xFEA5, Joining::Dual), (0xFEA9, Joining::Right), (0xFEAB, Joining::Right),
+    (0xFEAD, Joining::Right), (0xFEAF, Joining::Right), (0xFEB1, Joining::Dual), (0xFEB5, Joining::Dual),
+    (0xFEB9, Joining::Dual), (0xFEBD, Joining::Dual), (0xFEC1, Joining::Dual), (0xFEC5, Joining::Dual),
+    (0xFEC9, Joining::Dual), (0xFECD, Joining::Dual), (0, Joining::Dual), (0, Joining::Dual),
+    (0, Joining::Dual), (0, Joining::Dual), (0, Joining::Dual), (0, Joining::Causing),
+    (0xFED1, Joining::Dual), (0xFED5, Joining::Dual), (0xFED9, Joining::Dual), (0xFEDD, Joining::Dual),
+    (0xFEE1, Joining::Dual), (0xFEE5, Joining::Dual), (0xFEE9, Joining::Dual), (0xFEED, Joining::Right),
+    (0xFEEF, Joining::Right), (0xFEF1, Joining::Dual),
+];
+
+// 波斯语等使用的字母在 Arabic Presentation Forms-A 中的独立形式
+const EXTENDED_FORMS: [(char, u32, Joining); 6] = [
+    ('\u{067E}', 0xFB56, Joining::Dual), ('\u{0686}', 0xFB7A, Joining::Dual), ('\u{0698}', 0xFB8A, Joining::Right),
+    ('\u{06A9}', 0xFB8E, Joining::Dual), ('\u{06AF}', 0xFB92, Joining::Dual), ('\u{06CC}', 0xFBFC, Joining::Dual),
+];
+
+// lam 和各种 alef 组成的连字的独立形式，词尾形式为独立形式加 1
+const LAM: char = '\u{0644}';
+const LAM_ALEF: [(char, u32); 4] = [('\u{0622}', 0xFEF5), ('\u{0623}', 0xFEF7), ('\u{0625}', 0xFEF9), ('\u{0627}', 0xFEFB)];
+
+// 拉丁字母的连字，较长的排在前面
+const LIGATURES: [(&str, char); 5] = [("ffi", '\u{FB03}'), ("ffl", '\u{FB04}'), ("ff", '\u{FB00}'), ("fi", '\u{FB01}'), ("fl", '\u{FB02}')];
+
+// 变换逻辑顺序的文字，rtl 为 true 时返回从右到左的显示顺序，并镜像括号等字符
+pub fn shape(text: &str, rtl: bool, font: &Font) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let joined = join_arabic(&chars, font);
+    let shaped = ligatures(&joined, font);
+    if !rtl {
+        return shaped.into_iter().collect();
+    }
+
+    // 组合字符跟随前面的字符一起反转
+    let mut clusters: Vec<Vec<char>> = Vec::new();
+    for c in shaped {
+        match clusters.last_mut() {
+            Some(cluster) if bidi::bidi_class(c) == BidiClass::NSM => cluster.push(c),
+            _ => clusters.push(vec![bidi::mirror(c)]),
+        }
+    }
+    clusters.into_iter().rev().flatten().collect()
+}
+
+fn joining(c: char) -> Joining {
+    match c {
+        '\u{0621}'..='\u{064A}' => BASIC_FORMS[c as usize - 0x0621].1,
+        '\u{200D}' => Joining::Causing,
+        _ => match EXTENDED_FORMS.iter().find(|&&(letter, _, _)| letter == c) {
+            Some(&(_, _, joining)) => joining,
+            None if bidi::bidi_class(c) == BidiClass::NSM => Joining::Transparent,
+            None => Joining::None,
+        },
+    }
+}
+
+// 字母的某个连写形式，没有表现形式时返回 None
+fn form(c: char, offset: u32) -> Option<char> {
+    let isolated = match c {
+        '\u{0621}'..='\u{064A}' => BASIC_FORMS[c as usize - 0x0621].0,
+        _ => EXTENDED_FORMS.iter().find(|&&(letter, _, _)| letter == c)?.1,
+    };
+    if isolated == 0 {
+        return None;
+    }
+    char::from_u32(isolated + offset)
+}
+
+// 根据前后的字母选择每个阿拉伯字母的连写形式，lam 和 alef 合并为连字
+fn join_arabic(chars: &[char], font: &Font) -> Vec<char> {
+    let joinings: Vec<Joining> = chars.iter().map(|&c| joining(c)).collect();
+    // 跳过组合字符后相邻的字母
+    let neighbour = |range: &mut dyn Iterator<Item = usize>| range.map(|i| joinings[i]).find(|&j| j != Joining::Transparent);
+
+    let mut result = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let current = joinings[i];
+        if !matches!(current, Joining::Right | Joining::Dual) {
+            result.push(c);
+            i += 1;
+            continue;
+        }
+        let previous = neighbour(&mut (0..i).rev());
+        let next = neighbour(&mut (i + 1..chars.len()));
+        let joins_previous = matches!(previous, Some(Joining::Dual | Joining::Causing));
+        let joins_next = current == Joining::Dual && matches!(next, Some(Joining::Right | Joining::Dual | Joining::Causing));
+
+        // lam 之后紧跟 alef 时合并为一个字符，连字只和前面的字母连写
+        if c == LAM {
+            if let Some(&(_, isolated)) = chars.get(i + 1).and_then(|&alef| LAM_ALEF.iter().find(|&&(a, _)| a == alef)) {
+                let ligature = char::from_u32(isolated + if joins_previous { FINAL } else { ISOLATED }).unwrap();
+                if font.has_glyph(ligature) {
+                    result.push(ligature);
+                    i += 2;
+                    continue;
+                }
+            }
+        }
+
+        let offset = match (joins_previous, joins_next) {
+            (true, true) => MEDIAL,
+            (true, false) => FINAL,
+            (false, true) => INITIAL,
+            (false, false) => ISOLATED,
+        };
+        result.push(form(c, offset).filter(|&form| font.has_glyph(form)).unwrap_or(c));
+        i += 1;
+    }
+    result
+}
+
+fn ligatures(chars: &[char], font: &Font) -> Vec<char> {
+    let mut result = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    'chars: while i < chars.len() {
+        for (letters, ligature) in LIGATURES {
+            let len = letters.len();
+            if chars.len() - i >= len && letters.chars().eq(chars[i..i + len].iter().copied()) && font.has_glyph(ligature) {
+                result.push(ligature);
+                i += len;
+                continue 'chars;
+            }
+        }
+        result.push(chars[i]);
+        i += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::font::{testing, Font};
+
+    use super::shape;
+
+    // 包含阿拉伯字母、表现形式和拉丁字母的字体
+    fn arabic_font() -> Font {
+        testing::font("shaping", &[('a', 'z'), ('\u{0600}', '\u{06FF}'), ('\u{FB00}', '\u{FEFF}')], 500)
+    }
+
+    #[test]
+    fn arabic_joining_forms() {
+        let font = arabic_font();
+        // beh、teh、beh：词首、词中、词尾
+        assert_eq!(shape("\u{0628}\u{062A}\u{0628}", false, &font), "\u{FE91}\u{FE98}\u{FE90}");
+        assert_eq!(shape("\u{0628}", false, &font), "\u{FE8F}");
+        // alef 只和前面的字母连写，之后的 beh 是独立形式
+        assert_eq!(shape("\u{0628}\u{0627}\u{0628}", false, &font), "\u{FE91}\u{FE8E}\u{FE8F}");
+        // 组合字符不影响连写
+        assert_eq!(shape("\u{0628}\u{064E}\u{0628}", false, &font), "\u{FE91}\u{064E}\u{FE90}");
+        // tatweel 让前后的字母连写，自己保持不变
+        assert_eq!(shape("\u{0628}\u{0640}\u{0628}", false, &font), "\u{FE91}\u{0640}\u{FE90}");
+        // 波斯语的 peh
+        assert_eq!(shape("\u{067E}\u{067E}", false, &font), "\u{FB58}\u{FB57}");
+    }
+
+    #[test]
+    fn lam_alef_ligature() {
+        let font = arabic_font();
+        assert_eq!(shape("\u{0644}\u{0627}", false, &font), "\u{FEFB}");
+        assert_eq!(shape("\u{0628}\u{0644}\u{0627}", false, &font), "\u{FE91}\u{FEFC}");
+    }
+
+    #[test]
+    fn latin_ligatures() {
+        let font = arabic_font();
+        assert_eq!(shape("office", false, &font), "o\u{FB03}ce");
+        assert_eq!(shape("fluff", false, &font), "\u{FB02}u\u{FB00}");
+    }
+
+    #[test]
+    fn forms_missing_from_the_font_are_not_used() {
+        let font = testing::font("latin", &[('a', 'z')], 500);
+        assert_eq!(shape("\u{0628}\u{062A}\u{0628}", false, &font), "\u{0628}\u{062A}\u{0628}");
+        assert_eq!(shape("office", false, &font), "office");
+    }
+
+    #[test]
+    fn right_to_left_order() {
+        let font = arabic_font();
+        // 从右到左时反转为显示顺序，组合字符跟随前面的字母，括号镜像
+        assert_eq!(shape("\u{0628}\u{064E}\u{0628}", true, &font), "\u{FE90}\u{FE91}\u{064E}");
+        assert_eq!(shape("(\u{0628})", true, &font), "(\u{FE8F})");
+    }
+}